@@ -0,0 +1,149 @@
+use rusqlite::{params, Connection, OptionalExtension, Result};
+
+use crate::backup::{Backup, MistakenWordRecord, PassageRecord, PassageStatsRecord};
+
+/// Read everything worth backing up out of the stats db.
+pub fn retrieve_backup(conn: &Connection) -> Result<Backup, rusqlite::Error> {
+    let mut stmt = conn.prepare("SELECT passage, passage_len FROM passages ORDER BY passage")?;
+    let passages = stmt
+        .query_map(params![], |row| {
+            Ok(PassageRecord {
+                passage: row.get(0)?,
+                passage_len: row.get(1)?,
+            })
+        })?
+        .collect::<Result<Vec<PassageRecord>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT passage, wpm, accuracy, highest_combo, game_mode, when_played_secs, elapsed_millis,
+                raw_wpm, net_wpm, consistency
+            FROM passage_stats
+            ORDER BY when_played_secs ASC, row_id ASC",
+    )?;
+    let passage_stats = stmt
+        .query_map(params![], |row| {
+            Ok(PassageStatsRecord {
+                passage: row.get(0)?,
+                wpm: row.get(1)?,
+                accuracy: row.get(2)?,
+                highest_combo: row.get(3)?,
+                game_mode: row.get(4)?,
+                when_played_secs: row.get(5)?,
+                elapsed_millis: row.get(6)?,
+                raw_wpm: row.get(7)?,
+                net_wpm: row.get(8)?,
+                consistency: row.get(9)?,
+            })
+        })?
+        .collect::<Result<Vec<PassageStatsRecord>>>()?;
+
+    let mut stmt = conn.prepare(
+        "SELECT word, miss_count, correct_streak, last_seen_secs, next_due_secs
+            FROM mistaken_words
+            ORDER BY word",
+    )?;
+    let mistaken_words = stmt
+        .query_map(params![], |row| {
+            Ok(MistakenWordRecord {
+                word: row.get(0)?,
+                miss_count: row.get(1)?,
+                correct_streak: row.get(2)?,
+                last_seen_secs: row.get(3)?,
+                next_due_secs: row.get(4)?,
+            })
+        })?
+        .collect::<Result<Vec<MistakenWordRecord>>>()?;
+
+    Ok(Backup {
+        format_version: crate::backup::FORMAT_VERSION,
+        passages,
+        passage_stats,
+        mistaken_words,
+    })
+}
+
+/// Merge a backup into the stats db.
+/// Races already in the db are skipped, so importing the same backup twice is harmless.
+/// Returns the number of races that were added.
+pub fn store_backup(conn: &mut Connection, backup: &Backup) -> Result<usize, rusqlite::Error> {
+    let tx = conn.transaction()?;
+
+    for passage in backup.passages.iter() {
+        tx.execute(
+            "INSERT OR REPLACE INTO passages (passage, passage_len) VALUES (?1, ?2)",
+            params![passage.passage, passage.passage_len],
+        )?;
+    }
+
+    let mut races_added = 0;
+    for race in backup.passage_stats.iter() {
+        let already_stored = tx
+            .query_row(
+                "SELECT row_id FROM passage_stats
+                    WHERE passage = ?1 AND when_played_secs = ?2 AND game_mode = ?3 AND wpm = ?4",
+                params![
+                    race.passage,
+                    race.when_played_secs,
+                    race.game_mode,
+                    race.wpm
+                ],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?
+            .is_some();
+        if already_stored {
+            continue;
+        }
+
+        tx.execute(
+            "INSERT INTO passage_stats (
+                passage,
+                wpm,
+                accuracy,
+                highest_combo,
+                game_mode,
+                when_played_secs,
+                elapsed_millis,
+                raw_wpm,
+                net_wpm,
+                consistency
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                race.passage,
+                race.wpm,
+                race.accuracy,
+                race.highest_combo,
+                race.game_mode,
+                race.when_played_secs,
+                race.elapsed_millis,
+                race.raw_wpm,
+                race.net_wpm,
+                race.consistency
+            ],
+        )?;
+        races_added += 1;
+    }
+
+    // Words the user is already reviewing keep their schedule
+    for word in backup.mistaken_words.iter() {
+        tx.execute(
+            "INSERT OR IGNORE INTO mistaken_words (
+                word,
+                miss_count,
+                correct_streak,
+                last_seen_secs,
+                next_due_secs
+            ) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                word.word,
+                word.miss_count,
+                word.correct_streak,
+                word.last_seen_secs,
+                word.next_due_secs
+            ],
+        )?;
+    }
+
+    tx.commit()?;
+    Ok(races_added)
+}
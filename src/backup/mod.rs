@@ -0,0 +1,424 @@
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    io::{Error, ErrorKind, Write},
+    path::Path,
+};
+
+pub mod backup_db;
+
+/// Bumped whenever the backup format changes in a way older versions can't read
+pub const FORMAT_VERSION: i64 = 2;
+
+/// Formats race history can be exported to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn values() -> Vec<&'static str> {
+        vec!["json", "csv"]
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassageRecord {
+    pub passage: String,
+    pub passage_len: Option<i64>,
+}
+
+/// A race without its row_id, ids are handed out again when a backup is imported
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PassageStatsRecord {
+    pub passage: String,
+    pub wpm: i64,
+    pub accuracy: f64,
+    pub highest_combo: i64,
+    pub game_mode: i64,
+    pub when_played_secs: i64,
+    pub elapsed_millis: Option<i64>,
+    pub raw_wpm: Option<i64>,
+    pub net_wpm: Option<i64>,
+    pub consistency: Option<f64>,
+}
+
+/// A word the user keeps getting wrong, along with where it is in their review schedule
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "MistakenWordEntry")]
+pub struct MistakenWordRecord {
+    pub word: String,
+    pub miss_count: i64,
+    pub correct_streak: i64,
+    pub last_seen_secs: i64,
+    pub next_due_secs: i64,
+}
+
+impl MistakenWordRecord {
+    /// A word with the same schedule the db gives a newly mistaken word, due straight away
+    pub fn new(word: String) -> Self {
+        MistakenWordRecord {
+            word,
+            miss_count: 1,
+            correct_streak: 0,
+            last_seen_secs: 0,
+            next_due_secs: 0,
+        }
+    }
+}
+
+/// Version 1 backups only kept the word, without its schedule
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MistakenWordEntry {
+    Word(String),
+    Record {
+        word: String,
+        miss_count: Option<i64>,
+        correct_streak: Option<i64>,
+        last_seen_secs: Option<i64>,
+        next_due_secs: Option<i64>,
+    },
+}
+
+impl From<MistakenWordEntry> for MistakenWordRecord {
+    fn from(entry: MistakenWordEntry) -> Self {
+        match entry {
+            MistakenWordEntry::Word(word) => MistakenWordRecord::new(word),
+            MistakenWordEntry::Record {
+                word,
+                miss_count,
+                correct_streak,
+                last_seen_secs,
+                next_due_secs,
+            } => {
+                let defaults = MistakenWordRecord::new(word);
+                MistakenWordRecord {
+                    miss_count: miss_count.unwrap_or(defaults.miss_count),
+                    correct_streak: correct_streak.unwrap_or(defaults.correct_streak),
+                    last_seen_secs: last_seen_secs.unwrap_or(defaults.last_seen_secs),
+                    next_due_secs: next_due_secs.unwrap_or(defaults.next_due_secs),
+                    ..defaults
+                }
+            }
+        }
+    }
+}
+
+/// Everything from the stats db needed to rebuild a user's history on another machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Backup {
+    pub format_version: i64,
+    pub passages: Vec<PassageRecord>,
+    pub passage_stats: Vec<PassageStatsRecord>,
+    pub mistaken_words: Vec<MistakenWordRecord>,
+}
+
+/// CSV only holds a single table, so every table goes in one file and each row says which table
+/// it belongs to. Columns a table doesn't have are left empty.
+/// There's nowhere to put the format version, so CSV backups are always read as the current one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct CsvRow {
+    table: String,
+    passage: Option<String>,
+    passage_len: Option<i64>,
+    wpm: Option<i64>,
+    accuracy: Option<f64>,
+    highest_combo: Option<i64>,
+    game_mode: Option<i64>,
+    when_played_secs: Option<i64>,
+    elapsed_millis: Option<i64>,
+    raw_wpm: Option<i64>,
+    net_wpm: Option<i64>,
+    consistency: Option<f64>,
+    word: Option<String>,
+    miss_count: Option<i64>,
+    correct_streak: Option<i64>,
+    last_seen_secs: Option<i64>,
+    next_due_secs: Option<i64>,
+}
+
+impl CsvRow {
+    fn empty(table: &str) -> Self {
+        CsvRow {
+            table: table.to_owned(),
+            passage: None,
+            passage_len: None,
+            wpm: None,
+            accuracy: None,
+            highest_combo: None,
+            game_mode: None,
+            when_played_secs: None,
+            elapsed_millis: None,
+            raw_wpm: None,
+            net_wpm: None,
+            consistency: None,
+            word: None,
+            miss_count: None,
+            correct_streak: None,
+            last_seen_secs: None,
+            next_due_secs: None,
+        }
+    }
+}
+
+/// Print the user's whole race history to stdout.
+pub fn export(db_path: &Path, format: Format) -> Result<(), Error> {
+    let conn = Connection::open(db_path).map_err(Error::other)?;
+    let backup = backup_db::retrieve_backup(&conn).map_err(Error::other)?;
+
+    let mut out = std::io::stdout().lock();
+    match format {
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &backup)?;
+            writeln!(out)
+        }
+        Format::Csv => write_csv(&backup, &mut out),
+    }
+}
+
+/// Merge an exported race history into the stats db.
+/// The format is picked based on the file extension, or the file contents if the extension
+/// doesn't give it away.
+pub fn import(db_path: &Path, backup_path: &Path) -> Result<(), Error> {
+    let contents = fs::read_to_string(backup_path)?;
+    let format = match backup_path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => Format::Json,
+        Some("csv") => Format::Csv,
+        _ if contents.trim_start().starts_with('{') => Format::Json,
+        _ => Format::Csv,
+    };
+    let backup = match format {
+        Format::Json => {
+            serde_json::from_str(&contents).map_err(|e| Error::new(ErrorKind::InvalidData, e))?
+        }
+        Format::Csv => read_csv(contents.as_bytes())?,
+    };
+    if backup.format_version > FORMAT_VERSION {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            "This backup was made by a newer version of typeracer, upgrade to import it",
+        ));
+    }
+
+    let mut conn = Connection::open(db_path).map_err(Error::other)?;
+    let races_added = backup_db::store_backup(&mut conn, &backup).map_err(Error::other)?;
+    println!(
+        "Imported {} new races ({} already stored), {} passages and {} mistaken words",
+        races_added,
+        backup.passage_stats.len() - races_added,
+        backup.passages.len(),
+        backup.mistaken_words.len()
+    );
+    Ok(())
+}
+
+fn write_csv<W: Write>(backup: &Backup, out: &mut W) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(out);
+
+    for passage in backup.passages.iter() {
+        writer
+            .serialize(CsvRow {
+                passage: Some(passage.passage.clone()),
+                passage_len: passage.passage_len,
+                ..CsvRow::empty("passages")
+            })
+            .map_err(Error::other)?;
+    }
+    for race in backup.passage_stats.iter() {
+        writer
+            .serialize(CsvRow {
+                passage: Some(race.passage.clone()),
+                wpm: Some(race.wpm),
+                accuracy: Some(race.accuracy),
+                highest_combo: Some(race.highest_combo),
+                game_mode: Some(race.game_mode),
+                when_played_secs: Some(race.when_played_secs),
+                elapsed_millis: race.elapsed_millis,
+                raw_wpm: race.raw_wpm,
+                net_wpm: race.net_wpm,
+                consistency: race.consistency,
+                ..CsvRow::empty("passage_stats")
+            })
+            .map_err(Error::other)?;
+    }
+    for word in backup.mistaken_words.iter() {
+        writer
+            .serialize(CsvRow {
+                word: Some(word.word.clone()),
+                miss_count: Some(word.miss_count),
+                correct_streak: Some(word.correct_streak),
+                last_seen_secs: Some(word.last_seen_secs),
+                next_due_secs: Some(word.next_due_secs),
+                ..CsvRow::empty("mistaken_words")
+            })
+            .map_err(Error::other)?;
+    }
+    writer.flush()
+}
+
+fn read_csv<R: std::io::Read>(reader: R) -> Result<Backup, Error> {
+    let mut backup = Backup {
+        format_version: FORMAT_VERSION,
+        passages: vec![],
+        passage_stats: vec![],
+        mistaken_words: vec![],
+    };
+    let bad_row = |table: &str| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Missing a column for a {} row", table),
+        )
+    };
+
+    for row in csv::Reader::from_reader(reader).deserialize() {
+        let row: CsvRow = row.map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        match row.table.as_str() {
+            "passages" => backup.passages.push(PassageRecord {
+                passage: row.passage.ok_or_else(|| bad_row("passages"))?,
+                passage_len: row.passage_len,
+            }),
+            "passage_stats" => backup.passage_stats.push(PassageStatsRecord {
+                passage: row.passage.ok_or_else(|| bad_row("passage_stats"))?,
+                wpm: row.wpm.ok_or_else(|| bad_row("passage_stats"))?,
+                accuracy: row.accuracy.ok_or_else(|| bad_row("passage_stats"))?,
+                highest_combo: row.highest_combo.ok_or_else(|| bad_row("passage_stats"))?,
+                game_mode: row.game_mode.ok_or_else(|| bad_row("passage_stats"))?,
+                when_played_secs: row
+                    .when_played_secs
+                    .ok_or_else(|| bad_row("passage_stats"))?,
+                elapsed_millis: row.elapsed_millis,
+                raw_wpm: row.raw_wpm,
+                net_wpm: row.net_wpm,
+                consistency: row.consistency,
+            }),
+            "mistaken_words" => {
+                // Backups from before the schedule was exported only have the word
+                let defaults =
+                    MistakenWordRecord::new(row.word.ok_or_else(|| bad_row("mistaken_words"))?);
+                backup.mistaken_words.push(MistakenWordRecord {
+                    miss_count: row.miss_count.unwrap_or(defaults.miss_count),
+                    correct_streak: row.correct_streak.unwrap_or(defaults.correct_streak),
+                    last_seen_secs: row.last_seen_secs.unwrap_or(defaults.last_seen_secs),
+                    next_due_secs: row.next_due_secs.unwrap_or(defaults.next_due_secs),
+                    ..defaults
+                })
+            }
+            table => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unknown table in backup: {}", table),
+                ))
+            }
+        }
+    }
+    Ok(backup)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDb;
+
+    fn sample_backup() -> Backup {
+        Backup {
+            format_version: FORMAT_VERSION,
+            passages: vec![PassageRecord {
+                passage: "/default/0022791a-573f-414b-954f-7582f5bcd09e".to_owned(),
+                passage_len: Some(34),
+            }],
+            passage_stats: vec![
+                PassageStatsRecord {
+                    passage: "/default/0022791a-573f-414b-954f-7582f5bcd09e".to_owned(),
+                    wpm: 92,
+                    accuracy: 97.5,
+                    highest_combo: 30,
+                    game_mode: 0,
+                    when_played_secs: 1_600_000_000,
+                    elapsed_millis: None,
+                    raw_wpm: None,
+                    net_wpm: None,
+                    consistency: None,
+                },
+                PassageStatsRecord {
+                    passage: "/default/0022791a-573f-414b-954f-7582f5bcd09e".to_owned(),
+                    wpm: 101,
+                    accuracy: 100.0,
+                    highest_combo: 34,
+                    game_mode: 1,
+                    when_played_secs: 1_700_000_000,
+                    elapsed_millis: Some(4321),
+                    raw_wpm: Some(108),
+                    net_wpm: Some(108),
+                    consistency: Some(81.25),
+                },
+            ],
+            mistaken_words: vec![
+                MistakenWordRecord {
+                    word: "Voltaire, \"the\" best".to_owned(),
+                    miss_count: 4,
+                    correct_streak: 1,
+                    last_seen_secs: 1_700_000_100,
+                    next_due_secs: 1_700_086_500,
+                },
+                MistakenWordRecord::new("enemy".to_owned()),
+            ],
+        }
+    }
+
+    #[test]
+    fn csv_round_trips() {
+        let backup = sample_backup();
+        let mut csv: Vec<u8> = vec![];
+        write_csv(&backup, &mut csv).unwrap();
+
+        assert_eq!(read_csv(&csv[..]).unwrap(), backup);
+    }
+
+    #[test]
+    fn reads_backups_without_review_schedules() {
+        let json = r#"{
+            "format_version": 1,
+            "passages": [],
+            "passage_stats": [],
+            "mistaken_words": ["enemy"]
+        }"#;
+        let backup: Backup = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            backup.mistaken_words,
+            vec![MistakenWordRecord::new("enemy".to_owned())]
+        );
+
+        let csv = "table,passage,passage_len,wpm,accuracy,highest_combo,game_mode,\
+                   when_played_secs,elapsed_millis,raw_wpm,net_wpm,consistency,word\n\
+                   mistaken_words,,,,,,,,,,,,enemy\n";
+        assert_eq!(
+            read_csv(csv.as_bytes()).unwrap().mistaken_words,
+            vec![MistakenWordRecord::new("enemy".to_owned())]
+        );
+    }
+
+    #[test]
+    fn db_round_trips_without_duplicates() {
+        let db = TempDb::new("backup");
+        let mut conn = Connection::open(db.path()).unwrap();
+        let backup = sample_backup();
+
+        assert_eq!(backup_db::store_backup(&mut conn, &backup).unwrap(), 2);
+        // Importing the same races again shouldn't add them twice
+        assert_eq!(backup_db::store_backup(&mut conn, &backup).unwrap(), 0);
+        let exported = backup_db::retrieve_backup(&conn).unwrap();
+
+        assert_eq!(exported, backup);
+    }
+}
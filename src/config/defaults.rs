@@ -1,4 +1,4 @@
-use config::{Combo, Display, TyperacerConfig};
+use config::{CodeSettings, Combo, Display, Goals, TyperacerConfig};
 
 use crate::config;
 
@@ -23,6 +23,10 @@ impl Default for TyperacerConfig {
             combo_config: Combo {
                 combo_trigger: DEFAULT_COMBO_TRIGGER,
             },
+            code_settings: CodeSettings {
+                type_indentation: false,
+            },
+            goals: Goals::default(),
         }
     }
 }
@@ -0,0 +1,187 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+};
+use toml_edit::{value, Array, DocumentMut, InlineTable, Item, Table, Value};
+
+use crate::config::{get_config_file, ExtraRepo};
+
+/// Add an extra repo to the user's config file, leaving the rest of the file as it was.
+pub fn add_extra_repo(repo: &ExtraRepo) -> Result<(), Error> {
+    let config_file = get_config_file();
+    let contents = fs::read_to_string(&config_file)?;
+    fs::write(&config_file, with_extra_repo(&contents, repo)?)
+}
+
+/// Remove an extra repo from the user's config file, leaving the rest of the file as it was.
+pub fn remove_extra_repo(name: &str) -> Result<(), Error> {
+    let config_file = get_config_file();
+    let contents = fs::read_to_string(&config_file)?;
+    fs::write(&config_file, without_extra_repo(&contents, name)?)
+}
+
+fn parse(contents: &str) -> Result<DocumentMut, Error> {
+    contents
+        .parse::<DocumentMut>()
+        .map_err(|e| Error::new(ErrorKind::InvalidInput, format!("{}", e)))
+}
+
+fn not_a_list() -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        "extra_repos in the config file should be a list",
+    )
+}
+
+/// Name of a repo written as `{ name = ... }` in a list
+fn inline_repo_name(repo: &Value) -> Option<&str> {
+    repo.as_inline_table()
+        .and_then(|table| table.get("name"))
+        .and_then(|name| name.as_str())
+}
+
+/// Name of a repo written as an `[[extra_repos]]` table
+fn table_repo_name(repo: &Table) -> Option<&str> {
+    repo.get("name").and_then(|name| name.as_str())
+}
+
+fn with_extra_repo(contents: &str, repo: &ExtraRepo) -> Result<String, Error> {
+    let mut doc = parse(contents)?;
+    if doc.get("extra_repos").is_none() {
+        doc["extra_repos"] = value(Array::new());
+    }
+
+    match &mut doc["extra_repos"] {
+        Item::Value(Value::Array(repos)) => {
+            if repos
+                .iter()
+                .any(|existing| inline_repo_name(existing) == Some(repo.name.as_str()))
+            {
+                return Err(already_configured(&repo.name));
+            }
+
+            let mut entry = InlineTable::new();
+            entry.insert("name", repo.name.as_str().into());
+            entry.insert("url", repo.url.as_str().into());
+            entry.insert("version", repo.version.as_str().into());
+            repos.push(entry);
+            // One repo per line, the same as the example in the docs
+            repos
+                .iter_mut()
+                .for_each(|existing| existing.decor_mut().set_prefix("\n  "));
+            repos.set_trailing("\n");
+            repos.set_trailing_comma(true);
+        }
+        Item::ArrayOfTables(repos) => {
+            if repos
+                .iter()
+                .any(|existing| table_repo_name(existing) == Some(repo.name.as_str()))
+            {
+                return Err(already_configured(&repo.name));
+            }
+
+            let mut entry = Table::new();
+            entry["name"] = value(repo.name.as_str());
+            entry["url"] = value(repo.url.as_str());
+            entry["version"] = value(repo.version.as_str());
+            repos.push(entry);
+        }
+        _ => return Err(not_a_list()),
+    }
+    Ok(doc.to_string())
+}
+
+fn without_extra_repo(contents: &str, name: &str) -> Result<String, Error> {
+    let mut doc = parse(contents)?;
+    let removed = match doc.get_mut("extra_repos") {
+        None => false,
+        Some(Item::Value(Value::Array(repos))) => {
+            let position = repos
+                .iter()
+                .position(|existing| inline_repo_name(existing) == Some(name));
+            position.map(|position| repos.remove(position)).is_some()
+        }
+        Some(Item::ArrayOfTables(repos)) => {
+            let position = repos
+                .iter()
+                .position(|existing| table_repo_name(existing) == Some(name));
+            position.map(|position| repos.remove(position)).is_some()
+        }
+        Some(_) => return Err(not_a_list()),
+    };
+
+    if removed {
+        Ok(doc.to_string())
+    } else {
+        Err(Error::new(
+            ErrorKind::NotFound,
+            format!("No extra repo named {} in the config file", name),
+        ))
+    }
+}
+
+fn already_configured(name: &str) -> Error {
+    Error::new(
+        ErrorKind::AlreadyExists,
+        format!("An extra repo named {} is already in the config file", name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RawTyperacerConfig;
+
+    fn team_repo() -> ExtraRepo {
+        ExtraRepo {
+            name: "team".to_owned(),
+            url: "/mnt/shared/team.tar.gz".to_owned(),
+            version: "3".to_owned(),
+        }
+    }
+
+    fn repo_names(contents: &str) -> Vec<String> {
+        let config: RawTyperacerConfig = toml::from_str(contents).unwrap();
+        config
+            .extra_repos
+            .unwrap_or_default()
+            .into_iter()
+            .map(|repo| repo.name)
+            .collect()
+    }
+
+    #[test]
+    fn adds_to_an_empty_config() {
+        let contents = with_extra_repo("", &team_repo()).unwrap();
+        assert_eq!(repo_names(&contents), vec!["team"]);
+    }
+
+    #[test]
+    fn keeps_the_rest_of_the_file() {
+        let original = "# My config\nhistory_size = 100\n\nextra_repos = [\n  { name = \"legacy\", url = \"https://example.com/legacy.git\", version = \"1.0.0\" },\n]\n\n[goals]\nraces_per_day = 5 # keep me honest\n";
+
+        let added = with_extra_repo(original, &team_repo()).unwrap();
+        assert!(added.contains("# My config"));
+        assert!(added.contains("races_per_day = 5 # keep me honest"));
+        assert_eq!(repo_names(&added), vec!["legacy", "team"]);
+        // Can't add the same one twice
+        assert!(with_extra_repo(&added, &team_repo()).is_err());
+
+        let removed = without_extra_repo(&added, "legacy").unwrap();
+        assert_eq!(repo_names(&removed), vec!["team"]);
+        assert!(removed.contains("races_per_day = 5 # keep me honest"));
+        assert!(without_extra_repo(&removed, "legacy").is_err());
+    }
+
+    #[test]
+    fn works_with_tables_of_repos() {
+        let original = "[[extra_repos]]\nname = \"legacy\"\nurl = \"https://example.com/legacy.git\"\nversion = \"1.0.0\"\n";
+
+        let added = with_extra_repo(original, &team_repo()).unwrap();
+        assert_eq!(repo_names(&added), vec!["legacy", "team"]);
+        assert_eq!(
+            repo_names(&without_extra_repo(&added, "team").unwrap()),
+            vec!["legacy"]
+        );
+    }
+}
@@ -16,6 +16,8 @@ pub struct TyperacerConfig {
     pub extra_repos: Vec<ExtraRepo>,
     pub history_size: usize,
     pub combo_config: Combo,
+    pub code_settings: CodeSettings,
+    pub goals: Goals,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,6 +28,8 @@ pub struct RawTyperacerConfig {
     pub repo_version: Option<String>,
     pub extra_repos: Option<Vec<ExtraRepo>>,
     pub history_size: Option<usize>,
+    pub code_settings: Option<RawCodeSettings>,
+    pub goals: Option<Goals>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -62,33 +66,59 @@ pub struct RawDisplay {
     pub simple_borders: Option<bool>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CodeSettings {
+    pub type_indentation: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RawCodeSettings {
+    pub type_indentation: Option<bool>,
+}
+
+/// What the user is aiming for every day. Any goal left out isn't tracked.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+pub struct Goals {
+    pub races_per_day: Option<usize>,
+    pub minutes_per_day: Option<u64>,
+    /// Reached by finishing at least one race that day at this speed or faster
+    pub target_wpm: Option<u64>,
+}
+
 pub mod defaults;
+pub mod editor;
 mod validator;
 
-pub fn get_config() -> TyperacerConfig {
+/// Read and validate the config file. Nothing is printed, the error says what's wrong with it.
+pub fn load_config() -> Result<TyperacerConfig, Error> {
     match get_config_raw() {
-        Err(parse_err) => {
-            println!(
+        Err(parse_err) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
                 "Error parsing config file at: {:?}\n\n{:#?}\n",
                 get_config_file(),
                 parse_err
-            );
-            println!("Press <ENTER> to continue");
-            user_enter();
-            Default::default()
-        }
+            ),
+        )),
         Ok(v) => match validator::validate_config(v) {
-            Err(validate_err) => {
-                println!("Error validating config file:\n{:#?}", validate_err);
-                println!("Press <ENTER> to continue");
-                user_enter();
-                Default::default()
-            }
-            Ok(cfg) => construct_config(cfg),
+            Err(validate_err) => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Error validating config file:\n{:#?}", validate_err),
+            )),
+            Ok(cfg) => Ok(construct_config(cfg)),
         },
     }
 }
 
+/// Show the user why their config couldn't be loaded and carry on with the defaults once
+/// they've read it.
+pub fn default_after_error(config_err: &Error) -> TyperacerConfig {
+    println!("{}", config_err);
+    println!("Press <ENTER> to continue");
+    user_enter();
+    Default::default()
+}
+
 #[cfg(not(test))]
 fn get_config_raw() -> Result<RawTyperacerConfig, Error> {
     let config_buf = get_config_file();
@@ -121,6 +151,11 @@ fn construct_config(raw_config: RawTyperacerConfig) -> TyperacerConfig {
         history_size: raw_config
             .history_size
             .unwrap_or(default_config.history_size),
+        code_settings: construct_code_settings(
+            raw_config.code_settings,
+            default_config.code_settings,
+        ),
+        goals: raw_config.goals.unwrap_or(default_config.goals),
     }
 }
 
@@ -134,6 +169,20 @@ fn construct_display(display_config: Option<RawDisplay>, default_display: Displa
     }
 }
 
+fn construct_code_settings(
+    code_config: Option<RawCodeSettings>,
+    default_code_settings: CodeSettings,
+) -> CodeSettings {
+    match code_config {
+        None => default_code_settings,
+        Some(c) => CodeSettings {
+            type_indentation: c
+                .type_indentation
+                .unwrap_or(default_code_settings.type_indentation),
+        },
+    }
+}
+
 fn get_config_file() -> PathBuf {
     let mut config_dir = create_config_dir();
     config_dir.push("config.toml");
@@ -177,10 +226,14 @@ mod tests {
     fn test_sane_defaults() {
         // Note that get_config_raw is overloaded for tests to give an
         // empty config file
-        let config = get_config();
+        let config = load_config().unwrap();
         assert!(config.lang_packs.is_none());
         assert_eq!(config.repo, "https://gitlab.com/ttyperacer/lang-packs.git");
         assert_eq!(config.repo_version, "1.0.0");
         assert_eq!(config.history_size, 50);
+        assert!(!config.code_settings.type_indentation);
+        assert!(config.goals.races_per_day.is_none());
+        assert!(config.goals.minutes_per_day.is_none());
+        assert!(config.goals.target_wpm.is_none());
     }
 }
@@ -1,4 +1,4 @@
-use std::io::{Error, ErrorKind};
+use std::io::Error;
 
 use crate::config::RawTyperacerConfig;
 
@@ -7,6 +7,7 @@ use crate::config::RawTyperacerConfig;
 /// user's config.
 pub fn validate_config(config: RawTyperacerConfig) -> Result<RawTyperacerConfig, Error> {
     validate_lang_packs(&config)?;
+    validate_goals(&config)?;
     Ok(config)
 }
 
@@ -19,8 +20,7 @@ fn validate_lang_packs(config: &RawTyperacerConfig) -> Result<(), Error> {
         None => Ok(()),
         Some(x) => {
             if x.blacklisted.is_some() && x.whitelisted.is_some() {
-                Err(Error::new(
-                    ErrorKind::Other,
+                Err(Error::other(
                     "Both blacklist and whitelist cannot be filled out",
                 ))
             } else {
@@ -30,10 +30,25 @@ fn validate_lang_packs(config: &RawTyperacerConfig) -> Result<(), Error> {
     }
 }
 
+/// Validate the goals section, a goal of zero is met before the user starts so it can only be a
+/// mistake.
+fn validate_goals(config: &RawTyperacerConfig) -> Result<(), Error> {
+    match &config.goals {
+        Some(goals)
+            if goals.races_per_day == Some(0)
+                || goals.minutes_per_day == Some(0)
+                || goals.target_wpm == Some(0) =>
+        {
+            Err(Error::other("Goals must be greater than 0"))
+        }
+        _ => Ok(()),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::LangPacks;
+    use crate::config::{Goals, LangPacks};
 
     #[test]
     fn test_empty_config_ok() {
@@ -44,6 +59,8 @@ mod tests {
             repo_version: None,
             extra_repos: None,
             history_size: None,
+            code_settings: None,
+            goals: None,
         })
         .is_ok());
     }
@@ -60,6 +77,8 @@ mod tests {
             repo_version: None,
             extra_repos: None,
             history_size: None,
+            code_settings: None,
+            goals: None,
         })
         .is_err());
     }
@@ -76,6 +95,8 @@ mod tests {
             repo_version: None,
             extra_repos: None,
             history_size: None,
+            code_settings: None,
+            goals: None,
         })
         .is_ok());
 
@@ -89,7 +110,36 @@ mod tests {
             repo_version: None,
             extra_repos: None,
             history_size: None,
+            code_settings: None,
+            goals: None,
         })
         .is_ok());
     }
+
+    #[test]
+    fn test_zero_goals_invalid() {
+        let with_goals = |goals: Goals| RawTyperacerConfig {
+            lang_packs: None,
+            display_settings: None,
+            repo: None,
+            repo_version: None,
+            extra_repos: None,
+            history_size: None,
+            code_settings: None,
+            goals: Some(goals),
+        };
+
+        assert!(validate_config(with_goals(Goals {
+            races_per_day: Some(5),
+            minutes_per_day: None,
+            target_wpm: Some(80),
+        }))
+        .is_ok());
+        assert!(validate_config(with_goals(Goals {
+            races_per_day: None,
+            minutes_per_day: Some(0),
+            target_wpm: None,
+        }))
+        .is_err());
+    }
 }
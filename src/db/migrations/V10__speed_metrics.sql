@@ -0,0 +1,4 @@
+ALTER TABLE passage_stats ADD COLUMN raw_wpm INTEGER;
+ALTER TABLE passage_stats ADD COLUMN net_wpm INTEGER;
+ALTER TABLE passage_stats ADD COLUMN consistency REAL;
+PRAGMA user_version = 10;
@@ -0,0 +1,7 @@
+CREATE TABLE achievements(
+   achievement TEXT PRIMARY KEY NOT NULL,
+   passage_stats_id INTEGER,
+   unlocked_secs INTEGER NOT NULL,
+   FOREIGN KEY(passage_stats_id) REFERENCES passage_stats(row_id)
+);
+PRAGMA user_version = 11;
@@ -0,0 +1,15 @@
+CREATE TABLE passage_index_packs(
+   pack_dir TEXT PRIMARY KEY NOT NULL,
+   version TEXT NOT NULL
+);
+CREATE TABLE passage_index(
+   passage_path TEXT PRIMARY KEY NOT NULL,
+   pack_dir TEXT NOT NULL,
+   quote_dir TEXT NOT NULL,
+   passage_len INTEGER NOT NULL,
+   passage_hash INTEGER NOT NULL,
+   title TEXT NOT NULL,
+   FOREIGN KEY(pack_dir) REFERENCES passage_index_packs(pack_dir)
+);
+CREATE INDEX passage_index_by_quote_dir ON passage_index(quote_dir);
+PRAGMA user_version = 12;
@@ -0,0 +1,2 @@
+INSERT INTO game_mode VALUES (3, "Timed");
+PRAGMA user_version = 4;
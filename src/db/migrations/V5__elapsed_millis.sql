@@ -0,0 +1,2 @@
+ALTER TABLE passage_stats ADD COLUMN elapsed_millis INTEGER;
+PRAGMA user_version = 5;
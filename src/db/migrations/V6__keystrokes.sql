@@ -0,0 +1,13 @@
+CREATE TABLE keystrokes(
+   row_id INTEGER PRIMARY KEY,
+   passage_stats_id INTEGER,
+   offset_millis INTEGER,
+   position INTEGER,
+   expected TEXT,
+   typed TEXT,
+   correct INTEGER,
+   backspace INTEGER,
+   FOREIGN KEY(passage_stats_id) REFERENCES passage_stats(row_id)
+);
+CREATE INDEX keystrokes_by_passage_stats ON keystrokes(passage_stats_id);
+PRAGMA user_version = 6;
@@ -0,0 +1,10 @@
+CREATE TABLE char_errors(
+   expected TEXT,
+   previous TEXT,
+   typed TEXT,
+   game_mode INTEGER,
+   count INTEGER,
+   PRIMARY KEY(expected, previous, typed, game_mode),
+   FOREIGN KEY(game_mode) REFERENCES game_mode(id)
+);
+PRAGMA user_version = 7;
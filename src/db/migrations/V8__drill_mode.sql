@@ -0,0 +1,2 @@
+INSERT INTO game_mode VALUES (4, "Drill");
+PRAGMA user_version = 8;
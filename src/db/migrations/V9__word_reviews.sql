@@ -0,0 +1,6 @@
+ALTER TABLE mistaken_words ADD COLUMN miss_count INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE mistaken_words ADD COLUMN correct_streak INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE mistaken_words ADD COLUMN last_seen_secs INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE mistaken_words ADD COLUMN next_due_secs INTEGER NOT NULL DEFAULT 0;
+CREATE INDEX mistaken_words_by_next_due ON mistaken_words(next_due_secs);
+PRAGMA user_version = 9;
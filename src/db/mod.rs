@@ -12,7 +12,7 @@ mod embedded {
     embed_migrations!("src/db/migrations");
 }
 
-static DB_VERSION: i64 = 3;
+static DB_VERSION: i64 = 12;
 
 /// See if the stats db exists
 pub fn check_stats_db() -> bool {
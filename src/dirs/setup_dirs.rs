@@ -34,6 +34,11 @@ pub fn get_quote_dirs() -> LangPackFolders {
     }
 }
 
+/// Get path to the folder packs made with `typeracer packs create` are written to
+pub fn get_created_packs_dir() -> PathBuf {
+    create_data_dir(Some("created-lang-packs"))
+}
+
 /// Get path to folder database is in
 pub fn get_db_dir() -> PathBuf {
     create_data_dir(Some("stats-db"))
@@ -49,7 +54,7 @@ pub fn get_db_path() -> PathBuf {
 /// making the path and return a canned one for tests.
 pub fn get_quote_dirs() -> LangPackFolders {
     LangPackFolders {
-        main_pack_dir: PathBuf::new().join("/home/darrien/.local/share/typeracer/lang-packs"),
+        main_pack_dir: PathBuf::from("/home/darrien/.local/share/typeracer/lang-packs"),
         ..Default::default()
     }
 }
@@ -0,0 +1,98 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    terminal::Terminal,
+    text::Span,
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table},
+};
+
+use crate::{game::achievements::Achievement, report};
+
+pub fn render<B: Backend>(terminal: &mut Terminal<B>, unlocked: &[(Achievement, i64)]) {
+    let now = report::now_secs();
+    let achievements = Achievement::values();
+
+    terminal
+        .draw(|f| {
+            let padding_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
+
+            let main_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(80),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(padding_layout[1]);
+
+            let rows = achievements.iter().map(|achievement| {
+                match unlocked.iter().find(|(have, _)| have == achievement) {
+                    Some((_, unlocked_secs)) => Row::new(vec![
+                        "✔".to_owned(),
+                        achievement.name().to_owned(),
+                        achievement.description(),
+                        report::time_ago(now, *unlocked_secs),
+                    ])
+                    .style(Style::default().fg(Color::Green)),
+                    None => Row::new(vec![
+                        "".to_owned(),
+                        achievement.name().to_owned(),
+                        achievement.description(),
+                        "locked".to_owned(),
+                    ])
+                    .style(Style::default().fg(Color::Gray)),
+                }
+            });
+
+            f.render_widget(
+                Table::new(rows)
+                    .header(
+                        Row::new(vec!["", "Achievement", "How to unlock", "Unlocked"])
+                            .style(Style::default().add_modifier(Modifier::BOLD)),
+                    )
+                    .block(
+                        Block::default()
+                            .title(format!(
+                                "Achievements - {}/{} unlocked",
+                                unlocked.len(),
+                                achievements.len()
+                            ))
+                            .borders(Borders::ALL)
+                            .border_type(BorderType::Rounded),
+                    )
+                    .widths(
+                        [
+                            Constraint::Length(2),
+                            Constraint::Length(14),
+                            Constraint::Percentage(65),
+                            Constraint::Length(10),
+                        ]
+                        .as_ref(),
+                    ),
+                main_layout[1],
+            );
+
+            f.render_widget(
+                Paragraph::new(Span::raw("^C to go back"))
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::NONE)),
+                main_layout[2],
+            );
+        })
+        .expect("Failed to draw to terminal");
+}
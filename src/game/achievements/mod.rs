@@ -0,0 +1,224 @@
+use crossbeam_channel::Receiver;
+use std::path::Path;
+use std::time::Duration;
+use termion::event::Key;
+use tui::{backend::Backend, terminal::Terminal};
+
+use crate::game::{game_db, GameMode};
+
+mod achievements_render;
+
+/// Passages at least this many characters long are long enough for `Achievement::Flawless`
+pub const LONG_PASSAGE_CHARS: usize = 300;
+
+/// Days in a row the user has to meet their daily goals for `Achievement::Devoted`
+pub const DEVOTED_STREAK_DAYS: usize = 50;
+
+/// How many times longer than `combo_trigger` a combo has to run for `Achievement::ComboMaster`
+pub const COMBO_MASTER_MULTIPLIER: usize = 5;
+
+/// Milestones the user unlocks once and keeps forever
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Achievement {
+    TripleDigits,
+    Flawless,
+    Devoted,
+    Survivor,
+    ComboMaster,
+}
+
+impl Achievement {
+    pub fn values() -> Vec<Achievement> {
+        vec![
+            Achievement::TripleDigits,
+            Achievement::Flawless,
+            Achievement::Devoted,
+            Achievement::Survivor,
+            Achievement::ComboMaster,
+        ]
+    }
+
+    /// What the achievement is stored as in the db, these must never change
+    pub fn id(&self) -> &'static str {
+        match self {
+            Achievement::TripleDigits => "triple_digits",
+            Achievement::Flawless => "flawless",
+            Achievement::Devoted => "devoted",
+            Achievement::Survivor => "survivor",
+            Achievement::ComboMaster => "combo_master",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Achievement> {
+        Achievement::values()
+            .into_iter()
+            .find(|achievement| achievement.id() == id)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::TripleDigits => "Triple digits",
+            Achievement::Flawless => "Flawless",
+            Achievement::Devoted => "Devoted",
+            Achievement::Survivor => "Survivor",
+            Achievement::ComboMaster => "Combo master",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Achievement::TripleDigits => "Finish a race at 100 WPM or faster".to_owned(),
+            Achievement::Flawless => format!(
+                "Type a passage of {}+ characters with 100% accuracy",
+                LONG_PASSAGE_CHARS
+            ),
+            Achievement::Devoted => format!(
+                "Meet your daily goals {} days in a row",
+                DEVOTED_STREAK_DAYS
+            ),
+            Achievement::Survivor => "Make it to the end of a passage in instant death".to_owned(),
+            Achievement::ComboMaster => format!(
+                "Keep a combo going past {}x the combo trigger",
+                COMBO_MASTER_MULTIPLIER
+            ),
+        }
+    }
+}
+
+/// What happened in a race, as far as achievements are concerned
+#[derive(Debug, Clone, Copy)]
+pub struct RaceOutcome {
+    pub game_mode: GameMode,
+    pub wpm: u64,
+    pub accuracy: f64,
+    pub highest_combo: usize,
+    pub passage_chars: usize,
+    /// Set when the race was lost to a mistake in instant death
+    pub failed: bool,
+    /// The user's daily streak counting this race
+    pub streak: usize,
+}
+
+/// Every achievement a race earns, whether or not the user already has it
+pub fn earned(outcome: &RaceOutcome, combo_trigger: usize) -> Vec<Achievement> {
+    Achievement::values()
+        .into_iter()
+        .filter(|achievement| match achievement {
+            Achievement::TripleDigits => !outcome.failed && outcome.wpm >= 100,
+            Achievement::Flawless => {
+                !outcome.failed
+                    && outcome.accuracy >= 100.0
+                    && outcome.passage_chars >= LONG_PASSAGE_CHARS
+            }
+            Achievement::Devoted => outcome.streak >= DEVOTED_STREAK_DAYS,
+            Achievement::Survivor => outcome.game_mode == GameMode::InstantDeath && !outcome.failed,
+            Achievement::ComboMaster => {
+                outcome.highest_combo > combo_trigger * COMBO_MASTER_MULTIPLIER
+            }
+        })
+        .collect()
+}
+
+/// Show every achievement, and when the user unlocked the ones they have.
+pub fn show_achievements<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input_receiver: &Receiver<Key>,
+    db_path: &Path,
+) -> Result<(), rusqlite::Error> {
+    let unlocked = game_db::retrieve_achievements(db_path)?;
+
+    loop {
+        achievements_render::render(terminal, &unlocked);
+
+        // Re-render every so often in case the terminal was resized
+        if let Ok(Key::Ctrl('c')) = input_receiver.recv_timeout(Duration::from_secs(1)) {
+            return Ok(());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome() -> RaceOutcome {
+        RaceOutcome {
+            game_mode: GameMode::Default,
+            wpm: 60,
+            accuracy: 95.0,
+            highest_combo: 20,
+            passage_chars: 100,
+            failed: false,
+            streak: 1,
+        }
+    }
+
+    #[test]
+    fn ids_round_trip() {
+        for achievement in Achievement::values() {
+            assert_eq!(Achievement::from_id(achievement.id()), Some(achievement));
+        }
+        assert_eq!(Achievement::from_id("not_an_achievement"), None);
+    }
+
+    #[test]
+    fn an_average_race_earns_nothing() {
+        assert!(earned(&outcome(), 60).is_empty());
+    }
+
+    #[test]
+    fn races_earn_achievements() {
+        let fast_and_perfect = RaceOutcome {
+            wpm: 100,
+            accuracy: 100.0,
+            passage_chars: LONG_PASSAGE_CHARS,
+            ..outcome()
+        };
+        assert_eq!(
+            earned(&fast_and_perfect, 60),
+            vec![Achievement::TripleDigits, Achievement::Flawless]
+        );
+
+        let long_combo_streak = RaceOutcome {
+            highest_combo: 301,
+            streak: DEVOTED_STREAK_DAYS,
+            ..outcome()
+        };
+        assert_eq!(
+            earned(&long_combo_streak, 60),
+            vec![Achievement::Devoted, Achievement::ComboMaster]
+        );
+        // Exactly five times the trigger isn't past it
+        assert_eq!(
+            earned(
+                &RaceOutcome {
+                    highest_combo: 300,
+                    ..outcome()
+                },
+                60
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn failing_instant_death_earns_nothing() {
+        let instant_death = RaceOutcome {
+            game_mode: GameMode::InstantDeath,
+            wpm: 120,
+            ..outcome()
+        };
+        assert_eq!(
+            earned(&instant_death, 60),
+            vec![Achievement::TripleDigits, Achievement::Survivor]
+        );
+        assert!(earned(
+            &RaceOutcome {
+                failed: true,
+                ..instant_death
+            },
+            60
+        )
+        .is_empty());
+    }
+}
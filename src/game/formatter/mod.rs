@@ -13,6 +13,21 @@ pub struct FormattedTexts<'a> {
     pub input: Vec<Span<'a>>,
     pub error: bool,
     pub complete: bool,
+    /// Index in the passage the ghost of a previous run is on, if it should be shown.
+    pub ghost_idx: Option<usize>,
+}
+
+impl<'a> FormattedTexts<'a> {
+    /// Get the passage with the ghost's letter highlighted on top of the regular formatting.
+    pub fn ghosted_passage(&self) -> Vec<Span<'a>> {
+        let mut passage = self.passage.clone();
+        if let Some(span) = self.ghost_idx.and_then(|idx| passage.get_mut(idx)) {
+            span.style = span
+                .style
+                .patch(Style::default().bg(Color::Magenta).fg(Color::White));
+        }
+        passage
+    }
 }
 
 /// Should be the final formatting call.
@@ -35,6 +50,15 @@ pub fn get_reformatted_failed_texts<'a>(
     get_fully_reformatted_texts(game_mode, words, Color::Red, "FAIL", true)
 }
 
+/// Sets formatted texts to the end of game state for timed mode once the clock runs out.
+/// Only the words the user managed to type before time was up are shown.
+pub fn get_reformatted_timed_out_texts<'a>(
+    game_mode: &GameMode,
+    typed_words: &[&str],
+) -> FormattedTexts<'a> {
+    get_fully_reformatted_texts(game_mode, typed_words, Color::Green, "TIME UP", false)
+}
+
 /// Get fully formatted versions of the passage, and the user's input.
 pub fn get_formatted_texts<'a>(
     game_mode: &GameMode,
@@ -67,6 +91,7 @@ pub fn get_formatted_texts<'a>(
         input: formatted_input,
         error: user_has_err,
         complete: false,
+        ghost_idx: None,
     }
 }
 
@@ -94,6 +119,7 @@ pub fn get_formatted_texts_line_mode<'a>(
         input: formatted_input,
         error: !indexer::check_like_word(current_word, user_input),
         complete: false,
+        ghost_idx: None,
     }
 }
 
@@ -137,6 +163,8 @@ fn get_formatted_words<'a>(
                 .add_modifier(Modifier::ITALIC)
                 .add_modifier(Modifier::DIM);
             '.'
+        } else if *raw_input == '\n' {
+            '↵'
         } else {
             *raw_input
         };
@@ -200,10 +228,10 @@ fn decide_break(
     last_input_char: char,
     new_char: bool,
 ) -> bool {
-    if *game_mode == GameMode::Latin {
-        decide_break_latin(indexable_word, indexable_input, word_dex)
-    } else {
+    if *game_mode == GameMode::NonLatin {
         decide_break_nonlatin(indexable_word, indexable_input, last_input_char, new_char)
+    } else {
+        decide_break_latin(indexable_word, indexable_input, word_dex)
     }
 }
 
@@ -244,6 +272,7 @@ fn get_fully_reformatted_texts<'a>(
         )],
         error: err,
         complete: true,
+        ghost_idx: None,
     }
 }
 
@@ -317,6 +346,50 @@ mod tests {
         assert_eq!(properly_formatted_input, formatted_input);
     }
 
+    #[test]
+    fn test_timed_out_texts_only_show_typed_words() {
+        let typed_words = ["the", "quick"];
+
+        let formatted_texts = get_reformatted_timed_out_texts(&GameMode::Latin, &typed_words);
+
+        assert_eq!(
+            formatted_texts.passage,
+            vec![
+                Span::styled("the ", Style::default().fg(Color::Green)),
+                Span::styled("quick ", Style::default().fg(Color::Green)),
+            ]
+        );
+        assert!(formatted_texts.complete);
+        assert!(!formatted_texts.error);
+    }
+
+    #[test]
+    fn test_ghosted_passage() {
+        let mut formatted_texts = get_reformatted_timed_out_texts(&GameMode::Latin, &["the"]);
+        formatted_texts.passage = vec![
+            Span::styled("t", Style::default().fg(Color::Green)),
+            Span::raw("h"),
+            Span::raw("e"),
+        ];
+
+        // No ghost, no change
+        assert_eq!(formatted_texts.ghosted_passage(), formatted_texts.passage);
+
+        formatted_texts.ghost_idx = Some(1);
+        assert_eq!(
+            formatted_texts.ghosted_passage(),
+            vec![
+                Span::styled("t", Style::default().fg(Color::Green)),
+                Span::styled("h", Style::default().bg(Color::Magenta).fg(Color::White)),
+                Span::raw("e"),
+            ]
+        );
+
+        // A ghost past the end of the passage is just ignored
+        formatted_texts.ghost_idx = Some(10);
+        assert_eq!(formatted_texts.ghosted_passage(), formatted_texts.passage);
+    }
+
     #[test]
     fn test_get_formatted_texts() {
         // Test that words are added in place to a set of formatted texts
@@ -7,11 +7,17 @@ use std::{
     time::{SystemTime, UNIX_EPOCH},
 };
 
-use rand::Rng;
-use rusqlite::{params, types::ToSql, Connection, Result};
+use rusqlite::{params, types::ToSql, Connection, OptionalExtension, Result};
 
 use crate::{
-    db, dirs::setup_dirs::get_quote_dirs, game, passage_controller::PassageInfo, stats::Stats,
+    db,
+    dirs::setup_dirs::get_quote_dirs,
+    game::{
+        self, achievements::Achievement, ghost::Ghost, keystrokes::Keystroke,
+        personal_bests::PersonalBests, replay::RaceSummary,
+    },
+    passage_controller::PassageInfo,
+    stats::{Mistype, Stats},
 };
 
 #[derive(Debug)]
@@ -21,14 +27,17 @@ pub enum DbRecreationError {
     DbNotAFile,
 }
 
+/// Store the stats for a finished game.
+/// Returns the row_id of the stats so more info about the game can be linked to it, or None if
+/// the passage is not one we keep stats for.
 pub fn store_stats(
     db_path: &Path,
     game_stats: &Stats,
     passage_info: &PassageInfo,
     game_mode: game::GameMode,
-) -> Result<(), rusqlite::Error> {
+) -> Result<Option<i64>, rusqlite::Error> {
     if !should_persist(passage_info) {
-        return Ok(());
+        return Ok(None);
     }
 
     let local_path = local_passage_path(passage_info.passage_path.clone());
@@ -49,8 +58,12 @@ pub fn store_stats(
             accuracy,
             highest_combo,
             game_mode,
-            when_played_secs
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            when_played_secs,
+            elapsed_millis,
+            raw_wpm,
+            net_wpm,
+            consistency
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
         params![
             local_path,
             ToSql::to_sql(&i64::try_from(game_stats.get_wpm()).unwrap())?,
@@ -65,66 +78,474 @@ pub fn store_stats(
                         .as_secs()
                 )
                 .expect("Failed play_time_secs conversion to sqlite type")
-            )?
+            )?,
+            ToSql::to_sql(&i64::try_from(game_stats.get_elapsed_millis()).unwrap())?,
+            ToSql::to_sql(&i64::try_from(game_stats.get_raw_wpm()).unwrap())?,
+            ToSql::to_sql(&i64::try_from(game_stats.get_net_wpm()).unwrap())?,
+            game_stats.get_consistency()
         ],
     )?;
 
-    Ok(())
+    Ok(Some(conn.last_insert_rowid()))
+}
+
+/// Store every key the user pressed during a game, linked to the stats of that game.
+pub fn store_keystrokes(
+    db_path: &Path,
+    passage_stats_id: i64,
+    keystrokes: &[Keystroke],
+) -> Result<(), rusqlite::Error> {
+    let mut conn = Connection::open(db_path)?;
+    let tx = conn.transaction()?;
+
+    for keystroke in keystrokes {
+        tx.execute(
+            "INSERT INTO keystrokes (
+                passage_stats_id,
+                offset_millis,
+                position,
+                expected,
+                typed,
+                correct,
+                backspace
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                passage_stats_id,
+                ToSql::to_sql(&i64::try_from(keystroke.offset_millis).unwrap())?,
+                ToSql::to_sql(&i64::try_from(keystroke.position).unwrap())?,
+                keystroke.expected.map(String::from),
+                keystroke.typed.to_string(),
+                keystroke.correct,
+                keystroke.backspace,
+            ],
+        )?;
+    }
+
+    tx.commit()
 }
 
+/// Remember the words the user missed so they come up in training mode.
+/// Missing a word again starts its reviews over, so it's due straight away.
 pub fn store_mistaken_words(
     db_path: &Path,
     mistaken_words: &HashSet<String>,
 ) -> Result<(), rusqlite::Error> {
     let mut conn = Connection::open(db_path)?;
     let tx = conn.transaction()?;
+    let now = now_secs();
 
     for word in mistaken_words {
         tx.execute(
-            "INSERT OR REPLACE INTO mistaken_words (word) VALUES (?1)",
-            params![word,],
+            "INSERT INTO mistaken_words
+                (word, miss_count, correct_streak, last_seen_secs, next_due_secs)
+                VALUES (?1, 1, 0, ?2, ?2)
+                ON CONFLICT(word) DO UPDATE SET
+                    miss_count = miss_count + 1,
+                    correct_streak = 0,
+                    last_seen_secs = excluded.last_seen_secs,
+                    next_due_secs = excluded.next_due_secs",
+            params![word, now],
         )?;
     }
 
     tx.commit()
 }
 
-/// This function is intended to run at the end of a training mode game, to give a chance to
-/// remove some words from the mistaken_words table in the database.
+/// Add the characters missed during a race to the running totals of which keys the user misses.
+/// A missing previous character is stored as an empty string so it still counts towards the
+/// primary key.
+pub fn store_mistypes(
+    db_path: &Path,
+    mistypes: &[Mistype],
+    game_mode: game::GameMode,
+) -> Result<(), rusqlite::Error> {
+    let mut conn = Connection::open(db_path)?;
+    let tx = conn.transaction()?;
+
+    for mistype in mistypes {
+        tx.execute(
+            "INSERT INTO char_errors (expected, previous, typed, game_mode, count)
+                VALUES (?1, ?2, ?3, ?4, 1)
+                ON CONFLICT(expected, previous, typed, game_mode)
+                DO UPDATE SET count = count + 1",
+            params![
+                mistype.expected.to_string(),
+                mistype.previous.map_or(String::new(), |c| c.to_string()),
+                mistype.typed.to_string(),
+                i64::from(game_mode),
+            ],
+        )?;
+    }
+
+    tx.commit()
+}
+
+/// The user's average wpm across every race they've played in a game mode, None if they haven't
+/// played it yet.
+pub fn retrieve_average_wpm(
+    db_path: &Path,
+    game_mode: game::GameMode,
+) -> Result<Option<u64>, rusqlite::Error> {
+    let conn = Connection::open(db_path)?;
+    let average: Option<f64> = conn.query_row(
+        "SELECT AVG(wpm) FROM passage_stats WHERE game_mode = ?1",
+        params![i64::from(game_mode)],
+        |row| row.get(0),
+    )?;
+    Ok(average.map(|wpm| wpm.round() as u64))
+}
+
+/// The user's fastest wpm in a game mode overall, in the pack of a passage and on the passage
+/// itself. Passages we don't keep stats for have no personal bests.
+pub fn retrieve_personal_bests(
+    db_path: &Path,
+    passage_info: &PassageInfo,
+    game_mode: game::GameMode,
+) -> Result<PersonalBests, rusqlite::Error> {
+    if !should_persist(passage_info) {
+        return Ok(PersonalBests::default());
+    }
+
+    let local_path = local_passage_path(passage_info.passage_path.clone());
+    let conn = Connection::open(db_path)?;
+    let (overall, pack, passage): (Option<i64>, Option<i64>, Option<i64>) = conn.query_row(
+        "SELECT MAX(wpm),
+                MAX(CASE WHEN substr(passage, 1, length(?2)) = ?2 THEN wpm END),
+                MAX(CASE WHEN passage = ?3 THEN wpm END)
+            FROM passage_stats
+            WHERE game_mode = ?1",
+        params![i64::from(game_mode), pack_prefix(&local_path), local_path],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let as_wpm = |wpm: Option<i64>| wpm.map(|wpm| u64::try_from(wpm).unwrap_or(0));
+    Ok(PersonalBests {
+        overall: as_wpm(overall),
+        pack: as_wpm(pack),
+        passage: as_wpm(passage),
+    })
+}
+
+/// The fastest `limit` runs of a passage in any game mode, fastest first.
+pub fn retrieve_leaderboard(
+    db_path: &Path,
+    passage_info: &PassageInfo,
+    limit: usize,
+) -> Result<Vec<RaceSummary>, rusqlite::Error> {
+    if !should_persist(passage_info) {
+        return Ok(vec![]);
+    }
+
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE passage_stats.passage = ?1
+            ORDER BY passage_stats.wpm DESC, passage_stats.when_played_secs ASC
+            LIMIT ?2",
+        RACE_SUMMARY_QUERY
+    ))?;
+
+    let races = stmt.query_map(
+        params![
+            local_passage_path(passage_info.passage_path.clone()),
+            i64::try_from(limit).unwrap_or(i64::MAX)
+        ],
+        as_race_summary,
+    )?;
+
+    races.collect()
+}
+
+/// Unlock achievements earned in a race, linked to the stats of that race.
+/// Returns the ones the user didn't already have.
+pub fn store_achievements(
+    db_path: &Path,
+    passage_stats_id: i64,
+    achievements: &[Achievement],
+) -> Result<Vec<Achievement>, rusqlite::Error> {
+    let mut conn = Connection::open(db_path)?;
+    let tx = conn.transaction()?;
+    let mut unlocked = vec![];
+    {
+        let mut stmt = tx.prepare(
+            "INSERT OR IGNORE INTO achievements (achievement, passage_stats_id, unlocked_secs)
+                VALUES (?1, ?2, ?3)",
+        )?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
+        for achievement in achievements {
+            if stmt.execute(params![achievement.id(), passage_stats_id, now])? > 0 {
+                unlocked.push(*achievement);
+            }
+        }
+    }
+    tx.commit()?;
+    Ok(unlocked)
+}
+
+/// Every achievement the user has unlocked along with when they unlocked it, oldest first
+pub fn retrieve_achievements(db_path: &Path) -> Result<Vec<(Achievement, i64)>, rusqlite::Error> {
+    let conn = Connection::open(db_path)?;
+    let mut stmt = conn.prepare(
+        "SELECT achievement, unlocked_secs FROM achievements ORDER BY unlocked_secs ASC, rowid ASC",
+    )?;
+    let achievements = stmt.query_map(params![], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+    })?;
+
+    // Achievements from newer versions of typeracer are skipped
+    Ok(achievements
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .filter_map(|(id, unlocked_secs)| {
+            Achievement::from_id(&id).map(|achievement| (achievement, unlocked_secs))
+        })
+        .collect())
+}
+
+/// The user's average wpm over their last `races` races in the same pack as a passage, None if
+/// they haven't played the pack in this game mode yet.
+pub fn retrieve_pack_average(
+    db_path: &Path,
+    passage_info: &PassageInfo,
+    game_mode: game::GameMode,
+    races: usize,
+) -> Result<Option<f64>, rusqlite::Error> {
+    if !should_persist(passage_info) {
+        return Ok(None);
+    }
+
+    let pack = pack_prefix(&local_passage_path(passage_info.passage_path.clone()));
+    let conn = Connection::open(db_path)?;
+    conn.query_row(
+        "SELECT AVG(wpm) FROM (
+            SELECT wpm FROM passage_stats
+                WHERE substr(passage, 1, length(?1)) = ?1
+                AND game_mode = ?2
+                ORDER BY when_played_secs DESC, row_id DESC
+                LIMIT ?3
+        )",
+        params![pack, i64::from(game_mode), races as i64],
+        |row| row.get(0),
+    )
+}
+
+/// The name of the pack a passage came from, None for passages that don't come from a pack, like
+/// timed, training and drill races.
+pub fn pack_name(passage_path: &str) -> Option<String> {
+    Path::new(passage_path)
+        .parent()
+        .and_then(|pack_dir| pack_dir.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+/// What every passage in the same pack as this one starts with. Passages are stored in their
+/// pack's directory, and the passages that aren't files are each a pack of their own.
+fn pack_prefix(local_path: &str) -> String {
+    match local_path.rfind('/') {
+        Some(idx) => local_path[..=idx].to_owned(),
+        None => local_path.to_owned(),
+    }
+}
+
+/// Get the user's fastest run of a passage in the given game mode as a ghost to race against.
+/// Returns None if the user has no run of the passage with keystrokes to follow.
+pub fn retrieve_ghost(
+    db_path: &Path,
+    passage_info: &PassageInfo,
+    game_mode: game::GameMode,
+) -> Result<Option<Ghost>, rusqlite::Error> {
+    if !should_persist(passage_info) {
+        return Ok(None);
+    }
+
+    let conn = Connection::open(db_path)?;
+    let best_run: Option<(i64, i64)> = conn
+        .query_row(
+            "SELECT row_id, wpm
+                FROM passage_stats
+                WHERE passage = ?1
+                AND game_mode = ?2
+                AND row_id IN (SELECT passage_stats_id FROM keystrokes)
+                ORDER BY wpm DESC
+                LIMIT 1",
+            params![
+                local_passage_path(passage_info.passage_path.clone()),
+                i64::from(game_mode)
+            ],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .optional()?;
+
+    match best_run {
+        Some((passage_stats_id, wpm)) => Ok(Some(Ghost::from_keystrokes(
+            u64::try_from(wpm).unwrap_or(0),
+            &retrieve_keystrokes(&conn, passage_stats_id)?,
+        ))),
+        None => Ok(None),
+    }
+}
+
+/// Get all keystrokes stored for a game, in the order they were typed.
+pub fn retrieve_keystrokes(
+    conn: &Connection,
+    passage_stats_id: i64,
+) -> Result<Vec<Keystroke>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT offset_millis, position, expected, typed, correct, backspace
+            FROM keystrokes
+            WHERE passage_stats_id = ?1
+            ORDER BY row_id ASC",
+    )?;
+
+    let keystrokes = stmt.query_map(params![passage_stats_id], |row| {
+        let offset_millis: i64 = row.get(0)?;
+        let position: i64 = row.get(1)?;
+        let expected: Option<String> = row.get(2)?;
+        let typed: String = row.get(3)?;
+        Ok(Keystroke {
+            offset_millis: u64::try_from(offset_millis).unwrap_or(0),
+            position: usize::try_from(position).unwrap_or(0),
+            expected: expected.and_then(|expected| expected.chars().next()),
+            typed: typed.chars().next().unwrap_or(' '),
+            correct: row.get(4)?,
+            backspace: row.get(5)?,
+        })
+    })?;
+
+    keystrokes.collect()
+}
+
+/// How long to wait before reviewing a word again after typing it right this many times in a
+/// row, each review pushes the next one further out.
+const REVIEW_INTERVALS_SECS: [i64; 6] = [
+    10 * 60,
+    60 * 60,
+    24 * 60 * 60,
+    3 * 24 * 60 * 60,
+    7 * 24 * 60 * 60,
+    21 * 24 * 60 * 60,
+];
+
+/// Seconds until a word with the given streak of correct reviews is due again.
+/// None once the user has typed it right enough times in a row to count as learnt.
+fn next_review_secs(correct_streak: i64) -> Option<i64> {
+    usize::try_from(correct_streak - 1)
+        .ok()
+        .and_then(|idx| REVIEW_INTERVALS_SECS.get(idx).copied())
+}
+
+/// The most recent races that can be replayed, newest first.
+/// Races played before keystrokes were logged have nothing to replay, so they're left out.
+pub fn retrieve_replayable_races(
+    conn: &Connection,
+    limit: usize,
+) -> Result<Vec<RaceSummary>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "{} WHERE EXISTS (
+                SELECT 1 FROM keystrokes WHERE keystrokes.passage_stats_id = passage_stats.row_id
+            )
+            ORDER BY passage_stats.row_id DESC
+            LIMIT ?1",
+        RACE_SUMMARY_QUERY
+    ))?;
+
+    let races = stmt.query_map(
+        params![i64::try_from(limit).unwrap_or(i64::MAX)],
+        as_race_summary,
+    )?;
+
+    races.collect()
+}
+
+/// Get a single race by its id, None if there's no such race.
+pub fn retrieve_race_summary(
+    conn: &Connection,
+    passage_stats_id: i64,
+) -> Result<Option<RaceSummary>, rusqlite::Error> {
+    conn.query_row(
+        &format!("{} WHERE passage_stats.row_id = ?1", RACE_SUMMARY_QUERY),
+        params![passage_stats_id],
+        as_race_summary,
+    )
+    .optional()
+}
+
+const RACE_SUMMARY_QUERY: &str = "SELECT passage_stats.row_id,
+        passage_stats.passage,
+        passages.passage_len,
+        passage_stats.wpm,
+        passage_stats.accuracy,
+        passage_stats.game_mode,
+        passage_stats.when_played_secs
+    FROM passage_stats
+    LEFT JOIN passages ON passages.passage = passage_stats.passage";
+
+fn as_race_summary(row: &rusqlite::Row) -> Result<RaceSummary, rusqlite::Error> {
+    Ok(RaceSummary {
+        id: row.get(0)?,
+        passage_path: row.get(1)?,
+        passage_len: row.get(2)?,
+        wpm: row.get(3)?,
+        accuracy: row.get(4)?,
+        game_mode: game::GameMode::from(row.get::<_, i64>(5)?),
+        when_played_secs: row.get(6)?,
+    })
+}
+
+/// This function is intended to run at the end of a training mode game, to schedule the next
+/// review of each mistaken word in the passage.
 ///
-/// We do this by seeing if words in the typed passage also appear in the mistaken_words set
-/// for that passage (words the user made an error on). If a word was typed incorrectly, it doesn't
-/// get a chance to be removed from the mistaken_words table. Otherwise, we 'roll a dice' to
-/// determine if a correctly typed word should be removed.
-pub fn roll_to_delete_mistaken_words_typed_correctly(
+/// Words the user made an error on this time were already made due again when they were stored.
+/// Words typed correctly extend their streak and are put off for longer each time, until the
+/// streak is long enough that the word is learnt and removed from the mistaken_words table.
+pub fn review_mistaken_words_typed_correctly(
     db_path: &Path,
     words: &[&str],
     mistaken_words: &HashSet<String>,
 ) -> Result<(), rusqlite::Error> {
     let mut conn = Connection::open(db_path)?;
-    let mut rng = rand::thread_rng();
+    let now = now_secs();
 
     let tx = conn.transaction()?;
 
     for word in words {
-        match mistaken_words.get(&word.to_string()) {
-            Some(_a) => (),
-            _ => {
-                let random_val: f32 = rng.gen();
-                if random_val < 0.33 {
-                    // 1/3 chance of removing the word from the db
-                    tx.execute(
-                        "DELETE FROM mistaken_words where word = (?1)",
-                        params![word,],
-                    )?;
-                }
-            }
+        if mistaken_words.contains(*word) {
+            continue;
         }
+        let correct_streak: Option<i64> = tx
+            .query_row(
+                "SELECT correct_streak FROM mistaken_words WHERE word = ?1",
+                params![word],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let correct_streak = match correct_streak {
+            Some(streak) => streak + 1,
+            None => continue, // Not a word the user has had trouble with
+        };
+
+        match next_review_secs(correct_streak) {
+            Some(wait_secs) => tx.execute(
+                "UPDATE mistaken_words
+                    SET correct_streak = ?2, last_seen_secs = ?3, next_due_secs = ?4
+                    WHERE word = ?1",
+                params![word, correct_streak, now, now + wait_secs],
+            )?,
+            None => tx.execute("DELETE FROM mistaken_words WHERE word = ?1", params![word])?,
+        };
     }
 
     tx.commit()
 }
 
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as i64
+}
+
 /// Determines if we should persist data for the passage or not
 /// user given passages vary in length and content, so we do not want to persist
 /// any data about them.
@@ -188,29 +609,200 @@ fn rebuild_stats_db(db_path: &Path) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_utils::TempDb;
 
     #[test]
     fn properly_trims_to_local_path() {
         let quote_dir = get_quote_dirs().main_pack_dir;
 
         assert_eq!(
-            local_passage_path(
-                quote_dir
-                    .join("/default/b7448c1c-c70b-4183-86f9-94049376926e")
-                    .to_string_lossy()
-                    .into_owned()
-            ),
+            local_passage_path(format!(
+                "{}/default/b7448c1c-c70b-4183-86f9-94049376926e",
+                quote_dir.to_string_lossy()
+            )),
             "/default/b7448c1c-c70b-4183-86f9-94049376926e"
         );
 
         assert_eq!(
-            local_passage_path(
-                quote_dir
-                    .join("/default/itsnotover/broooooooo/extrapaths")
-                    .to_string_lossy()
-                    .into_owned()
-            ),
+            local_passage_path(format!(
+                "{}/default/itsnotover/broooooooo/extrapaths",
+                quote_dir.to_string_lossy()
+            )),
             "/default/itsnotover/broooooooo/extrapaths"
         );
     }
+
+    #[test]
+    fn mistypes_add_up_across_races() {
+        let db = TempDb::new("mistypes");
+        let db_path = db.path();
+        let mistypes = [
+            Mistype {
+                expected: 'e',
+                previous: Some('h'),
+                typed: 'r',
+            },
+            Mistype {
+                expected: 't',
+                previous: None,
+                typed: 'r',
+            },
+        ];
+
+        store_mistypes(&db_path, &mistypes, game::GameMode::Default).unwrap();
+        store_mistypes(&db_path, &mistypes[..1], game::GameMode::Default).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+        let counts: Vec<(String, String, i64)> = conn
+            .prepare("SELECT expected, previous, count FROM char_errors ORDER BY expected")
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+
+        assert_eq!(
+            counts,
+            vec![
+                ("e".to_owned(), "h".to_owned(), 2),
+                ("t".to_owned(), "".to_owned(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn pack_stats_only_count_races_in_the_pack() {
+        let db = TempDb::new("pack-average");
+        let db_path = db.path();
+        let conn = Connection::open(&db_path).unwrap();
+        for (passage, wpm, when_played_secs) in [
+            ("/default/a", 10, 1),
+            ("/default/b", 60, 2),
+            ("/default/a", 80, 3),
+            ("/default_extra/a", 500, 4),
+            ("TIMED_MODE_PATH", 40, 5),
+        ] {
+            conn.execute(
+                "INSERT OR IGNORE INTO passages (passage, passage_len) VALUES (?1, 10)",
+                params![passage],
+            )
+            .unwrap();
+            conn.execute(
+                "INSERT INTO passage_stats (passage, wpm, accuracy, highest_combo, game_mode, when_played_secs)
+                    VALUES (?1, ?2, 100.0, 10, 0, ?3)",
+                params![passage, wpm, when_played_secs],
+            )
+            .unwrap();
+        }
+        let passage = |passage_path: &str| PassageInfo {
+            passage: "the cat".to_owned(),
+            title: "cat".to_owned(),
+            passage_path: passage_path.to_owned(),
+        };
+
+        let pack_average = |passage_path: &str, races: usize| {
+            retrieve_pack_average(
+                &db_path,
+                &passage(passage_path),
+                game::GameMode::Default,
+                races,
+            )
+            .unwrap()
+        };
+        assert_eq!(pack_average("/default/c", 10), Some(50.0));
+        assert_eq!(pack_average("/default/c", 2), Some(70.0));
+        assert_eq!(pack_average("TIMED_MODE_PATH", 10), Some(40.0));
+        assert_eq!(pack_average("/nothing/here", 10), None);
+        assert_eq!(
+            retrieve_personal_bests(&db_path, &passage("/default/c"), game::GameMode::Default)
+                .unwrap(),
+            PersonalBests {
+                overall: Some(500),
+                pack: Some(80),
+                passage: None,
+            }
+        );
+        assert_eq!(
+            retrieve_leaderboard(&db_path, &passage("/default/a"), 10)
+                .unwrap()
+                .iter()
+                .map(|race| race.wpm)
+                .collect::<Vec<_>>(),
+            vec![80, 10]
+        );
+
+        assert_eq!(pack_name("/default/a").as_deref(), Some("default"));
+        assert_eq!(pack_name("TIMED_MODE_PATH"), None);
+    }
+
+    #[test]
+    fn achievements_only_unlock_once() {
+        let db = TempDb::new("achievements");
+        let db_path = db.path();
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "INSERT INTO passages (passage, passage_len) VALUES ('/default/a', 2);
+            INSERT INTO passage_stats (row_id, passage, game_mode) VALUES (1, '/default/a', 0),
+                                                                      (2, '/default/a', 0);",
+        )
+        .unwrap();
+
+        assert_eq!(
+            store_achievements(&db_path, 1, &[Achievement::Survivor]).unwrap(),
+            vec![Achievement::Survivor]
+        );
+        assert_eq!(
+            store_achievements(&db_path, 2, &[Achievement::Survivor, Achievement::Flawless])
+                .unwrap(),
+            vec![Achievement::Flawless]
+        );
+        let unlocked: Vec<Achievement> = retrieve_achievements(&db_path)
+            .unwrap()
+            .into_iter()
+            .map(|(achievement, _)| achievement)
+            .collect();
+
+        assert_eq!(unlocked, vec![Achievement::Survivor, Achievement::Flawless]);
+    }
+
+    #[test]
+    fn reviews_get_further_apart_until_learnt() {
+        assert_eq!(next_review_secs(1), Some(10 * 60));
+        assert_eq!(next_review_secs(2), Some(60 * 60));
+        assert_eq!(next_review_secs(6), Some(21 * 24 * 60 * 60));
+        assert_eq!(next_review_secs(7), None);
+    }
+
+    #[test]
+    fn missing_a_word_resets_its_streak() {
+        let db = TempDb::new("reviews");
+        let db_path = db.path();
+        let missed = HashSet::from(["enemy".to_owned()]);
+        let review = |conn: &Connection| -> (i64, i64, bool) {
+            conn.query_row(
+                "SELECT miss_count, correct_streak, next_due_secs > last_seen_secs
+                    FROM mistaken_words WHERE word = 'enemy'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap()
+        };
+
+        store_mistaken_words(&db_path, &missed).unwrap();
+        review_mistaken_words_typed_correctly(&db_path, &["enemy"], &HashSet::new()).unwrap();
+        review_mistaken_words_typed_correctly(&db_path, &["enemy"], &HashSet::new()).unwrap();
+        let conn = Connection::open(&db_path).unwrap();
+        let after_reviews = review(&conn);
+        store_mistaken_words(&db_path, &missed).unwrap();
+        let after_miss = review(&conn);
+        for _ in 0..REVIEW_INTERVALS_SECS.len() + 1 {
+            review_mistaken_words_typed_correctly(&db_path, &["enemy"], &HashSet::new()).unwrap();
+        }
+        let remaining: i64 = conn
+            .query_row("SELECT COUNT(*) FROM mistaken_words", [], |row| row.get(0))
+            .unwrap();
+
+        assert_eq!(after_reviews, (1, 2, true));
+        assert_eq!(after_miss, (2, 0, false));
+        assert_eq!(remaining, 0);
+    }
 }
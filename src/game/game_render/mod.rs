@@ -4,18 +4,21 @@ use tui::{
     style::Color,
     style::{Modifier, Style},
     terminal::Terminal,
-    text::{Spans, Text},
-    widgets::{Block, BorderType, Borders, Paragraph, Row, Table, Wrap},
+    text::{Span, Spans, Text},
+    widgets::{Block, BorderType, Borders, LineGauge, Paragraph, Row, Table, Wrap},
 };
 
 use crate::{
     config::TyperacerConfig,
-    game::{formatter::FormattedTexts, GameMode},
+    game::{formatter::FormattedTexts, personal_bests::Record, word_processing, GameMode},
+    multiplayer::PlayerProgress,
     stats,
 };
 use std::collections::HashSet;
 
 mod styles;
+pub mod toast;
+mod wpm_sparkline;
 
 #[derive(Clone, Debug)]
 pub struct GameState<'a> {
@@ -23,7 +26,14 @@ pub struct GameState<'a> {
     pub user_input: &'a str,
     pub stats: &'a stats::Stats,
     pub title: &'a str,
+    /// WPM of the previous run being raced against, if there is one
+    pub ghost_wpm: Option<u64>,
+    /// The user's average WPM in this game mode, None before their first race in it
+    pub average_wpm: Option<u64>,
+    /// The personal best the user is beating, shown as a banner over the passage
+    pub new_record: Option<Record>,
     pub game_mode: GameMode,
+    pub text_mode: word_processing::GameMode,
     pub config: &'a TyperacerConfig,
     // For debug
     pub debug_enabled: bool,
@@ -32,11 +42,17 @@ pub struct GameState<'a> {
     pub current_word: &'a str,
     pub mistaken_words: &'a HashSet<String>,
     pub complete: bool,
+    /// Seconds left in the race, only set in timed mode
+    pub time_left: Option<u64>,
+    /// Everyone in a multiplayer race, empty when racing alone
+    pub racers: &'a [PlayerProgress],
+    /// Shown under the passage in place of the usual game shortcuts
+    pub shortcuts: Option<&'a str>,
 }
 
 impl<'a> GameState<'a> {
     fn get_debug_output(&self) -> String {
-        format!("Running with options:\n Legacy WPM: {}, word_idx: {},  start: {}\nUser has err: {}, game mode: {}, num words: {}, complete: {}\npassage_path: {}\ncurrent_word: {}\nmistaken_words: {:?}\nuser_input: {}",
+        format!("Running with options:\n Legacy WPM: {}, word_idx: {},  start: {:?}\nUser has err: {}, game mode: {}, num words: {}, complete: {}\npassage_path: {}\ncurrent_word: {}\nmistaken_words: {:?}\nuser_input: {}",
             self.stats.get_legacy_wpm(),
             self.word_idx,
             self.stats.get_start_time(),
@@ -70,6 +86,28 @@ fn get_border_style(game_state: &GameState, modifiers: &[Modifier]) -> Style {
     }
 }
 
+/// Break a passage up into its lines, marking where each line ends so the user knows to hit
+/// enter there.
+fn get_code_lines(passage: Vec<Span>) -> Text {
+    let mut lines: Vec<Spans> = vec![];
+    let mut line: Vec<Span> = vec![];
+    for span in passage {
+        let mut parts = span.content.split('\n');
+        if let Some(first_part) = parts.next().filter(|part| !part.is_empty()) {
+            line.push(Span::styled(first_part.to_owned(), span.style));
+        }
+        for part in parts {
+            line.push(Span::styled("↵", span.style));
+            lines.push(Spans::from(std::mem::take(&mut line)));
+            if !part.is_empty() {
+                line.push(Span::styled(part.to_owned(), span.style));
+            }
+        }
+    }
+    lines.push(Spans::from(line));
+    Text::from(lines)
+}
+
 pub fn render<B: Backend>(
     terminal: &mut Terminal<B>,
     game_state: GameState,
@@ -119,16 +157,58 @@ pub fn render<B: Backend>(
                                 .alignment(Alignment::Left),
                             chunks[1],
                         );
+                    } else if !game_state.racers.is_empty() {
+                        let race_block = Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(get_border_style(&game_state, &[]))
+                            .border_type(BorderType::Rounded)
+                            .title("Race");
+                        let racer_chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints(
+                                game_state.racers.iter().map(|_| Constraint::Length(1)).collect::<Vec<_>>()
+                            )
+                            .split(race_block.inner(chunks[1]));
+                        f.render_widget(race_block, chunks[1]);
+                        for (racer, racer_chunk) in game_state.racers.iter().zip(racer_chunks) {
+                            let color = if racer.finished { Color::Green } else { Color::Blue };
+                            f.render_widget(
+                                LineGauge::default()
+                                    .gauge_style(Style::default().fg(color))
+                                    .label(format!("{} {} WPM", racer.name, racer.wpm))
+                                    .ratio(f64::from(racer.percent.min(100)) / 100.0),
+                                racer_chunk,
+                            );
+                        }
                     }
                     let passage_block = Block::default()
                         .borders(Borders::ALL)
                         .border_type(BorderType::Rounded)
                         .border_style(get_border_style(&game_state, &[]));
 
+                    let mut passage_title = vec![Span::raw(match game_state.ghost_wpm {
+                        Some(wpm) => format!("{} - racing your best: {} WPM", game_state.title, wpm),
+                        None => game_state.title.to_owned(),
+                    })];
+                    if let Some(record) = game_state.new_record {
+                        passage_title.push(Span::raw(" "));
+                        passage_title.push(Span::styled(
+                            format!(" {} ", record),
+                            Style::default().bg(Color::Green).fg(Color::Black).add_modifier(Modifier::BOLD),
+                        ));
+                    }
+
+                    // Code needs its line breaks and indentation left alone
+                    let (passage_text, trim) = if game_state.text_mode == word_processing::GameMode::Code {
+                        (get_code_lines(game_state.texts.ghosted_passage()), false)
+                    } else {
+                        (Text::from(Spans::from(game_state.texts.ghosted_passage())), true)
+                    };
+
                     f.render_widget(
-                        Paragraph::new(Spans::from(game_state.texts.passage.clone()))
-                            .block(passage_block.title(Spans::from(game_state.title)))
-                            .wrap(Wrap{trim: true})
+                        Paragraph::new(passage_text)
+                            .block(passage_block.title(Spans::from(passage_title)))
+                            .wrap(Wrap{trim})
                             .alignment(Alignment::Left),
                         chunks[2],
                     );
@@ -167,6 +247,30 @@ pub fn render<B: Backend>(
                         .border_type(BorderType::Rounded)
                         .border_style(get_border_style(&game_state, &[]));
 
+                    // Taller terminals have room to chart the user's speed above the stats
+                    if chunks[1].height > 0 {
+                        f.render_widget(
+                            wpm_sparkline::WpmSparkline {
+                                block: Block::default()
+                                    .borders(Borders::ALL)
+                                    .border_type(BorderType::Rounded)
+                                    .border_style(get_border_style(&game_state, &[]))
+                                    .title(match game_state.average_wpm {
+                                        Some(wpm) => format!("WPM - avg {}", wpm),
+                                        None => "WPM".to_owned(),
+                                    }),
+                                wpm_samples: game_state.stats.get_wpm_samples(),
+                                average_wpm: game_state.average_wpm,
+                            },
+                            chunks[1],
+                        );
+                    }
+
+                    let stats_title = match game_state.time_left {
+                        Some(seconds) => format!("Stats - {}s left", seconds),
+                        None => "Stats".to_owned(),
+                    };
+
                     let stats_text = game_state.stats.text();
                     let headers = stats_text.first()
                         .expect("Stats produced no text elements");
@@ -176,12 +280,12 @@ pub fn render<B: Backend>(
                         .map(|name_value_vec| Row::new(name_value_vec.clone()));
                     // set to be the width of the longest name in the stats
                     let stat_column_width = 5;
-                    // set to be an arbitrary value, 5 digits should be plenty to show the values for now
-                    let value_column_width = 5;
+                    // set to be an arbitrary value, 7 characters fits race times up to 999.9s
+                    let value_column_width = 7;
                     f.render_widget(
                         Table::new( rows)
                             .header(Row::new(headers.clone()))
-                            .block(stats_block.title("Stats"))
+                            .block(stats_block.title(stats_title))
                             .widths(&[
                                 Constraint::Length(stat_column_width),
                                 Constraint::Length(value_column_width),
@@ -200,7 +304,10 @@ pub fn render<B: Backend>(
                 let shortcut_block = Block::default()
                     .borders(Borders::NONE);
 
-                let mut continuation_text = Text::from("^C exit  ^U clear line ^W clear word \n^R[estart]  ^N[ext]  ^P[revious] ^G[raphs]  ^A[bout/docs]\n");
+                let mut continuation_text = Text::from(game_state.shortcuts.map_or_else(
+                    || "^C exit  ^U clear line ^W clear word  ^B[rowse history]  ^L[eaderboard]\n^R[estart]  ^N[ext]  ^P[revious] ^G[raphs]  ^K[eyboard heatmap]  ^T[rophies]  ^A[bout/docs]\n".to_owned(),
+                    |shortcuts| format!("{}\n", shortcuts),
+                ));
                 continuation_text.extend(
                         Text::styled(
                             format!("Build: {}", typeracer_version),
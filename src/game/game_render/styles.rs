@@ -27,8 +27,8 @@ const MEDIUM_TYPING_LAYOUT: [Constraint; 4] = [
 ];
 
 const MEDIUM_STATS_LAYOUT: [Constraint; 3] = [
-    Constraint::Percentage(20),
     Constraint::Percentage(0),
+    Constraint::Percentage(20),
     Constraint::Percentage(80),
 ];
 
@@ -0,0 +1,83 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Clear, Paragraph, Widget, Wrap},
+};
+
+use crate::game::achievements::Achievement;
+
+/// Pops up over the top right corner of the screen when the user unlocks achievements
+pub struct AchievementToast<'a> {
+    pub achievements: &'a [Achievement],
+}
+
+impl<'a> AchievementToast<'a> {
+    /// Where the toast goes on a screen the size of `area`, wide enough to fit every line
+    pub fn area(&self, area: Rect) -> Rect {
+        let widest_line = self
+            .achievements
+            .iter()
+            .map(|achievement| achievement.description().chars().count() + 2)
+            .max()
+            .unwrap_or(0);
+        // Room for the borders
+        let width = (widest_line as u16 + 2).min(area.width);
+        // A line for the name and one for the description of each, inside the borders
+        let height = (self.achievements.len() as u16 * 2 + 2).min(area.height);
+        Rect::new(area.right() - width, area.top(), width, height)
+    }
+}
+
+impl<'a> Widget for AchievementToast<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let lines: Vec<Spans> = self
+            .achievements
+            .iter()
+            .flat_map(|achievement| {
+                [
+                    Spans::from(Span::styled(
+                        format!("★ {}", achievement.name()),
+                        Style::default()
+                            .fg(Color::Yellow)
+                            .add_modifier(Modifier::BOLD),
+                    )),
+                    Spans::from(format!("  {}", achievement.description())),
+                ]
+            })
+            .collect();
+
+        // Whatever was drawn underneath shouldn't show through
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(
+                Block::default()
+                    .title("Achievement unlocked!")
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toast_sits_in_the_top_right() {
+        let toast = AchievementToast {
+            achievements: &[Achievement::Survivor, Achievement::Flawless],
+        };
+        // Wide enough for "Type a passage of 300+ characters with 100% accuracy"
+        assert_eq!(
+            toast.area(Rect::new(0, 0, 120, 40)),
+            Rect::new(64, 0, 56, 6)
+        );
+        // Squeezed onto small screens
+        assert_eq!(toast.area(Rect::new(0, 0, 30, 4)), Rect::new(0, 0, 30, 4));
+    }
+}
@@ -0,0 +1,100 @@
+use tui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Color, Style},
+    widgets::{Block, Sparkline, Widget},
+};
+
+/// Drawn across the chart wherever the bars don't reach the user's average
+const AVERAGE_MARKER: &str = "┈";
+
+/// The user's wpm over the race so far, one bar a second, with their usual speed marked so they
+/// can see when they're falling behind it
+pub struct WpmSparkline<'a> {
+    pub block: Block<'a>,
+    pub wpm_samples: &'a [u64],
+    pub average_wpm: Option<u64>,
+}
+
+impl<'a> Widget for WpmSparkline<'a> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let inner = self.block.inner(area);
+        self.block.render(area, buf);
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        // Only the most recent seconds fit
+        let shown = visible_samples(self.wpm_samples, inner.width);
+        let max = shown
+            .iter()
+            .copied()
+            .chain(self.average_wpm)
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        Sparkline::default()
+            .data(shown)
+            .max(max)
+            .style(Style::default().fg(Color::Yellow))
+            .render(inner, buf);
+
+        if let Some(average_wpm) = self.average_wpm {
+            let y = inner.bottom() - 1 - marker_row(average_wpm, max, inner.height);
+            for x in inner.left()..inner.right() {
+                let cell = buf.get_mut(x, y);
+                // Leave the bars alone, the marker only shows in the gaps above them
+                if cell.symbol == " " {
+                    cell.set_symbol(AVERAGE_MARKER).set_fg(Color::Gray);
+                }
+            }
+        }
+    }
+}
+
+fn visible_samples(wpm_samples: &[u64], width: u16) -> &[u64] {
+    &wpm_samples[wpm_samples.len().saturating_sub(usize::from(width))..]
+}
+
+/// Rows up from the bottom of the chart that `wpm` reaches, when `max` fills all `height` rows
+fn marker_row(wpm: u64, max: u64, height: u16) -> u16 {
+    let row = wpm * u64::from(height) / max;
+    (row as u16).min(height - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_the_latest_samples_are_shown() {
+        assert_eq!(visible_samples(&[1, 2, 3, 4], 2), [3, 4]);
+        assert_eq!(visible_samples(&[1, 2], 5), [1, 2]);
+    }
+
+    #[test]
+    fn marker_stays_on_the_chart() {
+        assert_eq!(marker_row(0, 100, 4), 0);
+        assert_eq!(marker_row(50, 100, 4), 2);
+        assert_eq!(marker_row(100, 100, 4), 3);
+    }
+
+    #[test]
+    fn average_is_marked_above_the_bars() {
+        let area = Rect::new(0, 0, 4, 4);
+        let mut buf = Buffer::empty(area);
+        WpmSparkline {
+            block: Block::default(),
+            wpm_samples: &[100, 20, 20, 20],
+            average_wpm: Some(50),
+        }
+        .render(area, &mut buf);
+
+        // The first bar is taller than the average so the marker goes around it
+        assert_eq!(buf.get(0, 1).symbol, "█");
+        for x in 1..4 {
+            assert_eq!(buf.get(x, 1).symbol, AVERAGE_MARKER);
+        }
+    }
+}
@@ -0,0 +1,106 @@
+use crate::game::keystrokes::Keystroke;
+
+/// The pace of the user's best previous run on a passage, replayed alongside them as they type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ghost {
+    pub wpm: u64,
+    /// Pairs of (milliseconds since the run started, letters typed correctly by then) for every
+    /// time the run moved forward.
+    progress: Vec<(u64, usize)>,
+}
+
+impl Ghost {
+    pub fn from_keystrokes(wpm: u64, keystrokes: &[Keystroke]) -> Self {
+        let mut progress: Vec<(u64, usize)> = vec![];
+        let mut furthest = 0;
+
+        for keystroke in keystrokes {
+            if keystroke.backspace || !keystroke.correct || keystroke.position < furthest {
+                continue;
+            }
+            furthest = keystroke.position + 1;
+            progress.push((keystroke.offset_millis, furthest));
+        }
+
+        Ghost { wpm, progress }
+    }
+
+    /// Index of the letter in the passage the ghost is on `millis` after the start.
+    pub fn position_at(&self, millis: u64) -> usize {
+        self.progress
+            .iter()
+            .take_while(|(offset_millis, _)| *offset_millis <= millis)
+            .last()
+            .map_or(0, |(_, position)| *position)
+    }
+
+    /// Index of the ghost in the passage as the user sees it.
+    /// When the passage is shown a line at a time, `hidden_letters` letters have been typed and
+    /// are no longer displayed. If the ghost is back there, the user is ahead and there is
+    /// nothing to show.
+    pub fn displayed_position(
+        &self,
+        millis: u64,
+        hidden_letters: usize,
+        passage_len: usize,
+    ) -> Option<usize> {
+        let position = self.position_at(millis);
+        if position < hidden_letters || position >= passage_len {
+            None
+        } else {
+            Some(position - hidden_letters)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keystroke(offset_millis: u64, position: usize, correct: bool) -> Keystroke {
+        Keystroke {
+            offset_millis,
+            position,
+            expected: None,
+            typed: 'a',
+            correct,
+            backspace: false,
+        }
+    }
+
+    #[test]
+    fn ghost_follows_correct_keystrokes() {
+        let ghost = Ghost::from_keystrokes(
+            100,
+            &[
+                keystroke(0, 0, true),
+                keystroke(200, 1, true),
+                // mistake, shouldn't move the ghost forward
+                keystroke(300, 2, false),
+                Keystroke {
+                    backspace: true,
+                    ..keystroke(400, 2, false)
+                },
+                keystroke(500, 2, true),
+            ],
+        );
+
+        assert_eq!(ghost.position_at(0), 1);
+        assert_eq!(ghost.position_at(199), 1);
+        assert_eq!(ghost.position_at(200), 2);
+        assert_eq!(ghost.position_at(450), 2);
+        assert_eq!(ghost.position_at(10_000), 3);
+    }
+
+    #[test]
+    fn ghost_hidden_when_behind_or_done() {
+        let ghost = Ghost::from_keystrokes(100, &[keystroke(0, 0, true), keystroke(100, 1, true)]);
+
+        assert_eq!(ghost.displayed_position(100, 0, 5), Some(2));
+        assert_eq!(ghost.displayed_position(100, 1, 5), Some(1));
+        // The user has typed past the ghost
+        assert_eq!(ghost.displayed_position(100, 3, 5), None);
+        // The ghost has finished the passage
+        assert_eq!(ghost.displayed_position(100, 0, 2), None);
+    }
+}
@@ -20,6 +20,19 @@ pub fn check_like_word(word: &str, input: &str) -> bool {
     word_graphene[..input_graphene.len()] == input_graphene[..]
 }
 
+/// Count the characters in the input that don't match the word, anything typed past the end
+/// of the word is wrong too. e.g. apple, abple => 1, apple applesauce => 5
+pub fn count_mismatched(word: &str, input: &str) -> usize {
+    let word_graphene = UnicodeSegmentation::graphemes(word, true).collect::<Vec<&str>>();
+    let input_graphene = UnicodeSegmentation::graphemes(input, true).collect::<Vec<&str>>();
+
+    input_graphene
+        .iter()
+        .enumerate()
+        .filter(|(idx, typed)| word_graphene.get(*idx) != Some(typed))
+        .count()
+}
+
 /// Given a vector of word and the current index of the word the user is typing,
 /// ["this", "is", "a", "vector"] and current_word_idx of 2,
 /// return the index as if we were indexing the previous vector as a space
@@ -104,6 +117,15 @@ mod tests {
         assert!(check_like_word("你好", "你好"));
     }
 
+    #[test]
+    fn test_count_mismatched() {
+        assert_eq!(count_mismatched("apple", ""), 0);
+        assert_eq!(count_mismatched("apple", "app"), 0);
+        assert_eq!(count_mismatched("apple", "abple"), 1);
+        assert_eq!(count_mismatched("apple", "applesauce"), 5);
+        assert_eq!(count_mismatched("你好", "你们"), 1);
+    }
+
     #[test]
     fn test_get_starting_idx_latin() {
         let words = vec!["this", "is", "a", "vector"];
@@ -0,0 +1,89 @@
+use crate::game::word_processing::GameMode;
+
+/// Stand-ins stored as the typed character for keys that delete input rather than add to it.
+/// They're the ASCII control codes for each key, see: https://www.asciitable.com/
+pub const BACKSPACE: char = '\x08';
+pub const CLEAR_WORD: char = '\x17';
+pub const CLEAR_LINE: char = '\x15';
+
+/// A single key event processed during a race.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keystroke {
+    /// Milliseconds since the user typed their first character
+    pub offset_millis: u64,
+    /// Index of the letter in the passage the key was aimed at. Spaces counted.
+    pub position: usize,
+    /// What the passage has at `position`, None if the user has typed past the end of it
+    pub expected: Option<char>,
+    pub typed: char,
+    pub correct: bool,
+    pub backspace: bool,
+}
+
+/// Get the character the user is expected to type next, given what they have typed of the
+/// current word so far.
+/// Once the user has typed out the whole word, they are expected to hit space to move on to
+/// the next one (unless it is NonLatin text, which has no spaces).
+pub fn expected_char(
+    game_mode: &GameMode,
+    words: &[&str],
+    current_word_idx: usize,
+    user_input: &str,
+) -> Option<char> {
+    let current_word = words.get(current_word_idx)?;
+    let typed_count = user_input.chars().count();
+
+    match current_word.chars().nth(typed_count) {
+        Some(c) => Some(c),
+        None => {
+            if *game_mode == GameMode::Latin
+                && typed_count == current_word.chars().count()
+                && current_word_idx + 1 < words.len()
+            {
+                Some(' ')
+            } else {
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expects_letters_in_word() {
+        let words = ["the", "quick", "brown"];
+
+        assert_eq!(expected_char(&GameMode::Latin, &words, 1, ""), Some('q'));
+        assert_eq!(expected_char(&GameMode::Latin, &words, 1, "qu"), Some('i'));
+        // Even with a mistake we want the letter in the same spot
+        assert_eq!(expected_char(&GameMode::Latin, &words, 1, "qx"), Some('i'));
+    }
+
+    #[test]
+    fn expects_space_after_word() {
+        let words = ["the", "quick", "brown"];
+
+        assert_eq!(
+            expected_char(&GameMode::Latin, &words, 1, "quick"),
+            Some(' ')
+        );
+        // There's nothing after the final word
+        assert_eq!(expected_char(&GameMode::Latin, &words, 2, "brown"), None);
+        assert_eq!(expected_char(&GameMode::Latin, &words, 1, "quickk"), None);
+    }
+
+    #[test]
+    fn expects_nonlatin_characters() {
+        let words = ["你", "好"];
+
+        assert_eq!(
+            expected_char(&GameMode::NonLatin, &words, 1, ""),
+            Some('好')
+        );
+        assert_eq!(expected_char(&GameMode::NonLatin, &words, 0, "你"), None);
+        assert_eq!(expected_char(&GameMode::NonLatin, &words, 2, ""), None);
+    }
+}
@@ -0,0 +1,101 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Style},
+    terminal::Terminal,
+    text::Span,
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table},
+};
+
+use crate::{game::replay::RaceSummary, report};
+
+pub fn render<B: Backend>(terminal: &mut Terminal<B>, title: &str, races: &[RaceSummary]) {
+    let now = report::now_secs();
+
+    terminal
+        .draw(|f| {
+            let padding_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
+
+            let main_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(80),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(padding_layout[1]);
+
+            let block = Block::default()
+                .title(format!("Leaderboard - {}", title))
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded);
+
+            if races.is_empty() {
+                f.render_widget(
+                    Paragraph::new("No finished runs of this passage yet! Set the first record.")
+                        .alignment(Alignment::Center)
+                        .block(block),
+                    main_layout[1],
+                );
+            } else {
+                let rows = races.iter().enumerate().map(|(place, race)| {
+                    let style = match place {
+                        0 => Style::default().fg(Color::Yellow),
+                        1 => Style::default().fg(Color::Gray),
+                        2 => Style::default().fg(Color::LightRed),
+                        _ => Style::default(),
+                    };
+                    Row::new(vec![
+                        format!("{}.", place + 1),
+                        race.wpm.to_string(),
+                        format!("{:.1}%", race.accuracy),
+                        race.game_mode.to_string(),
+                        report::time_ago(now, race.when_played_secs),
+                    ])
+                    .style(style)
+                });
+
+                f.render_widget(
+                    Table::new(rows)
+                        .header(
+                            Row::new(vec!["#", "WPM", "Accuracy", "Mode", "When"])
+                                .style(Style::default().fg(Color::Yellow)),
+                        )
+                        .block(block)
+                        .widths(
+                            [
+                                Constraint::Length(4),
+                                Constraint::Length(5),
+                                Constraint::Length(9),
+                                Constraint::Length(14),
+                                Constraint::Min(10),
+                            ]
+                            .as_ref(),
+                        ),
+                    main_layout[1],
+                );
+            }
+
+            f.render_widget(
+                Paragraph::new(Span::raw("^C to go back"))
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::NONE)),
+                main_layout[2],
+            );
+        })
+        .expect("Failed to draw to terminal");
+}
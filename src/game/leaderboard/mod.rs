@@ -0,0 +1,31 @@
+use crossbeam_channel::Receiver;
+use std::path::Path;
+use std::time::Duration;
+use termion::event::Key;
+use tui::{backend::Backend, terminal::Terminal};
+
+use crate::{game::game_db, passage_controller::PassageInfo};
+
+mod leaderboard_render;
+
+/// Number of runs listed on a leaderboard
+const LEADERBOARD_SIZE: usize = 10;
+
+/// Show the user's fastest runs of a passage, across every game mode.
+pub fn show_leaderboard<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input_receiver: &Receiver<Key>,
+    db_path: &Path,
+    passage_info: &PassageInfo,
+) -> Result<(), rusqlite::Error> {
+    let races = game_db::retrieve_leaderboard(db_path, passage_info, LEADERBOARD_SIZE)?;
+
+    loop {
+        leaderboard_render::render(terminal, &passage_info.title, &races);
+
+        // Re-render every so often in case the terminal was resized
+        if let Ok(Key::Ctrl('c')) = input_receiver.recv_timeout(Duration::from_secs(1)) {
+            return Ok(());
+        }
+    }
+}
@@ -1,4 +1,10 @@
-use std::{collections::HashSet, fmt, io::stdout, path::Path, time::Duration};
+use std::{
+    collections::HashSet,
+    fmt,
+    io::stdout,
+    path::Path,
+    time::{Duration, Instant},
+};
 
 use crossbeam_channel::Receiver;
 use termion::{event::Key, raw::IntoRawMode, screen::AlternateScreen};
@@ -6,21 +12,36 @@ use tui::{backend::TermionBackend, text::Span, Terminal};
 
 use config::TyperacerConfig;
 use graphs::show_graphs;
+use heatmap::show_heatmap;
 use info::show_info;
 
 use crate::{
-    actions::Action, config, dirs::setup_dirs::get_db_path, graphs, info,
-    passage_controller::PassageInfo, stats,
+    actions::Action,
+    config,
+    dirs::setup_dirs::get_db_path,
+    goals, graphs, heatmap, info, multiplayer,
+    passage_controller::{Controller, PassageInfo},
+    stats,
 };
 
+pub mod achievements;
 pub mod formatter;
+pub mod ghost;
 pub mod indexer;
+pub mod keystrokes;
+pub mod leaderboard;
+pub mod personal_bests;
+pub mod replay;
+pub mod results;
 pub mod split;
 pub mod word_processing;
 
 mod game_db;
 mod game_render;
 
+/// How long achievement toasts stay up on the results screen
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+
 const TERRIBLE_DB_FAILURE: &str =
     "HELP - TROUBLE STORING DATA IN THE DB, CONTACT THE MAINTAINER AND SHOW THEM THIS ERROR:";
 
@@ -29,6 +50,20 @@ pub enum GameMode {
     Default,
     InstantDeath,
     Training,
+    Timed,
+    Drill,
+}
+
+/// Settings for a game that stay the same no matter which passage is being played.
+pub struct GameSettings<'a> {
+    pub debug_enabled: bool,
+    pub game_mode: GameMode,
+    /// How long a race lasts in `GameMode::Timed`. Unused in all other modes.
+    pub time_limit: Duration,
+    pub typeracer_version: &'a str,
+    pub typeracer_config: &'a TyperacerConfig,
+    /// The multiplayer race this game is a part of, if any
+    pub race: Option<&'a multiplayer::Session>,
 }
 
 impl GameMode {
@@ -37,6 +72,8 @@ impl GameMode {
             GameMode::Default,
             GameMode::InstantDeath,
             GameMode::Training,
+            GameMode::Timed,
+            GameMode::Drill,
         ]
     }
     // This is supposed to work like a baby state machine/ringbuffer
@@ -50,13 +87,17 @@ impl GameMode {
         match self {
             GameMode::Default => GameMode::InstantDeath,
             GameMode::InstantDeath => GameMode::Training,
-            GameMode::Training => GameMode::Default,
+            GameMode::Training => GameMode::Timed,
+            GameMode::Timed => GameMode::Drill,
+            GameMode::Drill => GameMode::Default,
         }
     }
 
     pub fn prev(self) -> Self {
         match self {
-            GameMode::Default => GameMode::Training,
+            GameMode::Default => GameMode::Drill,
+            GameMode::Drill => GameMode::Timed,
+            GameMode::Timed => GameMode::Training,
             GameMode::Training => GameMode::InstantDeath,
             GameMode::InstantDeath => GameMode::Default,
         }
@@ -69,6 +110,8 @@ impl fmt::Display for GameMode {
             GameMode::InstantDeath => write!(f, "Instant Death"),
             GameMode::Default => write!(f, "Default"),
             GameMode::Training => write!(f, "Training"),
+            GameMode::Timed => write!(f, "Timed"),
+            GameMode::Drill => write!(f, "Drill"),
         }
     }
 }
@@ -77,6 +120,8 @@ impl fmt::Display for GameMode {
 impl From<GameMode> for i64 {
     fn from(gm: GameMode) -> i64 {
         match gm {
+            GameMode::Drill => 4,
+            GameMode::Timed => 3,
             GameMode::Training => 2,
             GameMode::InstantDeath => 1,
             GameMode::Default => 0,
@@ -87,6 +132,8 @@ impl From<GameMode> for i64 {
 impl From<i64> for GameMode {
     fn from(i: i64) -> Self {
         match i {
+            4 => GameMode::Drill,
+            3 => GameMode::Timed,
             2 => GameMode::Training,
             1 => GameMode::InstantDeath,
             _ => GameMode::Default,
@@ -99,13 +146,16 @@ impl From<i64> for GameMode {
 // TODO: Provide get_backend method in game_render
 pub fn play_game(
     passage_info: &PassageInfo,
+    passage_controller: &mut Controller,
     input_receiver: &Receiver<Key>,
     stats: &mut stats::Stats,
-    debug_enabled: bool,
-    game_mode: GameMode,
-    typeracer_version: &str,
-    typeracer_config: &TyperacerConfig,
+    settings: &GameSettings,
 ) -> Action {
+    let game_mode = settings.game_mode;
+    let typeracer_config = settings.typeracer_config;
+    let typeracer_version = settings.typeracer_version;
+    let debug_enabled = settings.debug_enabled;
+
     let stdout = stdout()
         .into_raw_mode()
         .expect("Failed to manipulate terminal to raw mode");
@@ -114,37 +164,135 @@ pub fn play_game(
     let mut terminal = Terminal::new(backend).expect("Unable to get handle to terminal.");
     terminal.hide_cursor().expect("Failed to hide the cursor");
 
+    // In a multiplayer race everyone types whatever passage the host picked
+    let race_passage;
+    let passage_info = match settings.race {
+        Some(race) => match race.wait_for_start(&mut terminal, input_receiver, passage_info) {
+            Some(passage) => {
+                race_passage = passage;
+                &race_passage
+            }
+            None => return Action::Quit,
+        },
+        None => passage_info,
+    };
+    let mut reported_progress: Option<(u16, u64, bool)> = None;
+
+    // In timed mode new passages are tacked onto the end of this one as the user runs out of
+    // words, so we hold onto our own copy and split it into words at the top of every loop.
+    let mut passage = passage_info.passage.clone();
+    // Word index each passage starts at paired with its title, so we can show the title of
+    // whichever passage the user is currently typing.
+    let mut titles: Vec<(usize, String)> = vec![(0, passage_info.title.clone())];
+
     let mut formatted_texts = formatter::FormattedTexts {
-        passage: passage_info
-            .passage
+        passage: passage
             .chars()
             .map(|it| Span::raw(it.to_string()))
             .collect(),
         input: vec![],
         error: false,
         complete: false,
+        ghost_idx: None,
     };
 
     let mut user_input = String::new();
     let mut mistaken_words: HashSet<String> = HashSet::new();
+    let mut keystroke_log: Vec<keystrokes::Keystroke> = vec![];
+    let mut mistyped_words: Vec<results::MistypedWord> = vec![];
+    // Only instant death can end a race early
+    let mut failed = false;
 
-    // Split the passage into vec of words to work on one at a time
-    let words: Vec<&str> = split::to_words(&passage_info.passage);
-    let text_mode = word_processing::get_game_mode(&passage_info.passage);
+    let text_mode = word_processing::get_game_mode(&passage, passage_info.is_code());
 
     let mut current_word_idx = 0;
 
     let mut got_first_input = false;
+    let mut race_start: Option<Instant> = None;
+
+    // Race against the best previous run if the user has played this passage before.
+    // Training, drill and timed passages are different every time, so there is nothing to race.
+    let ghost = match game_mode {
+        GameMode::Default | GameMode::InstantDeath => {
+            game_db::retrieve_ghost(&get_db_path(), passage_info, game_mode).unwrap_or(None)
+        }
+        GameMode::Training | GameMode::Timed | GameMode::Drill => None,
+    };
+    let passage_len = passage.chars().count();
+    let average_wpm = game_db::retrieve_average_wpm(&get_db_path(), game_mode).unwrap_or(None);
+    let personal_bests = game_db::retrieve_personal_bests(
+        &get_db_path(),
+        &played_passage(game_mode, passage_info, &[]),
+        game_mode,
+    )
+    .unwrap_or_default();
 
     loop {
+        // Split the passage into vec of words to work on one at a time
+        let words: Vec<&str> =
+            split::to_words(&passage, text_mode == word_processing::GameMode::Code);
+
+        if game_mode == GameMode::Timed
+            && !formatted_texts.complete
+            && current_word_idx + 1 >= words.len()
+        {
+            let next_passage = next_timed_passage(passage_controller, &text_mode, passage_info);
+            let separator = match text_mode {
+                word_processing::GameMode::Latin => " ",
+                word_processing::GameMode::NonLatin => "",
+                word_processing::GameMode::Code => "\n",
+            };
+            let appended_text = format!("{}{}", separator, next_passage.passage);
+            formatted_texts
+                .passage
+                .extend(appended_text.chars().map(|it| Span::raw(it.to_string())));
+            titles.push((words.len(), next_passage.title));
+            passage.push_str(&appended_text);
+            continue;
+        }
+
+        let time_left = get_time_left(settings, race_start);
+
+        report_race_progress(
+            settings,
+            &mut reported_progress,
+            current_word_idx,
+            words.len(),
+            stats,
+        );
+        let racers = settings.race.map(|race| race.players()).unwrap_or_default();
+
+        formatted_texts.ghost_idx = match &ghost {
+            Some(ghost) if !formatted_texts.complete => ghost.displayed_position(
+                stats.get_millis_since_start(),
+                if typeracer_config.display_settings.always_full {
+                    0
+                } else {
+                    indexer::get_starting_idx(&text_mode, &words, current_word_idx)
+                },
+                passage_len,
+            ),
+            _ => None,
+        };
+
         game_render::render(
             &mut terminal,
             game_render::GameState {
                 texts: &formatted_texts,
                 user_input: &user_input,
                 stats,
-                title: &passage_info.title,
+                title: current_title(&titles, current_word_idx),
+                ghost_wpm: ghost.as_ref().map(|ghost| ghost.wpm),
+                average_wpm,
+                new_record: if failed
+                    || !past_halfway(settings, race_start, current_word_idx, words.len())
+                {
+                    None
+                } else {
+                    personal_bests.beaten_by(stats.get_wpm())
+                },
                 game_mode,
+                text_mode,
                 config: typeracer_config,
                 debug_enabled,
                 word_idx: current_word_idx,
@@ -156,6 +304,9 @@ pub fn play_game(
                     words[current_word_idx]
                 },
                 mistaken_words: &mistaken_words,
+                time_left,
+                racers: &racers,
+                shortcuts: None,
             },
             typeracer_version,
         );
@@ -163,6 +314,14 @@ pub fn play_game(
             break;
         }
 
+        if time_left == Some(0) {
+            stats.update_wpm(current_word_idx, &words);
+            formatted_texts =
+                formatter::get_reformatted_timed_out_texts(&text_mode, &words[..current_word_idx]);
+            user_input.clear();
+            continue;
+        }
+
         // backspace and clearing the line are technically new chars, but shouldn't be
         // added to the combo. This lets us keep track of when when the user actually types
         // a new character (useful for combo).
@@ -174,7 +333,15 @@ pub fn play_game(
 
         let mut allowed_to_increment_combo = false;
 
-        let recv_result = input_receiver.recv_timeout(Duration::from_millis(500));
+        // Timed mode needs to notice the clock running out quickly and the ghost needs to move
+        // smoothly, otherwise we can take our time
+        let recv_timeout = if game_mode == GameMode::Timed || ghost.is_some() {
+            Duration::from_millis(100)
+        } else {
+            Duration::from_millis(500)
+        };
+
+        let recv_result = input_receiver.recv_timeout(recv_timeout);
         if recv_result.is_err() {
             if got_first_input {
                 stats.update_wpm(current_word_idx, &words);
@@ -183,33 +350,84 @@ pub fn play_game(
             continue;
         }
 
+        // Where the user was aiming before we handle the key, so typed characters can be logged
+        let typing_position = indexer::get_starting_idx(&text_mode, &words, current_word_idx)
+            + user_input.chars().count();
+        let typing_expected =
+            keystrokes::expected_char(&text_mode, &words, current_word_idx, &user_input);
+        // Only the key that first takes the user off track counts as missing a character, anything
+        // typed after that is wrong because of the earlier mistake.
+        let on_track = indexer::check_like_word(words[current_word_idx], &user_input);
+        // Set for any key that changes the user's input, these are what go in the keystroke log
+        let mut logged_key: Option<char> = None;
+
         match recv_result.unwrap() {
-            Key::Ctrl('a') => show_info(&mut terminal, input_receiver, typeracer_version),
+            Key::Ctrl('a') => show_info(
+                &mut terminal,
+                input_receiver,
+                &get_db_path(),
+                &typeracer_config.goals,
+                typeracer_version,
+            ),
             Key::Ctrl('c') => return Action::Quit,
             Key::Ctrl('n') => return Action::NextPassage,
             Key::Ctrl('p') => return Action::PreviousPassage,
             Key::Ctrl('r') => return Action::RestartPassage,
             Key::Ctrl('g') => show_graphs(&mut terminal, input_receiver, &get_db_path(), game_mode)
                 .expect("Unable to get data for graph"),
+            Key::Ctrl('k') => {
+                show_heatmap(&mut terminal, input_receiver, &get_db_path(), game_mode)
+                    .expect("Unable to get data for heatmap")
+            }
+            Key::Ctrl('b') => replay::show_history(
+                &mut terminal,
+                input_receiver,
+                &get_db_path(),
+                typeracer_config,
+                typeracer_version,
+            )
+            .expect("Unable to get race history"),
+            Key::Ctrl('l') => leaderboard::show_leaderboard(
+                &mut terminal,
+                input_receiver,
+                &get_db_path(),
+                &played_passage(game_mode, passage_info, &[]),
+            )
+            .expect("Unable to get leaderboard"),
+            Key::Ctrl('t') => {
+                achievements::show_achievements(&mut terminal, input_receiver, &get_db_path())
+                    .expect("Unable to get achievements")
+            }
             // Get some basic readline bindings
-            Key::Ctrl('u') => user_input.clear(),
+            Key::Ctrl('u') => {
+                user_input.clear();
+                logged_key = Some(keystrokes::CLEAR_LINE);
+            }
             // \x08 is ascii backspace. See: https://www.asciitable.com/
             Key::Ctrl('w') | Key::Alt('\x08') => {
-                user_input = word_processing::get_all_input_minus_last_word(&user_input)
+                user_input =
+                    word_processing::get_all_input_minus_last_word(&text_mode, &user_input);
+                logged_key = Some(keystrokes::CLEAR_WORD);
             }
             Key::Backspace | Key::Ctrl('h') => {
                 user_input.pop();
+                logged_key = Some(keystrokes::BACKSPACE);
             }
             Key::Char(c) => {
                 got_first_input = true;
                 new_char = true;
                 last_input_char = c;
                 stats.update_start_time();
+                race_start.get_or_insert_with(Instant::now);
 
                 if word_processing::word_completed(
                     &text_mode,
                     last_input_char,
-                    words[current_word_idx],
+                    word_processing::get_typed_part(
+                        &text_mode,
+                        words[current_word_idx],
+                        typeracer_config.code_settings.type_indentation,
+                    ),
                     &user_input,
                 ) {
                     if !typeracer_config.display_settings.always_full {
@@ -221,11 +439,16 @@ pub fn play_game(
                     }
                     current_word_idx += 1;
                     user_input.clear();
-                } else if c == '\n' || c == '\t' {
+                    logged_key = Some(c);
+                    stats.record_typed_char();
+                } else if c == '\t' || (c == '\n' && text_mode != word_processing::GameMode::Code) {
                     // Ignore a few types that can put the user in a weird spot
                     // We just want to ignore these characters.
+                    // Code is the exception, newlines are part of the passage there.
                 } else {
                     user_input.push(c);
+                    logged_key = Some(c);
+                    stats.record_typed_char();
                 }
 
                 stats.update_wpm(current_word_idx, &words);
@@ -259,13 +482,70 @@ pub fn play_game(
 
         let current_letter_idx =
             indexer::get_trying_letter_idx(&text_mode, &words, current_word_idx, &user_input);
+        stats.update_uncorrected_errors(match words.get(current_word_idx) {
+            Some(word) => indexer::count_mismatched(
+                word_processing::get_typed_part(
+                    &text_mode,
+                    word,
+                    typeracer_config.code_settings.type_indentation,
+                ),
+                &user_input,
+            ),
+            None => 0,
+        });
+
+        if let Some(typed) = logged_key {
+            keystroke_log.push(if new_char {
+                keystrokes::Keystroke {
+                    offset_millis: stats.get_millis_since_start(),
+                    position: typing_position,
+                    expected: typing_expected,
+                    typed,
+                    correct: !formatted_texts.error,
+                    backspace: false,
+                }
+            } else {
+                // Deleting moves the user back, so log where they ended up
+                keystrokes::Keystroke {
+                    offset_millis: stats.get_millis_since_start(),
+                    position: indexer::get_starting_idx(&text_mode, &words, current_word_idx)
+                        + user_input.chars().count(),
+                    expected: keystrokes::expected_char(
+                        &text_mode,
+                        &words,
+                        current_word_idx,
+                        &user_input,
+                    ),
+                    typed,
+                    correct: false,
+                    backspace: true,
+                }
+            });
+        }
         if formatted_texts.error && new_char {
-            stats.increment_errors(current_letter_idx);
+            let mistype = typing_expected
+                .filter(|_| on_track)
+                .map(|expected| stats::Mistype {
+                    expected,
+                    previous: typing_position
+                        .checked_sub(1)
+                        .and_then(|previous_idx| passage.chars().nth(previous_idx)),
+                    typed: last_input_char,
+                });
+            stats.increment_errors(current_letter_idx, mistype);
 
             // Additionally build the set of mistaken words
-            mistaken_words.insert(words[current_word_idx].to_string());
+            // Code words carry their whitespace with them, which we don't want to train on
+            mistaken_words.insert(words[current_word_idx].trim().to_string());
+            results::record_mistyped_word(
+                &mut mistyped_words,
+                current_word_idx,
+                words[current_word_idx].trim(),
+                &user_input,
+            );
 
             if game_mode == GameMode::InstantDeath {
+                failed = true;
                 formatted_texts = formatter::get_reformatted_failed_texts(&text_mode, &words);
                 continue;
             }
@@ -275,7 +555,10 @@ pub fn play_game(
             stats.increment_combo(current_letter_idx);
         }
 
-        if word_processing::decide_game_end(&text_mode, current_word_idx, &words, &user_input) {
+        // Timed games only end when the clock runs out, there is always another passage.
+        if game_mode != GameMode::Timed
+            && word_processing::decide_game_end(&text_mode, current_word_idx, &words, &user_input)
+        {
             // Check to see if the user is on the last word and it is correct.
             // If it is, we need to do a little extra work to set the passage back to the full
             // passage. If the user is running with display_settings.always_max=false then they
@@ -287,16 +570,51 @@ pub fn play_game(
         }
     }
 
-    if let Err(e) = game_db::store_stats(&get_db_path(), stats, passage_info, game_mode) {
-        maybe_rebuild_db_clean(&get_db_path(), &e);
-    }
+    let words: Vec<&str> = split::to_words(&passage, text_mode == word_processing::GameMode::Code);
+
+    let played_passage = played_passage(
+        game_mode,
+        passage_info,
+        &words[..current_word_idx.min(words.len())],
+    );
+
+    // Compare against how the user did before this race, so this is looked up before it's stored
+    let mut race_results = results::RaceResults {
+        failed,
+        mistyped_words,
+        personal_bests,
+        pack: game_db::pack_name(&played_passage.passage_path),
+        pack_average: game_db::retrieve_pack_average(
+            &get_db_path(),
+            &played_passage,
+            game_mode,
+            results::PACK_AVERAGE_RACES,
+        )
+        .unwrap_or(None),
+        ..results::RaceResults::new(stats, game_mode, &played_passage.title)
+    };
+
+    let passage_stats_id =
+        match game_db::store_stats(&get_db_path(), stats, &played_passage, game_mode) {
+            Ok(Some(passage_stats_id)) => {
+                if let Err(e) =
+                    game_db::store_keystrokes(&get_db_path(), passage_stats_id, &keystroke_log)
+                {
+                    maybe_rebuild_db_clean(&get_db_path(), &e);
+                }
+                Some(passage_stats_id)
+            }
+            Ok(None) => None, // Not a passage we keep stats for
+            Err(e) => {
+                maybe_rebuild_db_clean(&get_db_path(), &e);
+                None
+            }
+        };
 
     if game_mode == GameMode::Training {
-        if let Err(e) = game_db::roll_to_delete_mistaken_words_typed_correctly(
-            &get_db_path(),
-            &words,
-            &mistaken_words,
-        ) {
+        if let Err(e) =
+            game_db::review_mistaken_words_typed_correctly(&get_db_path(), &words, &mistaken_words)
+        {
             maybe_rebuild_db_clean(&get_db_path(), &e);
         }
     }
@@ -305,27 +623,58 @@ pub fn play_game(
         maybe_rebuild_db_clean(&get_db_path(), &e);
     }
 
+    if let Err(e) = game_db::store_mistypes(&get_db_path(), stats.get_mistypes(), game_mode) {
+        maybe_rebuild_db_clean(&get_db_path(), &e);
+    }
+
+    race_results.goal_progress =
+        goals::todays_progress(&get_db_path(), &typeracer_config.goals).ok();
+
+    let mut unlocked = vec![];
+    if let Some(passage_stats_id) = passage_stats_id {
+        let outcome = achievements::RaceOutcome {
+            game_mode,
+            wpm: race_results.wpm,
+            accuracy: race_results.accuracy,
+            highest_combo: race_results.highest_combo,
+            passage_chars: played_passage.passage.chars().count(),
+            failed,
+            streak: race_results
+                .goal_progress
+                .as_ref()
+                .map_or(0, |progress| progress.current_streak),
+        };
+        match game_db::store_achievements(
+            &get_db_path(),
+            passage_stats_id,
+            &achievements::earned(&outcome, typeracer_config.combo_config.combo_trigger),
+        ) {
+            Ok(newly_unlocked) => unlocked = newly_unlocked,
+            Err(e) => maybe_rebuild_db_clean(&get_db_path(), &e),
+        }
+    }
+    let results_shown = Instant::now();
+
     loop {
-        game_render::render(
+        report_race_progress(
+            settings,
+            &mut reported_progress,
+            current_word_idx,
+            words.len(),
+            stats,
+        );
+        let racers = settings.race.map(|race| race.players()).unwrap_or_default();
+
+        let toasts = if results_shown.elapsed() < TOAST_DURATION {
+            &unlocked[..]
+        } else {
+            &[]
+        };
+        results::render(
             &mut terminal,
-            game_render::GameState {
-                texts: &formatted_texts,
-                user_input: &user_input,
-                stats,
-                title: &passage_info.title,
-                game_mode,
-                config: typeracer_config,
-                debug_enabled,
-                complete: formatted_texts.complete,
-                word_idx: current_word_idx,
-                passage_path: &passage_info.passage_path,
-                current_word: if current_word_idx == words.len() {
-                    "DONE"
-                } else {
-                    words[current_word_idx]
-                },
-                mistaken_words: &mistaken_words,
-            },
+            &race_results,
+            &racers,
+            toasts,
             typeracer_version,
         );
         let recv_result = input_receiver.recv_timeout(Duration::from_millis(500));
@@ -335,21 +684,149 @@ pub fn play_game(
         }
         match recv_result.unwrap() {
             Key::Ctrl('a') => {
-                show_info(&mut terminal, input_receiver, typeracer_version);
+                show_info(
+                    &mut terminal,
+                    input_receiver,
+                    &get_db_path(),
+                    &typeracer_config.goals,
+                    typeracer_version,
+                );
             }
             Key::Ctrl('c') => return Action::Quit,
-            Key::Ctrl('n') => return Action::NextPassage,
+            Key::Ctrl('n') | Key::Char('\n') => return Action::NextPassage,
             Key::Ctrl('p') => return Action::PreviousPassage,
             Key::Ctrl('r') => return Action::RestartPassage,
             Key::Ctrl('g') => {
                 show_graphs(&mut terminal, input_receiver, &get_db_path(), game_mode)
                     .expect("Unable to get data for graph");
             }
+            Key::Ctrl('k') => {
+                show_heatmap(&mut terminal, input_receiver, &get_db_path(), game_mode)
+                    .expect("Unable to get data for heatmap");
+            }
+            Key::Ctrl('b') => {
+                replay::show_history(
+                    &mut terminal,
+                    input_receiver,
+                    &get_db_path(),
+                    typeracer_config,
+                    typeracer_version,
+                )
+                .expect("Unable to get race history");
+            }
+            Key::Ctrl('l') => {
+                leaderboard::show_leaderboard(
+                    &mut terminal,
+                    input_receiver,
+                    &get_db_path(),
+                    &played_passage,
+                )
+                .expect("Unable to get leaderboard");
+            }
+            Key::Ctrl('t') => {
+                achievements::show_achievements(&mut terminal, input_receiver, &get_db_path())
+                    .expect("Unable to get achievements");
+            }
             _ => (),
         }
     }
 }
 
+/// What a race is stored as. A timed race spans any number of passages, so we store what the
+/// user managed to type rather than stats for any one passage.
+fn played_passage(
+    game_mode: GameMode,
+    passage_info: &PassageInfo,
+    typed_words: &[&str],
+) -> PassageInfo {
+    if game_mode == GameMode::Timed {
+        PassageInfo {
+            passage: split::join_to_passage(typed_words),
+            title: "Timed".to_owned(),
+            passage_path: "TIMED_MODE_PATH".to_owned(),
+        }
+    } else {
+        passage_info.clone()
+    }
+}
+
+/// Whether the user is far enough through a race for their wpm to have settled, early on a fast
+/// first word can look like a record.
+fn past_halfway(
+    settings: &GameSettings,
+    race_start: Option<Instant>,
+    current_word_idx: usize,
+    num_words: usize,
+) -> bool {
+    match get_time_left(settings, race_start) {
+        Some(seconds_left) => seconds_left * 2 <= settings.time_limit.as_secs(),
+        None => current_word_idx * 2 >= num_words,
+    }
+}
+
+/// Seconds left on the clock in timed mode, None for every other mode.
+/// The clock doesn't start ticking until the user types their first character.
+fn get_time_left(settings: &GameSettings, race_start: Option<Instant>) -> Option<u64> {
+    if settings.game_mode != GameMode::Timed {
+        return None;
+    }
+
+    let elapsed = race_start.map_or(Duration::ZERO, |start| start.elapsed());
+    let remaining = settings.time_limit.saturating_sub(elapsed);
+    // Round up so we only hit 0 once the time is really up
+    Some(remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0))
+}
+
+/// Let the rest of a multiplayer race know how far along the user is, but only when something
+/// has changed so we don't flood the other racers.
+fn report_race_progress(
+    settings: &GameSettings,
+    reported_progress: &mut Option<(u16, u64, bool)>,
+    current_word_idx: usize,
+    num_words: usize,
+    stats: &stats::Stats,
+) {
+    let race = match settings.race {
+        Some(race) => race,
+        None => return,
+    };
+
+    let typed_words = current_word_idx.min(num_words);
+    let percent = (typed_words * 100 / num_words.max(1)) as u16;
+    let progress = (percent, stats.get_wpm(), typed_words == num_words);
+    if *reported_progress != Some(progress) {
+        race.report_progress(progress.0, progress.1, progress.2);
+        *reported_progress = Some(progress);
+    }
+}
+
+/// Grab the next passage to append in timed mode.
+/// Latin and NonLatin passages can't be mixed in one race, so we try a few times to find one that
+/// matches what the user is already typing, and go back around to the first passage if we can't.
+fn next_timed_passage(
+    passage_controller: &mut Controller,
+    text_mode: &word_processing::GameMode,
+    first_passage: &PassageInfo,
+) -> PassageInfo {
+    for _ in 0..10 {
+        let next_passage = passage_controller.retrieve_passage(Action::NextPassage);
+        if word_processing::get_game_mode(&next_passage.passage, next_passage.is_code())
+            == *text_mode
+        {
+            return next_passage.clone();
+        }
+    }
+    first_passage.clone()
+}
+
+fn current_title(titles: &[(usize, String)], current_word_idx: usize) -> &str {
+    titles
+        .iter()
+        .rev()
+        .find(|(starting_word_idx, _)| *starting_word_idx <= current_word_idx)
+        .map_or("", |(_, title)| title)
+}
+
 fn maybe_rebuild_db_clean(db_path: &Path, sqlite_err: &rusqlite::Error) {
     match game_db::rebuild_stats_db_if_ancient(db_path) {
         Ok(()) => {} // don't need to do anything, we have successfully recreated the database
@@ -0,0 +1,76 @@
+use std::fmt;
+
+/// The fastest the user has been in a game mode, before the race being played
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PersonalBests {
+    /// Across every passage
+    pub overall: Option<u64>,
+    /// Across the passages in the same pack
+    pub pack: Option<u64>,
+    /// On this passage
+    pub passage: Option<u64>,
+}
+
+/// Which personal best a race beat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Record {
+    Overall,
+    Pack,
+    Passage,
+}
+
+impl PersonalBests {
+    /// The widest personal best `wpm` beats, beating the overall best beats the others too.
+    /// There's nothing to beat the first time through a passage, so that doesn't count.
+    pub fn beaten_by(&self, wpm: u64) -> Option<Record> {
+        let beats = |best: Option<u64>| best.is_some_and(|best| wpm > best);
+        if beats(self.overall) {
+            Some(Record::Overall)
+        } else if beats(self.pack) {
+            Some(Record::Pack)
+        } else if beats(self.passage) {
+            Some(Record::Passage)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Record::Overall => write!(f, "NEW PB - fastest race ever!"),
+            Record::Pack => write!(f, "NEW PB - fastest in the pack!"),
+            Record::Passage => write!(f, "NEW PB - fastest on this passage!"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn widest_record_beaten_wins() {
+        let personal_bests = PersonalBests {
+            overall: Some(100),
+            pack: Some(80),
+            passage: Some(60),
+        };
+        assert_eq!(personal_bests.beaten_by(101), Some(Record::Overall));
+        assert_eq!(personal_bests.beaten_by(100), Some(Record::Pack));
+        assert_eq!(personal_bests.beaten_by(61), Some(Record::Passage));
+        assert_eq!(personal_bests.beaten_by(60), None);
+    }
+
+    #[test]
+    fn first_races_are_not_records() {
+        assert_eq!(PersonalBests::default().beaten_by(200), None);
+        let first_time_on_passage = PersonalBests {
+            overall: Some(100),
+            pack: Some(80),
+            passage: None,
+        };
+        assert_eq!(first_time_on_passage.beaten_by(70), None);
+    }
+}
@@ -0,0 +1,100 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    terminal::Terminal,
+    text::Span,
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table, TableState},
+};
+
+use crate::{game::replay::RaceSummary, report};
+
+pub fn render<B: Backend>(terminal: &mut Terminal<B>, races: &[RaceSummary], selected: usize) {
+    let now = report::now_secs();
+
+    terminal
+        .draw(|f| {
+            let padding_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
+
+            let main_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(80),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(padding_layout[1]);
+
+            let block = Block::default()
+                .title("Race history")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded);
+
+            if races.is_empty() {
+                f.render_widget(
+                    Paragraph::new("No races to replay yet! Finish a race and come back.")
+                        .alignment(Alignment::Center)
+                        .block(block),
+                    main_layout[1],
+                );
+            } else {
+                let rows = races.iter().map(|race| {
+                    Row::new(vec![
+                        race.id.to_string(),
+                        report::time_ago(now, race.when_played_secs),
+                        race.game_mode.to_string(),
+                        race.wpm.to_string(),
+                        format!("{:.1}%", race.accuracy),
+                        race.passage_path.clone(),
+                    ])
+                });
+                let mut table_state = TableState::default();
+                table_state.select(Some(selected));
+
+                f.render_stateful_widget(
+                    Table::new(rows)
+                        .header(
+                            Row::new(vec!["ID", "When", "Mode", "WPM", "Accuracy", "Passage"])
+                                .style(Style::default().fg(Color::Yellow)),
+                        )
+                        .block(block)
+                        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+                        .widths(
+                            [
+                                Constraint::Length(6),
+                                Constraint::Length(10),
+                                Constraint::Length(14),
+                                Constraint::Length(5),
+                                Constraint::Length(9),
+                                Constraint::Min(10),
+                            ]
+                            .as_ref(),
+                        ),
+                    main_layout[1],
+                    &mut table_state,
+                );
+            }
+
+            f.render_widget(
+                Paragraph::new(Span::raw("^C to go back  ⇕ pick a race  <enter> replay it"))
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::NONE)),
+                main_layout[2],
+            );
+        })
+        .expect("Failed to draw to terminal");
+}
@@ -0,0 +1,657 @@
+use crossbeam_channel::{unbounded, Receiver};
+use rusqlite::Connection;
+use std::{
+    collections::HashSet,
+    io::{stdout, Error, ErrorKind},
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+use termion::{event::Key, raw::IntoRawMode, screen::AlternateScreen};
+use tui::{
+    backend::{Backend, TermionBackend},
+    text::Span,
+    Terminal,
+};
+
+use crate::{
+    config::TyperacerConfig,
+    dirs::setup_dirs,
+    game::{
+        formatter::{self, FormattedTexts},
+        game_db, game_render, indexer,
+        keystrokes::{self, Keystroke},
+        split, word_processing, GameMode,
+    },
+    input,
+    passage_controller::{passage_file, PassageInfo},
+    stats::Stats,
+};
+
+mod history_render;
+
+/// Speeds a replay can be played back at
+const SPEEDS: [u32; 3] = [1, 2, 4];
+/// Number of races listed in the history browser
+const HISTORY_SIZE: usize = 50;
+const REPLAY_SHORTCUTS: &str = "^C back  <space> pause/play  1/2/4 speed  ^R start over";
+
+/// A race as it's stored in the stats db
+#[derive(Debug, Clone, PartialEq)]
+pub struct RaceSummary {
+    pub id: i64,
+    pub passage_path: String,
+    /// Length of the passage in bytes, only known for passages we keep stats on
+    pub passage_len: Option<i64>,
+    pub wpm: i64,
+    pub accuracy: f64,
+    pub game_mode: GameMode,
+    pub when_played_secs: i64,
+}
+
+/// Settings that change how keystrokes are handled, and with that which word each one lands in.
+/// Races don't store the settings they were played with, so a replay has to work out which ones
+/// line up with the keystroke log, see `pick_settings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ReplaySettings {
+    type_indentation: bool,
+    always_full: bool,
+}
+
+impl ReplaySettings {
+    fn from_config(config: &TyperacerConfig) -> Self {
+        ReplaySettings {
+            type_indentation: config.code_settings.type_indentation,
+            always_full: config.display_settings.always_full,
+        }
+    }
+}
+
+/// Where a race was at some point in time, rebuilt by typing its keystrokes out again
+struct Playback<'a> {
+    game_mode: GameMode,
+    text_mode: word_processing::GameMode,
+    settings: ReplaySettings,
+    words: Vec<&'a str>,
+    formatted_texts: FormattedTexts<'a>,
+    user_input: String,
+    current_word_idx: usize,
+    stats: Stats,
+    mistaken_words: HashSet<String>,
+    keystrokes: &'a [Keystroke],
+    /// Number of keystrokes played so far
+    played: usize,
+    /// Set once a keystroke is aimed somewhere other than where it was aimed in the race
+    off_track: bool,
+}
+
+impl<'a> Playback<'a> {
+    fn new(
+        passage_info: &'a PassageInfo,
+        keystrokes: &'a [Keystroke],
+        game_mode: GameMode,
+        settings: ReplaySettings,
+    ) -> Self {
+        let passage = passage_info.passage.as_str();
+        let is_code = passage_info.is_code();
+        Playback {
+            game_mode,
+            text_mode: word_processing::get_game_mode(passage, is_code),
+            settings,
+            words: split::to_words(passage, is_code),
+            formatted_texts: FormattedTexts {
+                passage: passage
+                    .chars()
+                    .map(|it| Span::raw(it.to_string()))
+                    .collect(),
+                input: vec![],
+                error: false,
+                complete: false,
+                ghost_idx: None,
+            },
+            user_input: String::new(),
+            current_word_idx: 0,
+            stats: Stats::new(false),
+            mistaken_words: HashSet::new(),
+            keystrokes,
+            played: 0,
+            off_track: false,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.played == self.keystrokes.len()
+    }
+
+    /// Play every keystroke typed in the first `millis` of the race
+    fn advance_to(&mut self, millis: u64) {
+        let keystrokes = self.keystrokes;
+        while let Some(keystroke) = keystrokes
+            .get(self.played)
+            .filter(|keystroke| keystroke.offset_millis <= millis)
+        {
+            self.play(keystroke);
+            self.played += 1;
+        }
+
+        // The clock stops with the last keystroke, same as it did in the race
+        let last_millis = keystrokes
+            .last()
+            .map_or(0, |keystroke| keystroke.offset_millis);
+        if self.played > 0 && !self.formatted_texts.complete {
+            self.stats
+                .update_wpm_at(millis.min(last_millis), self.current_word_idx, &self.words);
+        }
+    }
+
+    /// Handle a logged key the same way it was handled when the race was played
+    fn play(&mut self, keystroke: &Keystroke) {
+        if self.formatted_texts.complete || self.current_word_idx >= self.words.len() {
+            return;
+        }
+        let mut new_char = false;
+        let mut last_input_char = ' ';
+
+        match keystroke.typed {
+            keystrokes::CLEAR_LINE => self.user_input.clear(),
+            keystrokes::CLEAR_WORD => {
+                self.user_input = word_processing::get_all_input_minus_last_word(
+                    &self.text_mode,
+                    &self.user_input,
+                )
+            }
+            keystrokes::BACKSPACE => {
+                self.user_input.pop();
+            }
+            c => {
+                new_char = true;
+                last_input_char = c;
+                let position =
+                    indexer::get_starting_idx(&self.text_mode, &self.words, self.current_word_idx)
+                        + self.user_input.chars().count();
+                if position != keystroke.position {
+                    self.off_track = true;
+                }
+                let current_word = self.words[self.current_word_idx];
+                if word_processing::word_completed(
+                    &self.text_mode,
+                    c,
+                    word_processing::get_typed_part(
+                        &self.text_mode,
+                        current_word,
+                        self.settings.type_indentation,
+                    ),
+                    &self.user_input,
+                ) {
+                    if !self.settings.always_full {
+                        self.formatted_texts.passage = word_processing::get_updated_texts(
+                            &self.text_mode,
+                            std::mem::take(&mut self.formatted_texts.passage),
+                            current_word,
+                        );
+                    }
+                    self.current_word_idx += 1;
+                    self.user_input.clear();
+                } else {
+                    self.user_input.push(c);
+                }
+                self.stats.record_typed_char_at(keystroke.offset_millis);
+                self.stats.update_wpm_at(
+                    keystroke.offset_millis,
+                    self.current_word_idx,
+                    &self.words,
+                );
+            }
+        }
+
+        let passage = std::mem::take(&mut self.formatted_texts.passage);
+        self.formatted_texts = if self.current_word_idx >= self.words.len() {
+            FormattedTexts {
+                passage,
+                ..self.formatted_texts.clone()
+            }
+        } else if self.settings.always_full {
+            formatter::get_formatted_texts(
+                &self.text_mode,
+                &self.words,
+                &self.user_input,
+                self.current_word_idx,
+                last_input_char,
+                new_char,
+                passage,
+            )
+        } else {
+            formatter::get_formatted_texts_line_mode(
+                &self.text_mode,
+                self.words[self.current_word_idx],
+                &self.user_input,
+                last_input_char,
+                new_char,
+                passage,
+            )
+        };
+
+        let current_letter_idx = indexer::get_trying_letter_idx(
+            &self.text_mode,
+            &self.words,
+            self.current_word_idx,
+            &self.user_input,
+        );
+        self.stats
+            .update_uncorrected_errors(match self.words.get(self.current_word_idx) {
+                Some(word) => indexer::count_mismatched(
+                    word_processing::get_typed_part(
+                        &self.text_mode,
+                        word,
+                        self.settings.type_indentation,
+                    ),
+                    &self.user_input,
+                ),
+                None => 0,
+            });
+        if self.formatted_texts.error && new_char {
+            self.stats.increment_errors(current_letter_idx, None);
+            self.mistaken_words
+                .insert(self.words[self.current_word_idx].trim().to_string());
+
+            if self.game_mode == GameMode::InstantDeath {
+                self.formatted_texts =
+                    formatter::get_reformatted_failed_texts(&self.text_mode, &self.words);
+                return;
+            }
+        } else if new_char {
+            self.stats.increment_combo(current_letter_idx);
+        }
+
+        if self.game_mode != GameMode::Timed
+            && word_processing::decide_game_end(
+                &self.text_mode,
+                self.current_word_idx,
+                &self.words,
+                &self.user_input,
+            )
+        {
+            self.formatted_texts =
+                formatter::get_reformatted_complete_texts(&self.text_mode, &self.words);
+            self.current_word_idx += 1;
+            self.stats
+                .update_wpm_at(keystroke.offset_millis, self.current_word_idx, &self.words);
+            self.user_input.clear();
+        }
+    }
+}
+
+/// Replay a race straight from the command line, without starting a game.
+pub fn replay_from_db(
+    db_path: &Path,
+    race_id: i64,
+    config: &TyperacerConfig,
+    typeracer_version: &str,
+) -> Result<(), Error> {
+    let conn = Connection::open(db_path).map_err(Error::other)?;
+    let race = game_db::retrieve_race_summary(&conn, race_id)
+        .map_err(Error::other)?
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::NotFound,
+                format!(
+                    "There's no race with id {}, `typeracer stats` lists the ids of your recent races",
+                    race_id
+                ),
+            )
+        })?;
+    let keystrokes = game_db::retrieve_keystrokes(&conn, race_id).map_err(Error::other)?;
+    if keystrokes.is_empty() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!(
+                "Race {} was played before keystrokes were recorded, so there's nothing to replay",
+                race_id
+            ),
+        ));
+    }
+
+    let (input_sender, input_receiver) = unbounded::<Key>();
+    input::capture(input_sender);
+
+    let screen = AlternateScreen::from(stdout().into_raw_mode()?);
+    let mut terminal = Terminal::new(TermionBackend::new(screen))?;
+    terminal.hide_cursor()?;
+    replay_race(
+        &mut terminal,
+        &input_receiver,
+        &race,
+        &keystrokes,
+        config,
+        typeracer_version,
+    );
+    Ok(())
+}
+
+/// Let the user pick one of their recent races and watch it again.
+pub fn show_history<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input_receiver: &Receiver<Key>,
+    db_path: &Path,
+    config: &TyperacerConfig,
+    typeracer_version: &str,
+) -> Result<(), rusqlite::Error> {
+    let conn = Connection::open(db_path)?;
+    let races = game_db::retrieve_replayable_races(&conn, HISTORY_SIZE)?;
+    let mut selected = 0;
+
+    loop {
+        history_render::render(terminal, &races, selected);
+
+        match input_receiver.recv() {
+            Ok(Key::Ctrl('c')) | Err(_) => return Ok(()),
+            Ok(Key::Up) => selected = selected.saturating_sub(1),
+            Ok(Key::Down) => selected = (selected + 1).min(races.len().saturating_sub(1)),
+            Ok(Key::Char('\n')) => {
+                if let Some(race) = races.get(selected) {
+                    let keystrokes = game_db::retrieve_keystrokes(&conn, race.id)?;
+                    replay_race(
+                        terminal,
+                        input_receiver,
+                        race,
+                        &keystrokes,
+                        config,
+                        typeracer_version,
+                    );
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Watch a race play out again in real time, or faster.
+pub fn replay_race<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input_receiver: &Receiver<Key>,
+    race: &RaceSummary,
+    keystrokes: &[Keystroke],
+    config: &TyperacerConfig,
+    typeracer_version: &str,
+) {
+    let passage_info = load_passage(race, keystrokes);
+    let settings = pick_settings(&passage_info, keystrokes, race.game_mode, config);
+    let mut playback = Playback::new(&passage_info, keystrokes, race.game_mode, settings);
+    let mut speed_idx = 0;
+    let mut paused = false;
+    let mut replay_time = Duration::ZERO;
+    let mut last_tick = Instant::now();
+
+    loop {
+        let now = Instant::now();
+        if !paused {
+            replay_time += now.duration_since(last_tick) * SPEEDS[speed_idx];
+        }
+        last_tick = now;
+        playback.advance_to(replay_time.as_millis() as u64);
+
+        let status = if playback.finished() {
+            "done".to_owned()
+        } else if paused {
+            "paused".to_owned()
+        } else {
+            format!("{}x", SPEEDS[speed_idx])
+        };
+        let title = format!(
+            "{} - replay of race {} ({})",
+            passage_info.title, race.id, status
+        );
+        game_render::render(
+            terminal,
+            game_render::GameState {
+                texts: &playback.formatted_texts,
+                user_input: &playback.user_input,
+                stats: &playback.stats,
+                title: &title,
+                ghost_wpm: None,
+                average_wpm: None,
+                new_record: None,
+                game_mode: race.game_mode,
+                text_mode: playback.text_mode,
+                config,
+                debug_enabled: false,
+                word_idx: playback.current_word_idx,
+                passage_path: &passage_info.passage_path,
+                current_word: playback
+                    .words
+                    .get(playback.current_word_idx)
+                    .copied()
+                    .unwrap_or("DONE"),
+                mistaken_words: &playback.mistaken_words,
+                complete: playback.formatted_texts.complete,
+                time_left: None,
+                racers: &[],
+                shortcuts: Some(REPLAY_SHORTCUTS),
+            },
+            typeracer_version,
+        );
+
+        // Check back often enough that keystrokes show up close to when they were typed
+        match input_receiver.recv_timeout(Duration::from_millis(30)) {
+            Ok(Key::Ctrl('c')) => return,
+            Ok(Key::Char(' ')) => paused = !paused,
+            Ok(Key::Char('1')) => speed_idx = 0,
+            Ok(Key::Char('2')) => speed_idx = 1,
+            Ok(Key::Char('4')) => speed_idx = 2,
+            Ok(Key::Ctrl('r')) => {
+                playback = Playback::new(&passage_info, keystrokes, race.game_mode, settings);
+                replay_time = Duration::ZERO;
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Settings to play a race back with. The user's current settings are tried first, and if the
+/// keystrokes don't line up with them, the race was played with different settings so the others
+/// are tried. If nothing lines up (e.g. a passage rebuilt from its keystrokes) the current
+/// settings are used.
+fn pick_settings(
+    passage_info: &PassageInfo,
+    keystrokes: &[Keystroke],
+    game_mode: GameMode,
+    config: &TyperacerConfig,
+) -> ReplaySettings {
+    let current = ReplaySettings::from_config(config);
+    let others = [false, true].iter().flat_map(|&type_indentation| {
+        [false, true]
+            .iter()
+            .map(move |&always_full| ReplaySettings {
+                type_indentation,
+                always_full,
+            })
+    });
+    std::iter::once(current)
+        .chain(others.filter(|settings| *settings != current))
+        .find(|settings| {
+            let mut playback = Playback::new(passage_info, keystrokes, game_mode, *settings);
+            playback.advance_to(u64::MAX);
+            !playback.off_track
+        })
+        .unwrap_or(current)
+}
+
+/// The passage a race was played on. Passages from lang packs are read in again, anything else
+/// (or a pack passage that has changed since) is pieced back together from the characters the
+/// user was expected to type.
+fn load_passage(race: &RaceSummary, keystrokes: &[Keystroke]) -> PassageInfo {
+    let pack_passage = passage_file_path(
+        &setup_dirs::get_quote_dirs().main_pack_dir,
+        &race.passage_path,
+    );
+    match passage_file::read_passage_file(&pack_passage) {
+        // Races from before passage lengths were stored have nothing to compare, so trust the pack
+        Ok(passage_info)
+            if race
+                .passage_len
+                .unwrap_or(passage_info.passage.len() as i64)
+                == passage_info.passage.len() as i64 =>
+        {
+            passage_info
+        }
+        _ => PassageInfo {
+            passage: rebuild_passage(keystrokes),
+            title: format!("{} race", race.game_mode),
+            passage_path: race.passage_path.clone(),
+        },
+    }
+}
+
+/// Where a stored passage path points to. Passages from the main pack are stored relative to it,
+/// while passages from extra packs keep the full path they were read from.
+fn passage_file_path(main_pack_dir: &Path, passage_path: &str) -> PathBuf {
+    let stored_path = Path::new(passage_path);
+    // Main pack paths start with a slash too, so only a file that's really there is taken as-is
+    if stored_path.is_absolute() && stored_path.is_file() {
+        stored_path.to_owned()
+    } else {
+        main_pack_dir.join(passage_path.trim_start_matches('/'))
+    }
+}
+
+/// Every keystroke knows which character of the passage it was aimed at. Anything the user never
+/// had to type, like skipped indentation, is filled in with spaces.
+fn rebuild_passage(keystrokes: &[Keystroke]) -> String {
+    let mut passage: Vec<Option<char>> = vec![];
+    for keystroke in keystrokes {
+        if let Some(expected) = keystroke.expected {
+            if passage.len() <= keystroke.position {
+                passage.resize(keystroke.position + 1, None);
+            }
+            passage[keystroke.position] = Some(expected);
+        }
+    }
+    passage.into_iter().map(|c| c.unwrap_or(' ')).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    fn keystroke(offset_millis: u64, position: usize, expected: char, typed: char) -> Keystroke {
+        Keystroke {
+            offset_millis,
+            position,
+            expected: Some(expected),
+            typed,
+            correct: expected == typed,
+            backspace: typed == keystrokes::BACKSPACE,
+        }
+    }
+
+    /// "the cat" with a mistake on the a that gets fixed
+    fn keystrokes() -> Vec<Keystroke> {
+        vec![
+            keystroke(0, 0, 't', 't'),
+            keystroke(100, 1, 'h', 'h'),
+            keystroke(200, 2, 'e', 'e'),
+            keystroke(300, 3, ' ', ' '),
+            keystroke(400, 4, 'c', 'c'),
+            keystroke(500, 5, 'a', 's'),
+            keystroke(600, 5, 'a', keystrokes::BACKSPACE),
+            keystroke(700, 5, 'a', 'a'),
+            keystroke(800, 6, 't', 't'),
+        ]
+    }
+
+    fn the_cat() -> PassageInfo {
+        PassageInfo {
+            passage: "the cat".to_owned(),
+            title: "cat".to_owned(),
+            passage_path: "User input".to_owned(),
+        }
+    }
+
+    #[test]
+    fn passage_is_rebuilt_from_keystrokes() {
+        assert_eq!(rebuild_passage(&keystrokes()), "the cat");
+    }
+
+    #[test]
+    fn extra_pack_passages_are_found() {
+        let extra_pack = TempDir::new("replay-extra");
+        extra_pack.write("default/cat", "the cat\nCat");
+        let passage_path = extra_pack.join("default").join("cat");
+        let race = RaceSummary {
+            id: 1,
+            passage_path: passage_path.to_string_lossy().into_owned(),
+            passage_len: Some(7),
+            wpm: 120,
+            accuracy: 100.0,
+            game_mode: GameMode::Default,
+            when_played_secs: 0,
+        };
+
+        let passage_info = load_passage(&race, &keystrokes());
+
+        assert_eq!(passage_info.title, "Cat");
+        assert_eq!(
+            passage_file_path(Path::new("/main"), "/default/cat"),
+            Path::new("/main").join("default").join("cat")
+        );
+    }
+
+    #[test]
+    fn playback_follows_the_race() {
+        let config = TyperacerConfig::default();
+        let cat = the_cat();
+        let keystrokes = keystrokes();
+        let mut playback = Playback::new(
+            &cat,
+            &keystrokes,
+            GameMode::Default,
+            ReplaySettings::from_config(&config),
+        );
+
+        playback.advance_to(550);
+        assert_eq!(playback.current_word_idx, 1);
+        assert_eq!(playback.user_input, "cs");
+        assert!(playback.formatted_texts.error);
+        assert!(!playback.finished());
+
+        playback.advance_to(10_000);
+        assert!(playback.finished());
+        assert!(playback.formatted_texts.complete);
+        assert_eq!(playback.stats.errors, 1);
+        // 8 characters (counting the space after the last word) in 0.8 seconds
+        assert_eq!(playback.stats.get_wpm(), 120);
+    }
+
+    #[test]
+    fn settings_follow_the_keystrokes() {
+        let config = TyperacerConfig::default();
+        let code = PassageInfo {
+            passage: "a {\n  b\n}".to_owned(),
+            title: "code".to_owned(),
+            passage_path: "User input".to_owned(),
+        };
+        // Played with indentation typed out, which isn't the default
+        let keystrokes: Vec<Keystroke> = "a {\n  b\n}"
+            .chars()
+            .enumerate()
+            .map(|(position, c)| keystroke(position as u64 * 100, position, c, c))
+            .collect();
+
+        let mut current = Playback::new(
+            &code,
+            &keystrokes,
+            GameMode::Default,
+            ReplaySettings::from_config(&config),
+        );
+        current.advance_to(u64::MAX);
+        let settings = pick_settings(&code, &keystrokes, GameMode::Default, &config);
+        let mut playback = Playback::new(&code, &keystrokes, GameMode::Default, settings);
+        playback.advance_to(u64::MAX);
+
+        assert!(current.off_track);
+        assert!(settings.type_indentation);
+        assert!(!playback.off_track);
+        assert!(playback.formatted_texts.complete);
+        assert_eq!(playback.stats.errors, 0);
+    }
+}
@@ -0,0 +1,158 @@
+use crate::{
+    game::{
+        personal_bests::{PersonalBests, Record},
+        GameMode,
+    },
+    goals::GoalProgress,
+    multiplayer::PlayerProgress,
+    stats::Stats,
+};
+
+mod results_render;
+
+pub use results_render::render;
+
+/// Number of recent races in a pack the user's speed is compared against
+pub const PACK_AVERAGE_RACES: usize = 10;
+
+/// A word the user got wrong, with the furthest they got typing it before fixing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MistypedWord {
+    /// Index of the word in the passage, so typing the same word twice counts twice
+    pub word_idx: usize,
+    pub word: String,
+    pub typed: String,
+}
+
+/// Everything shown on the results screen once a race is over
+#[derive(Debug, Clone)]
+pub struct RaceResults {
+    pub game_mode: GameMode,
+    pub title: String,
+    pub wpm: u64,
+    pub raw_wpm: u64,
+    pub accuracy: f64,
+    pub elapsed_millis: u64,
+    pub highest_combo: usize,
+    /// Set when the race was lost to a mistake in instant death
+    pub failed: bool,
+    pub mistyped_words: Vec<MistypedWord>,
+    /// The user's bests from before this race
+    pub personal_bests: PersonalBests,
+    /// The pack the passage is from, None for passages that aren't from one
+    pub pack: Option<String>,
+    /// Average wpm over the last `PACK_AVERAGE_RACES` races in the pack, before this race
+    pub pack_average: Option<f64>,
+    /// Today's goals, counting this race
+    pub goal_progress: Option<GoalProgress>,
+}
+
+impl RaceResults {
+    pub fn new(stats: &Stats, game_mode: GameMode, title: &str) -> Self {
+        RaceResults {
+            game_mode,
+            title: title.to_owned(),
+            wpm: stats.get_wpm(),
+            raw_wpm: stats.get_raw_wpm(),
+            accuracy: stats.get_typing_accuracy(),
+            elapsed_millis: stats.get_elapsed_millis(),
+            highest_combo: stats.get_highest_combo(),
+            failed: false,
+            mistyped_words: vec![],
+            personal_bests: PersonalBests::default(),
+            pack: None,
+            pack_average: None,
+            goal_progress: None,
+        }
+    }
+
+    pub fn heading(&self) -> &'static str {
+        if self.failed {
+            "Instant death!"
+        } else if self.game_mode == GameMode::Timed {
+            "Time's up!"
+        } else {
+            "Race complete!"
+        }
+    }
+
+    /// The personal best this race beat, if it beat one. Losing in instant death doesn't count.
+    pub fn new_record(&self) -> Option<Record> {
+        if self.failed {
+            None
+        } else {
+            self.personal_bests.beaten_by(self.wpm)
+        }
+    }
+}
+
+/// Remember what the user typed while they were stuck on a word.
+/// Keeps the longest wrong input for each word, which is usually the whole mistake rather than the
+/// first wrong character.
+pub fn record_mistyped_word(
+    mistyped_words: &mut Vec<MistypedWord>,
+    word_idx: usize,
+    word: &str,
+    typed: &str,
+) {
+    match mistyped_words.last_mut() {
+        Some(last) if last.word_idx == word_idx => {
+            if typed.chars().count() > last.typed.chars().count() {
+                last.typed = typed.to_owned();
+            }
+        }
+        _ => mistyped_words.push(MistypedWord {
+            word_idx,
+            word: word.to_owned(),
+            typed: typed.to_owned(),
+        }),
+    }
+}
+
+/// How far ahead or behind `wpm` is of what it's compared to, e.g. "+4" or "-12"
+pub fn wpm_difference(wpm: u64, compared_to: f64) -> String {
+    format!("{:+.0}", wpm as f64 - compared_to)
+}
+
+/// Other racers sorted by how they did, finishers first
+pub fn standings(racers: &[PlayerProgress]) -> Vec<PlayerProgress> {
+    let mut standings = racers.to_vec();
+    standings.sort_by(|a, b| {
+        b.finished
+            .cmp(&a.finished)
+            .then(b.percent.cmp(&a.percent))
+            .then(b.wpm.cmp(&a.wpm))
+    });
+    standings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn longest_mistake_per_word_is_kept() {
+        let mut mistyped_words = vec![];
+        record_mistyped_word(&mut mistyped_words, 1, "quick", "qi");
+        record_mistyped_word(&mut mistyped_words, 1, "quick", "qiu");
+        // Deleting part of the mistake doesn't lose what was typed
+        record_mistyped_word(&mut mistyped_words, 1, "quick", "qj");
+        record_mistyped_word(&mut mistyped_words, 4, "the", "teh");
+        record_mistyped_word(&mut mistyped_words, 7, "the", "hte");
+
+        assert_eq!(
+            mistyped_words
+                .iter()
+                .map(|mistyped| (mistyped.word.as_str(), mistyped.typed.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("quick", "qiu"), ("the", "teh"), ("the", "hte")]
+        );
+    }
+
+    #[test]
+    fn differences_are_signed() {
+        assert_eq!(wpm_difference(80, 75.6), "+4");
+        assert_eq!(wpm_difference(60, 72.0), "-12");
+        assert_eq!(wpm_difference(60, 60.0), "+0");
+    }
+}
@@ -0,0 +1,271 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    terminal::Terminal,
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Row, Table},
+};
+
+use crate::{
+    game::{
+        achievements::Achievement,
+        game_render::toast::AchievementToast,
+        results::{self, RaceResults},
+        GameMode,
+    },
+    multiplayer::PlayerProgress,
+};
+
+const RESULTS_SHORTCUTS: &str = "<enter>/^N next passage  ^R restart  ^P previous  ^C exit\n^G[raphs]  ^K[eyboard heatmap]  ^B[rowse history]  ^L[eaderboard]  ^T[rophies]  ^A[bout/docs]";
+
+pub fn render<B: Backend>(
+    terminal: &mut Terminal<B>,
+    race_results: &RaceResults,
+    racers: &[PlayerProgress],
+    toasts: &[Achievement],
+    typeracer_version: &str,
+) {
+    terminal
+        .draw(|f| {
+            let padding_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
+
+            let main_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(80),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(padding_layout[1]);
+
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(main_layout[1]);
+
+            let left_column = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(if racers.is_empty() {
+                    [Constraint::Percentage(100), Constraint::Percentage(0)]
+                } else {
+                    [Constraint::Percentage(60), Constraint::Percentage(40)]
+                })
+                .split(columns[0]);
+
+            f.render_widget(
+                Paragraph::new(summary_lines(race_results)).block(
+                    Block::default()
+                        .title(format!(
+                            "{} - {}",
+                            race_results.heading(),
+                            race_results.title
+                        ))
+                        .borders(Borders::ALL)
+                        .border_type(BorderType::Rounded)
+                        .border_style(borders(race_results.game_mode)),
+                ),
+                left_column[0],
+            );
+
+            if !racers.is_empty() {
+                f.render_widget(
+                    Table::new(results::standings(racers).into_iter().enumerate().map(
+                        |(place, racer)| {
+                            Row::new(vec![
+                                format!("{}.", place + 1),
+                                racer.name,
+                                format!("{} WPM", racer.wpm),
+                                if racer.finished {
+                                    "finished".to_owned()
+                                } else {
+                                    format!("{}%", racer.percent)
+                                },
+                            ])
+                        },
+                    ))
+                    .block(
+                        Block::default()
+                            .title("Race")
+                            .borders(Borders::ALL)
+                            .border_type(BorderType::Rounded)
+                            .border_style(borders(race_results.game_mode)),
+                    )
+                    .widths(&[
+                        Constraint::Length(3),
+                        Constraint::Percentage(50),
+                        Constraint::Length(8),
+                        Constraint::Length(8),
+                    ]),
+                    left_column[1],
+                );
+            }
+
+            let mistakes_block = Block::default()
+                .title("Mistyped words")
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(borders(race_results.game_mode));
+            if race_results.mistyped_words.is_empty() {
+                f.render_widget(
+                    Paragraph::new("Not a single mistake!")
+                        .alignment(Alignment::Center)
+                        .block(mistakes_block),
+                    columns[1],
+                );
+            } else {
+                f.render_widget(
+                    Table::new(race_results.mistyped_words.iter().map(|mistyped| {
+                        Row::new(vec![mistyped.word.clone(), visible_spaces(&mistyped.typed)])
+                    }))
+                    .header(
+                        Row::new(vec!["Word", "You typed"])
+                            .style(Style::default().add_modifier(Modifier::BOLD)),
+                    )
+                    .block(mistakes_block)
+                    .widths(&[Constraint::Percentage(50), Constraint::Percentage(50)]),
+                    columns[1],
+                );
+            }
+
+            f.render_widget(
+                Paragraph::new(RESULTS_SHORTCUTS).alignment(Alignment::Center),
+                main_layout[2],
+            );
+            f.render_widget(
+                Paragraph::new(Span::styled(
+                    format!("Build: {}", typeracer_version),
+                    Style::default().fg(Color::Gray),
+                ))
+                .alignment(Alignment::Center),
+                main_layout[3],
+            );
+
+            if !toasts.is_empty() {
+                let toast = AchievementToast {
+                    achievements: toasts,
+                };
+                let toast_area = toast.area(f.size());
+                f.render_widget(toast, toast_area);
+            }
+        })
+        .expect("Failed to draw to terminal");
+}
+
+fn summary_lines(race_results: &RaceResults) -> Vec<Spans<'static>> {
+    let mut lines = vec![];
+    if let Some(record) = race_results.new_record() {
+        lines.push(Spans::from(Span::styled(
+            record.to_string(),
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD | Modifier::SLOW_BLINK),
+        )));
+        lines.push(Spans::from(""));
+    }
+    lines.extend([
+        stat_line("WPM", race_results.wpm.to_string()),
+        stat_line("Raw WPM", race_results.raw_wpm.to_string()),
+        stat_line("Accuracy", format!("{:.1}%", race_results.accuracy)),
+        stat_line(
+            "Time",
+            format!("{:.1}s", race_results.elapsed_millis as f64 / 1000.0),
+        ),
+        stat_line("Best combo", race_results.highest_combo.to_string()),
+        Spans::from(""),
+    ]);
+
+    let personal_bests = race_results.personal_bests;
+    for (name, best) in [
+        ("Passage best", personal_bests.passage),
+        ("Pack best", personal_bests.pack),
+        ("Overall best", personal_bests.overall),
+    ] {
+        lines.push(match best {
+            Some(best) => {
+                comparison_line(name, format!("{} WPM", best), race_results.wpm, best as f64)
+            }
+            None => stat_line(name, "-".to_owned()),
+        });
+    }
+
+    let pack = race_results
+        .pack
+        .clone()
+        .unwrap_or_else(|| race_results.game_mode.to_string());
+    let average_label = format!("{} average", pack);
+    lines.push(match race_results.pack_average {
+        Some(average) => comparison_line(
+            &average_label,
+            format!("{:.0} WPM", average),
+            race_results.wpm,
+            average,
+        ),
+        None => stat_line(&average_label, "-".to_owned()),
+    });
+
+    if let Some(progress) = &race_results.goal_progress {
+        let style = Style::default().fg(if progress.goals_met_today {
+            Color::Green
+        } else {
+            Color::Yellow
+        });
+        lines.push(Spans::from(""));
+        for line in progress.lines() {
+            lines.push(Spans::from(Span::styled(line, style)));
+        }
+    }
+    lines
+}
+
+fn stat_line(name: &str, value: String) -> Spans<'static> {
+    Spans::from(vec![
+        Span::styled(
+            format!("{:<16}", name),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+        Span::raw(value),
+    ])
+}
+
+/// A line comparing the user's wpm against something, green when they beat it and red when they
+/// didn't
+fn comparison_line(name: &str, value: String, wpm: u64, compared_to: f64) -> Spans<'static> {
+    let color = if wpm as f64 >= compared_to {
+        Color::Green
+    } else {
+        Color::Red
+    };
+    let mut spans = stat_line(name, value).0;
+    spans.push(Span::styled(
+        format!(" ({})", results::wpm_difference(wpm, compared_to)),
+        Style::default().fg(color),
+    ));
+    Spans::from(spans)
+}
+
+fn visible_spaces(typed: &str) -> String {
+    typed.replace(' ', "␣")
+}
+
+fn borders(game_mode: GameMode) -> Style {
+    if game_mode == GameMode::InstantDeath {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    }
+}
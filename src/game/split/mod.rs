@@ -1,10 +1,16 @@
 use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthStr;
 
-pub fn to_words(passage: &str) -> Vec<&str> {
-    // We're working with multi-width characters. The input is likely
-    // a word per character rather than split on space.
-    if is_wide_character(passage) {
+/// Number of spaces a tab is swapped out for in code passages
+const TAB_WIDTH: usize = 4;
+
+pub fn to_words(passage: &str, is_code: bool) -> Vec<&str> {
+    if is_code {
+        // Only code keeps its line breaks, the user needs to type them.
+        to_code_words(passage)
+    } else if is_wide_character(passage) {
+        // We're working with multi-width characters. The input is likely
+        // a word per character rather than split on space.
         UnicodeSegmentation::graphemes(passage, true).collect::<Vec<&str>>()
     } else {
         passage.split_whitespace().collect()
@@ -14,7 +20,8 @@ pub fn to_words(passage: &str) -> Vec<&str> {
 pub fn join_to_passage(words: &[&str]) -> String {
     match words.first() {
         Some(s) => {
-            if is_wide_character(s) {
+            // Code words carry their own whitespace
+            if is_wide_character(s) || s.ends_with(char::is_whitespace) {
                 words.join("")
             } else {
                 words.join(" ")
@@ -33,6 +40,58 @@ pub fn is_wide_character(passage: &str) -> bool {
         == 2
 }
 
+/// Split code into words where each word keeps the whitespace following it, so the passage
+/// can be put back together with its line breaks and indentation intact.
+/// e.g. "fn main() {\n    x\n}" becomes ["fn ", "main() ", "{\n    ", "x\n", "}"]
+fn to_code_words(passage: &str) -> Vec<&str> {
+    let mut words = vec![];
+    let mut word_start = 0;
+    let mut in_whitespace = false;
+    for (idx, c) in passage.char_indices() {
+        if c.is_whitespace() {
+            in_whitespace = true;
+        } else if in_whitespace {
+            words.push(&passage[word_start..idx]);
+            word_start = idx;
+            in_whitespace = false;
+        }
+    }
+    if word_start < passage.len() {
+        words.push(&passage[word_start..]);
+    }
+    words
+}
+
+/// Clean up code so it's fair to type: tabs become spaces, trailing whitespace and surrounding
+/// blank lines are dropped, and indentation shared by every line is removed.
+pub fn tidy_code(raw_code: &str) -> String {
+    let lines: Vec<String> = raw_code
+        .lines()
+        .map(|line| {
+            line.replace('\t', &" ".repeat(TAB_WIDTH))
+                .trim_end()
+                .to_owned()
+        })
+        .skip_while(|line| line.is_empty())
+        .collect();
+    let last_line = lines
+        .iter()
+        .rposition(|line| !line.is_empty())
+        .map_or(0, |idx| idx + 1);
+    let shared_indent = lines
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    lines[..last_line]
+        .iter()
+        .map(|line| line.get(shared_indent..).unwrap_or(""))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,7 +101,7 @@ mod tests {
         let passage = "the quick brown fox";
         let expected = ["the", "quick", "brown", "fox"];
 
-        assert_eq!(to_words(passage), expected);
+        assert_eq!(to_words(passage, false), expected);
     }
 
     #[test]
@@ -50,7 +109,7 @@ mod tests {
         let passage = "你好你好你好";
         let expected = ["你", "好", "你", "好", "你", "好"];
 
-        assert_eq!(to_words(passage), expected);
+        assert_eq!(to_words(passage, false), expected);
     }
 
     #[test]
@@ -68,4 +127,21 @@ mod tests {
 
         assert_eq!(join_to_passage(&split), expected);
     }
+
+    #[test]
+    fn test_to_words_code() {
+        let passage = "fn main() {\n    x\n\n}";
+        let expected = ["fn ", "main() ", "{\n    ", "x\n\n", "}"];
+
+        assert_eq!(to_words(passage, true), expected);
+        assert_eq!(join_to_passage(&to_words(passage, true)), passage);
+    }
+
+    #[test]
+    fn test_tidy_code() {
+        let raw_code = "\n\n    if x {\n    \ty(); \n\n    }\n\n";
+        let expected = "if x {\n    y();\n\n}";
+
+        assert_eq!(tidy_code(raw_code), expected);
+    }
 }
@@ -2,17 +2,19 @@ use tui::text::Span;
 
 use crate::game::split;
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
 pub enum GameMode {
     Latin,
     NonLatin,
+    /// Source code, typed out line by line with newlines and indentation kept
+    Code,
 }
 
 /// This is effectively functionality for C-w. It removes the last word in a passage.
 /// If there is a trailing space, it will remove that AND the word.
 /// The goal is to mimic readline C-w as much as possible.
-pub fn get_all_input_minus_last_word(input: &str) -> String {
-    let words = split::to_words(input);
+pub fn get_all_input_minus_last_word(game_mode: &GameMode, input: &str) -> String {
+    let words = split::to_words(input, *game_mode == GameMode::Code);
     if words.is_empty() {
         input.to_owned()
     } else {
@@ -32,14 +34,18 @@ pub fn decide_game_end(
     if *game_mode == GameMode::Latin {
         decide_game_latin(current_word_idx, words, words[current_word_idx], user_input)
     } else {
+        // Code words complete on their last character just like NonLatin words do
         decide_game_nonlatin(words, current_word_idx, user_input)
     }
 }
 
 /// Retrieves `GameMode` based on the character encoding of the first character.
 /// If the character is wide it is assumed to be NonLatin, otherwise we assume Latin.
-pub fn get_game_mode(raw_passage: &str) -> GameMode {
-    if split::is_wide_character(raw_passage) {
+/// Code passages are always Code, see `PassageInfo::is_code`.
+pub fn get_game_mode(raw_passage: &str, is_code: bool) -> GameMode {
+    if is_code {
+        GameMode::Code
+    } else if split::is_wide_character(raw_passage) {
         GameMode::NonLatin
     } else {
         GameMode::Latin
@@ -48,6 +54,7 @@ pub fn get_game_mode(raw_passage: &str) -> GameMode {
 
 /// Decide if current word has been completed by user.
 /// We trigger word completion for latin and non-latin words differently.
+/// Code words include their trailing whitespace, so they complete the same way NonLatin words do.
 pub fn word_completed(game_mode: &GameMode, c: char, current_word: &str, user_input: &str) -> bool {
     if *game_mode == GameMode::Latin {
         latin_word_complete(c, current_word, user_input)
@@ -65,10 +72,22 @@ pub fn get_updated_texts<'a>(
     passage: Vec<Span<'a>>,
     current_word: &str,
 ) -> Vec<Span<'a>> {
-    if *game_mode == GameMode::Latin {
-        get_updated_latin_texts(passage, current_word)
-    } else {
-        get_updated_nonlatin_texts(passage)
+    match game_mode {
+        GameMode::Latin => get_updated_latin_texts(passage, current_word),
+        GameMode::NonLatin => get_updated_nonlatin_texts(passage),
+        GameMode::Code => get_updated_code_texts(passage, current_word),
+    }
+}
+
+/// The part of a Code word the user has to type to complete it.
+/// Unless the user wants to type indentation, the indentation at the start of the next line is
+/// skipped for them once they hit enter.
+pub fn get_typed_part<'a>(game_mode: &GameMode, word: &'a str, type_indentation: bool) -> &'a str {
+    match word.rfind('\n') {
+        Some(newline_idx) if *game_mode == GameMode::Code && !type_indentation => {
+            &word[..=newline_idx]
+        }
+        _ => word,
     }
 }
 
@@ -109,6 +128,11 @@ fn get_updated_nonlatin_texts(passage: Vec<Span>) -> Vec<Span> {
     passage[1..passage.len()].to_vec()
 }
 
+/// Code words carry their own whitespace, so only the word itself is removed.
+fn get_updated_code_texts<'a>(passage: Vec<Span<'a>>, current_word: &str) -> Vec<Span<'a>> {
+    passage[current_word.chars().count()..passage.len()].to_vec()
+}
+
 /// Determine if a Latin word is completed.
 /// All Latin words are delineated with a space, so we check to see if the user
 /// has entered <space> to decide if they have completed the word.
@@ -207,25 +231,25 @@ mod tests {
     #[test]
     fn get_game_mode_interprets_latin() {
         let mut raw_passage = "the quick brown bubber";
-        assert!(get_game_mode(raw_passage) == GameMode::Latin);
+        assert!(get_game_mode(raw_passage, false) == GameMode::Latin);
 
         raw_passage = "él zorro marrón rápido salta sobre el perro perezoso";
-        assert!(get_game_mode(raw_passage) == GameMode::Latin);
+        assert!(get_game_mode(raw_passage, false) == GameMode::Latin);
 
         raw_passage = "πᾶν γράμμα";
-        assert!(get_game_mode(raw_passage) == GameMode::Latin);
+        assert!(get_game_mode(raw_passage, false) == GameMode::Latin);
 
         raw_passage = "क्विक ब्राउन फ़ॉक्स";
-        assert!(get_game_mode(raw_passage) == GameMode::Latin);
+        assert!(get_game_mode(raw_passage, false) == GameMode::Latin);
     }
 
     #[test]
     fn get_game_mode_interprets_nonlatin() {
         let mut raw_passage = "你好";
-        assert!(get_game_mode(raw_passage) == GameMode::NonLatin);
+        assert!(get_game_mode(raw_passage, false) == GameMode::NonLatin);
 
         raw_passage = "速い茶色のキツネ";
-        assert!(get_game_mode(raw_passage) == GameMode::NonLatin);
+        assert!(get_game_mode(raw_passage, false) == GameMode::NonLatin);
     }
 
     #[test]
@@ -359,27 +383,78 @@ mod tests {
         let input = "this is a test ";
         let expected = "this is a";
 
-        assert_eq!(get_all_input_minus_last_word(input), expected);
+        assert_eq!(
+            get_all_input_minus_last_word(&GameMode::Latin, input),
+            expected
+        );
 
         // empty case
         let input = "";
         let expected = "";
 
-        assert_eq!(get_all_input_minus_last_word(input), expected);
+        assert_eq!(
+            get_all_input_minus_last_word(&GameMode::Latin, input),
+            expected
+        );
 
         let input = "él zorro marrón rápido salta sobre el perro";
         let expected = "él zorro marrón rápido salta sobre el";
-        assert_eq!(get_all_input_minus_last_word(input), expected);
+        assert_eq!(
+            get_all_input_minus_last_word(&GameMode::Latin, input),
+            expected
+        );
     }
 
     #[test]
     fn removes_last_word_nonlatin() {
         let input = "你好";
         let expected = "你";
-        assert_eq!(get_all_input_minus_last_word(input), expected);
+        assert_eq!(
+            get_all_input_minus_last_word(&GameMode::NonLatin, input),
+            expected
+        );
 
         let input = "速い茶色のキツネ";
         let expected = "速い茶色のキツ";
-        assert_eq!(get_all_input_minus_last_word(input), expected);
+        assert_eq!(
+            get_all_input_minus_last_word(&GameMode::NonLatin, input),
+            expected
+        );
+    }
+
+    #[test]
+    fn removes_last_word_code() {
+        // The whitespace after a code word belongs to it, so it stays
+        let input = "fn main";
+        let expected = "fn ";
+        assert_eq!(
+            get_all_input_minus_last_word(&GameMode::Code, input),
+            expected
+        );
+    }
+
+    #[test]
+    fn code_word_completed() {
+        let current_word = "{\n";
+
+        assert!(!word_completed(&GameMode::Code, ' ', current_word, "{"));
+        assert!(word_completed(&GameMode::Code, '\n', current_word, "{"));
+    }
+
+    #[test]
+    fn get_game_mode_interprets_code() {
+        assert!(get_game_mode("fn main() {\n}", true) == GameMode::Code);
+        // Code packs can have passages that fit on one line
+        assert!(get_game_mode("x += 1;", true) == GameMode::Code);
+    }
+
+    #[test]
+    fn code_indentation_is_skipped() {
+        let word = "{\n    ";
+
+        assert_eq!(get_typed_part(&GameMode::Code, word, false), "{\n");
+        assert_eq!(get_typed_part(&GameMode::Code, word, true), word);
+        assert_eq!(get_typed_part(&GameMode::Code, "main() ", false), "main() ");
+        assert_eq!(get_typed_part(&GameMode::Latin, word, false), word);
     }
 }
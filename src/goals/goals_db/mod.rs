@@ -0,0 +1,83 @@
+use rusqlite::{params, Connection, Result};
+
+use crate::goals::DayTotals;
+
+/// Days since the unix epoch of the local midnight before a time, so races are split into days the
+/// same way the user's clock splits them
+const LOCAL_DAY: &str = "CAST(strftime('%s', {}, 'localtime', 'start of day') AS INTEGER) / 86400";
+
+fn local_day(time: &str) -> String {
+    LOCAL_DAY.replace("{}", time)
+}
+
+/// Totals for every day the user has raced on, oldest first.
+pub fn retrieve_day_totals(conn: &Connection) -> Result<Vec<DayTotals>, rusqlite::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} AS day, COUNT(*), COALESCE(SUM(elapsed_millis), 0), MAX(wpm)
+            FROM passage_stats
+            GROUP BY day
+            ORDER BY day ASC",
+        local_day("when_played_secs, 'unixepoch'")
+    ))?;
+
+    let days = stmt.query_map(params![], |row| {
+        Ok(DayTotals {
+            day: row.get(0)?,
+            races: row.get::<_, i64>(1)? as usize,
+            millis: row.get::<_, i64>(2)? as u64,
+            best_wpm: row.get::<_, Option<i64>>(3)?.unwrap_or(0) as u64,
+        })
+    })?;
+
+    days.collect()
+}
+
+/// Today, counted the same way as the days in `retrieve_day_totals`
+pub fn retrieve_today(conn: &Connection) -> Result<i64, rusqlite::Error> {
+    conn.query_row(
+        &format!("SELECT {}", local_day("'now'")),
+        params![],
+        |row| row.get(0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDb;
+
+    #[test]
+    fn races_are_totalled_by_day() {
+        let db = TempDb::new("goals");
+        let db_path = db.path();
+        let conn = Connection::open(&db_path).unwrap();
+        // Midday UTC, so the first two races land on the same day in any timezone
+        let noon = 19_000 * 86_400 + 12 * 3600;
+        conn.execute_batch(&format!(
+            "INSERT INTO passages (passage, passage_len) VALUES ('/default/a', 2);
+            INSERT INTO passage_stats (passage, wpm, game_mode, when_played_secs, elapsed_millis)
+                VALUES ('/default/a', 60, 0, {noon}, 30000),
+                       ('/default/a', 75, 0, {later}, NULL),
+                       ('/default/a', 50, 0, {days_later}, 45000);",
+            noon = noon,
+            later = noon + 60,
+            days_later = noon + 3 * 86_400,
+        ))
+        .unwrap();
+
+        let days = retrieve_day_totals(&conn).unwrap();
+        let today = retrieve_today(&conn).unwrap();
+
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[1].day - days[0].day, 3);
+        assert_eq!(
+            (days[0].races, days[0].millis, days[0].best_wpm),
+            (2, 30_000, 75)
+        );
+        assert_eq!(
+            (days[1].races, days[1].millis, days[1].best_wpm),
+            (1, 45_000, 50)
+        );
+        assert!(today > days[1].day);
+    }
+}
@@ -0,0 +1,221 @@
+use rusqlite::Connection;
+use serde::Serialize;
+use std::{io::Error, path::Path};
+
+use crate::config::Goals;
+
+pub mod goals_db;
+
+/// Everything the user played on one day, split at midnight local time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DayTotals {
+    /// Days since the unix epoch
+    pub day: i64,
+    pub races: usize,
+    /// Only counts races played after race times were tracked
+    pub millis: u64,
+    pub best_wpm: u64,
+}
+
+/// How far along the user is with today's goals, and how many days in a row they've met them
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GoalProgress {
+    pub races_today: usize,
+    pub races_goal: Option<usize>,
+    pub minutes_today: f64,
+    pub minutes_goal: Option<u64>,
+    /// None until the user has raced today
+    pub best_wpm_today: Option<u64>,
+    pub wpm_goal: Option<u64>,
+    pub goals_met_today: bool,
+    /// Days in a row the goals were met, up to today. Not meeting them yet today doesn't break it.
+    pub current_streak: usize,
+    pub longest_streak: usize,
+}
+
+impl GoalProgress {
+    /// Progress toward each goal followed by the streak, e.g. "Races today: 3/5"
+    pub fn lines(&self) -> Vec<String> {
+        let of_goal =
+            |goal: Option<String>| goal.map_or("".to_owned(), |goal| format!("/{}", goal));
+        let mut lines = vec![
+            format!(
+                "Races today: {}{}",
+                self.races_today,
+                of_goal(self.races_goal.map(|races| races.to_string()))
+            ),
+            format!(
+                "Minutes today: {:.1}{}",
+                self.minutes_today,
+                of_goal(self.minutes_goal.map(|minutes| minutes.to_string()))
+            ),
+        ];
+        if self.wpm_goal.is_some() || self.best_wpm_today.is_some() {
+            lines.push(format!(
+                "Best WPM today: {}{}",
+                self.best_wpm_today
+                    .map_or("-".to_owned(), |wpm| wpm.to_string()),
+                of_goal(self.wpm_goal.map(|wpm| wpm.to_string()))
+            ));
+        }
+        lines.push(format!(
+            "Streak: {} (longest {}){}",
+            days(self.current_streak),
+            self.longest_streak,
+            if self.goals_met_today {
+                " - done for today!"
+            } else {
+                ""
+            }
+        ));
+        lines
+    }
+}
+
+fn days(count: usize) -> String {
+    if count == 1 {
+        "1 day".to_owned()
+    } else {
+        format!("{} days", count)
+    }
+}
+
+/// Look up how the user is doing on their goals today.
+pub fn todays_progress(db_path: &Path, goals: &Goals) -> Result<GoalProgress, Error> {
+    let conn = Connection::open(db_path).map_err(Error::other)?;
+    let days = goals_db::retrieve_day_totals(&conn).map_err(Error::other)?;
+    let today = goals_db::retrieve_today(&conn).map_err(Error::other)?;
+    Ok(progress(&days, today, goals))
+}
+
+/// `days` must be sorted oldest first.
+pub fn progress(days: &[DayTotals], today: i64, goals: &Goals) -> GoalProgress {
+    let today_totals = days
+        .iter()
+        .find(|totals| totals.day == today)
+        .copied()
+        .unwrap_or(DayTotals {
+            day: today,
+            ..DayTotals::default()
+        });
+
+    let mut longest_streak = 0;
+    let mut streak = 0;
+    let mut last_met: Option<i64> = None;
+    for totals in days.iter().filter(|totals| goals_met(goals, totals)) {
+        streak = if last_met == Some(totals.day - 1) {
+            streak + 1
+        } else {
+            1
+        };
+        longest_streak = longest_streak.max(streak);
+        last_met = Some(totals.day);
+    }
+    // The streak is still alive until the end of the day after goals were last met
+    let current_streak = match last_met {
+        Some(day) if day >= today - 1 => streak,
+        _ => 0,
+    };
+
+    GoalProgress {
+        races_today: today_totals.races,
+        races_goal: goals.races_per_day,
+        minutes_today: today_totals.millis as f64 / 60_000.0,
+        minutes_goal: goals.minutes_per_day,
+        best_wpm_today: (today_totals.races > 0).then_some(today_totals.best_wpm),
+        wpm_goal: goals.target_wpm,
+        goals_met_today: goals_met(goals, &today_totals),
+        current_streak,
+        longest_streak,
+    }
+}
+
+/// Whether a day meets every goal the user set. With no goals set, any day with a race does.
+fn goals_met(goals: &Goals, totals: &DayTotals) -> bool {
+    totals.races > 0
+        && totals.races >= goals.races_per_day.unwrap_or(0)
+        && totals.millis >= goals.minutes_per_day.unwrap_or(0) * 60_000
+        && totals.best_wpm >= goals.target_wpm.unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: i64, races: usize, minutes: u64, best_wpm: u64) -> DayTotals {
+        DayTotals {
+            day,
+            races,
+            millis: minutes * 60_000,
+            best_wpm,
+        }
+    }
+
+    #[test]
+    fn every_goal_has_to_be_met() {
+        let goals = Goals {
+            races_per_day: Some(3),
+            minutes_per_day: Some(5),
+            target_wpm: Some(80),
+        };
+        assert!(goals_met(&goals, &day(0, 3, 5, 80)));
+        assert!(!goals_met(&goals, &day(0, 2, 10, 90)));
+        assert!(!goals_met(&goals, &day(0, 4, 4, 90)));
+        assert!(!goals_met(&goals, &day(0, 4, 10, 79)));
+        // Without goals any race keeps the streak going
+        assert!(goals_met(&Goals::default(), &day(0, 1, 0, 10)));
+        assert!(!goals_met(&Goals::default(), &day(0, 0, 0, 0)));
+    }
+
+    #[test]
+    fn streaks_count_consecutive_days() {
+        let goals = Goals {
+            races_per_day: Some(2),
+            ..Goals::default()
+        };
+        let days = [
+            day(1, 2, 1, 50),
+            day(2, 2, 1, 50),
+            day(3, 2, 1, 50),
+            // Not enough races to count, which breaks the streak
+            day(4, 1, 1, 50),
+            day(5, 3, 1, 50),
+            day(6, 2, 1, 50),
+        ];
+
+        let progress_today = progress(&days, 6, &goals);
+        assert_eq!(progress_today.races_today, 2);
+        assert!(progress_today.goals_met_today);
+        assert_eq!(progress_today.current_streak, 2);
+        assert_eq!(progress_today.longest_streak, 3);
+
+        // Nothing played yet today, but yesterday still counts
+        let progress_tomorrow = progress(&days, 7, &goals);
+        assert_eq!(progress_tomorrow.races_today, 0);
+        assert_eq!(progress_tomorrow.best_wpm_today, None);
+        assert!(!progress_tomorrow.goals_met_today);
+        assert_eq!(progress_tomorrow.current_streak, 2);
+
+        // Skip a day and it's gone
+        assert_eq!(progress(&days, 8, &goals).current_streak, 0);
+    }
+
+    #[test]
+    fn lines_show_progress_toward_set_goals() {
+        let goals = Goals {
+            races_per_day: Some(5),
+            minutes_per_day: None,
+            target_wpm: Some(80),
+        };
+        let lines = progress(&[day(10, 3, 2, 64)], 10, &goals).lines();
+        assert_eq!(
+            lines,
+            vec![
+                "Races today: 3/5",
+                "Minutes today: 2.0",
+                "Best WPM today: 64/80",
+                "Streak: 0 days (longest 0)",
+            ]
+        );
+    }
+}
@@ -1,13 +1,16 @@
 use rusqlite::{params, Connection, Result};
 
-use crate::{game::GameMode, graphs::RawUserResults};
+use crate::{
+    game::GameMode,
+    graphs::{RawCharError, RawUserResults},
+};
 
 pub fn aggregrate_graph_data(
     conn: &Connection,
     game_mode: GameMode,
 ) -> Result<Vec<RawUserResults>, rusqlite::Error> {
     let mut stmt = conn.prepare(
-        "SELECT wpm, accuracy, highest_combo, when_played_secs
+        "SELECT wpm, accuracy, highest_combo, when_played_secs, raw_wpm, net_wpm, consistency
             FROM passage_stats
             WHERE game_mode = ?1
             ORDER BY when_played_secs ASC",
@@ -19,6 +22,9 @@ pub fn aggregrate_graph_data(
             accuracy: row.get(1)?,
             highest_combo: row.get(2)?,
             when_played_secs: row.get(3)?,
+            raw_wpm: row.get(4)?,
+            net_wpm: row.get(5)?,
+            consistency: row.get(6)?,
         })
     })?;
 
@@ -26,3 +32,25 @@ pub fn aggregrate_graph_data(
         .map(|result| result.unwrap())
         .collect::<Vec<RawUserResults>>())
 }
+
+pub fn char_error_data(
+    conn: &Connection,
+    game_mode: GameMode,
+) -> Result<Vec<RawCharError>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT expected, previous, typed, count
+            FROM char_errors
+            WHERE game_mode = ?1",
+    )?;
+
+    let char_errors_iter = stmt.query_map(params![game_mode as i64], |row| {
+        Ok(RawCharError {
+            expected: row.get(0)?,
+            previous: row.get(1)?,
+            typed: row.get(2)?,
+            count: row.get(3)?,
+        })
+    })?;
+
+    char_errors_iter.collect()
+}
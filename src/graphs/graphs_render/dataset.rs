@@ -1,9 +1,15 @@
 use crate::graphs::{Mode, UserResults};
 
-pub fn relevant_data(user_results: &UserResults, mode: &Mode) -> f64 {
+/// The value charted for a race, None if the race is from before the value was kept
+pub fn relevant_data(user_results: &UserResults, mode: &Mode) -> Option<f64> {
     match *mode {
-        Mode::Wpm => user_results.wpm as f64,
-        Mode::Accuracy => user_results.accuracy,
-        Mode::Combo => user_results.highest_combo as f64,
+        Mode::Wpm => Some(user_results.wpm as f64),
+        Mode::RawWpm => user_results.raw_wpm.map(|wpm| wpm as f64),
+        Mode::NetWpm => user_results.net_wpm.map(|wpm| wpm as f64),
+        Mode::Accuracy => Some(user_results.accuracy),
+        Mode::Consistency => user_results.consistency,
+        Mode::Combo => Some(user_results.highest_combo as f64),
+        // Mistakes are shown as a table rather than charted over time
+        Mode::Mistakes => None,
     }
 }
@@ -1,15 +1,17 @@
 use tui::{
     backend::Backend,
-    layout::{Alignment, Constraint, Direction, Layout},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     symbols,
-    terminal::Terminal,
+    terminal::{Frame, Terminal},
     text::Span,
-    widgets::{Axis, Block, BorderType, Borders, Chart, Dataset, GraphType, Paragraph},
+    widgets::{
+        Axis, Block, BorderType, Borders, Cell, Chart, Dataset, GraphType, Paragraph, Row, Table,
+    },
 };
 
 use crate::game::GameMode;
-use crate::graphs::{Mode, UserResults};
+use crate::graphs::{KeyErrors, Mode, UserResults, WorstKeys};
 
 mod dataset;
 
@@ -19,6 +21,7 @@ mod styles;
 pub fn render<B: Backend>(
     terminal: &mut Terminal<B>,
     ordered_user_results: &[UserResults],
+    worst_keys: &WorstKeys,
     game_mode: GameMode,
     active_mode: &Mode,
 ) {
@@ -56,6 +59,12 @@ pub fn render<B: Backend>(
                 )
                 .split(padding_layout[1]);
 
+            if let Mode::Mistakes = active_mode {
+                render_worst_keys(f, worst_keys, game_mode, main_layout[1]);
+                render_footer(f, main_layout[2]);
+                return;
+            }
+
             let chart_block = Block::default()
                 .borders(Borders::ALL)
                 .border_type(BorderType::Rounded)
@@ -63,11 +72,9 @@ pub fn render<B: Backend>(
 
             let filtered_results: Vec<(f64, f64)> = (*ordered_user_results)
                 .iter()
-                .map(|result| {
-                    (
-                        -result.days_back_played,
-                        dataset::relevant_data(result, active_mode),
-                    )
+                .filter_map(|result| {
+                    dataset::relevant_data(result, active_mode)
+                        .map(|value| (-result.days_back_played, value))
                 })
                 .collect::<Vec<(f64, f64)>>();
 
@@ -128,14 +135,100 @@ pub fn render<B: Backend>(
                 main_layout[1],
             );
 
-            f.render_widget(
-                Paragraph::new(Span::raw(
-                    "^C to go back  ⇕ cycle game mode  ⇔ switch graph",
-                ))
-                .alignment(Alignment::Center)
-                .block(Block::default().borders(Borders::NONE)),
-                main_layout[2],
-            );
+            render_footer(f, main_layout[2]);
         })
         .expect("Failed to draw to terminal");
 }
+
+fn render_footer<B: Backend>(f: &mut Frame<B>, area: Rect) {
+    f.render_widget(
+        Paragraph::new(Span::raw(
+            "^C to go back  ⇕ cycle game mode  ⇔ switch graph",
+        ))
+        .alignment(Alignment::Center)
+        .block(Block::default().borders(Borders::NONE)),
+        area,
+    );
+}
+
+/// The worst characters and pairs of characters side by side, in place of a chart
+fn render_worst_keys<B: Backend>(
+    f: &mut Frame<B>,
+    worst_keys: &WorstKeys,
+    game_mode: GameMode,
+    area: Rect,
+) {
+    let block = |title: String| {
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(styles::borders(game_mode))
+    };
+
+    if worst_keys.chars.is_empty() {
+        f.render_widget(
+            Paragraph::new(Span::raw(
+                "No mistakes recorded yet! Play a few games and come back.",
+            ))
+            .alignment(Alignment::Center)
+            .block(block(format!("{}: Mistakes", game_mode))),
+            area,
+        );
+        return;
+    }
+
+    let tables_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+        .split(area);
+
+    f.render_widget(
+        key_errors_table(&worst_keys.chars, "Key")
+            .block(block(format!("{}: Worst keys", game_mode))),
+        tables_layout[0],
+    );
+    f.render_widget(
+        key_errors_table(&worst_keys.bigrams, "Pair")
+            .block(block(format!("{}: Worst pairs", game_mode))),
+        tables_layout[1],
+    );
+}
+
+fn key_errors_table<'a>(key_errors: &[KeyErrors], keys_header: &'a str) -> Table<'a> {
+    let rows = key_errors
+        .iter()
+        .map(|key_error| {
+            Row::new(vec![
+                Cell::from(visible_keys(&key_error.keys)),
+                Cell::from(key_error.errors.to_string()),
+                Cell::from(visible_keys(&key_error.usual_mistake)),
+            ])
+        })
+        .collect::<Vec<Row>>();
+
+    Table::new(rows)
+        .header(
+            Row::new(vec![keys_header, "Misses", "Usually typed"])
+                .style(Style::default().fg(Color::Yellow)),
+        )
+        .widths(
+            [
+                Constraint::Percentage(30),
+                Constraint::Percentage(30),
+                Constraint::Percentage(40),
+            ]
+            .as_ref(),
+        )
+}
+
+/// Whitespace doesn't show up in a table, swap it for something that does
+fn visible_keys(keys: &str) -> String {
+    keys.chars()
+        .map(|c| match c {
+            ' ' => '␣',
+            '\n' => '↵',
+            c => c,
+        })
+        .collect()
+}
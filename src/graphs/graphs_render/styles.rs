@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
 use tui::style::{Color, Style};
 
+use super::dataset;
 use crate::{
     game::GameMode,
     graphs::{Mode, UserResults},
@@ -22,8 +23,12 @@ pub fn borders(game_mode: GameMode) -> Style {
 pub fn graph_title(mode: &Mode) -> String {
     match *mode {
         Mode::Wpm => "WPM".to_owned(),
+        Mode::RawWpm => "Raw WPM".to_owned(),
+        Mode::NetWpm => "Net WPM".to_owned(),
+        Mode::Consistency => "Consistency".to_owned(),
         Mode::Accuracy => "Accuracy".to_owned(),
         Mode::Combo => "Combo".to_owned(),
+        Mode::Mistakes => "Mistakes".to_owned(),
     }
 }
 
@@ -43,17 +48,27 @@ pub fn y_axis_data(mode: &Mode, user_results: &[UserResults]) -> YAxisData {
 fn resorted_results(mode: &Mode, user_results: &[UserResults]) -> Vec<UserResults> {
     let mut resorted_results = user_results.to_vec();
     resorted_results.clone_from_slice(user_results);
+    // Races without a value aren't charted, so they shouldn't stretch the axis either
+    resorted_results.retain(|result| dataset::relevant_data(result, mode).is_some());
 
     match *mode {
         Mode::Wpm => {
             resorted_results.sort_by(|a, b| a.wpm.partial_cmp(&b.wpm).unwrap_or(Ordering::Equal))
         }
+        Mode::RawWpm => resorted_results.sort_by_key(|a| a.raw_wpm),
+        Mode::NetWpm => resorted_results.sort_by_key(|a| a.net_wpm),
+        Mode::Consistency => resorted_results.sort_by(|a, b| {
+            a.consistency
+                .partial_cmp(&b.consistency)
+                .unwrap_or(Ordering::Equal)
+        }),
         Mode::Accuracy => resorted_results.sort_by(|a, b| {
             a.accuracy
                 .partial_cmp(&b.accuracy)
                 .unwrap_or(Ordering::Equal)
         }),
-        Mode::Combo => resorted_results.sort_by(|a, b| a.highest_combo.cmp(&b.highest_combo)),
+        Mode::Combo => resorted_results.sort_by_key(|a| a.highest_combo),
+        Mode::Mistakes => (),
     };
 
     resorted_results
@@ -62,8 +77,13 @@ fn resorted_results(mode: &Mode, user_results: &[UserResults]) -> Vec<UserResult
 fn y_axis_bounds(mode: &Mode, first: &UserResults, last: &UserResults) -> [f64; 2] {
     match *mode {
         Mode::Wpm => [0.0, last.wpm as f64 + 1.0],
+        Mode::RawWpm => [0.0, last.raw_wpm.unwrap_or(0) as f64 + 1.0],
+        Mode::NetWpm => [0.0, last.net_wpm.unwrap_or(0) as f64 + 1.0],
+        // Consistency is a score out of 100
+        Mode::Consistency => [0.0, 100.0],
         Mode::Accuracy => [first.accuracy - 1.0, last.accuracy],
         Mode::Combo => [0.0, last.highest_combo as f64],
+        Mode::Mistakes => [0.0, 0.0],
     }
 }
 
@@ -74,6 +94,9 @@ fn y_axis_labels(mode: &Mode, first: &UserResults, last: &UserResults) -> [Strin
             format!("{:.2}", (first.wpm + last.wpm) / 2),
             format!("{:.2}", last.wpm),
         ],
+        Mode::RawWpm => wpm_labels(first.raw_wpm, last.raw_wpm),
+        Mode::NetWpm => wpm_labels(first.net_wpm, last.net_wpm),
+        Mode::Consistency => ["0".to_owned(), "50".to_owned(), "100".to_owned()],
         Mode::Accuracy => [
             format!("{:.2}", first.accuracy),
             format!("{:.2}", (first.accuracy + last.accuracy) / 2.0),
@@ -85,5 +108,16 @@ fn y_axis_labels(mode: &Mode, first: &UserResults, last: &UserResults) -> [Strin
             format!("{:.2}", (first.highest_combo + last.highest_combo) / 2),
             format!("{:.2}", last.highest_combo),
         ],
+        Mode::Mistakes => [String::new(), String::new(), String::new()],
     }
 }
+
+fn wpm_labels(first: Option<i64>, last: Option<i64>) -> [String; 3] {
+    let first = first.unwrap_or(0);
+    let last = last.unwrap_or(0);
+    [
+        format!("{:.2}", first),
+        format!("{:.2}", (first + last) / 2),
+        format!("{:.2}", last),
+    ]
+}
@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use crate::graphs::{KeyErrors, RawCharError, WorstKeys};
+
+/// Totals up raw char errors into the `limit` most missed characters and pairs of characters,
+/// most missed first.
+pub fn as_worst_keys(char_errors: &[RawCharError], limit: usize) -> WorstKeys {
+    let chars = worst_of(
+        char_errors
+            .iter()
+            .map(|char_error| (char_error.expected.clone(), char_error)),
+        limit,
+    );
+    // Nothing came before the first character of a passage, so it's not part of a pair
+    let bigrams = worst_of(
+        char_errors
+            .iter()
+            .filter(|char_error| !char_error.previous.is_empty())
+            .map(|char_error| {
+                (
+                    format!("{}{}", char_error.previous, char_error.expected),
+                    char_error,
+                )
+            }),
+        limit,
+    );
+
+    WorstKeys { chars, bigrams }
+}
+
+fn worst_of<'a>(
+    keyed_errors: impl Iterator<Item = (String, &'a RawCharError)>,
+    limit: usize,
+) -> Vec<KeyErrors> {
+    // keys -> (total errors, errors per typed character)
+    let mut totals: HashMap<String, (i64, HashMap<&str, i64>)> = HashMap::new();
+    for (keys, char_error) in keyed_errors {
+        let (errors, typed) = totals.entry(keys).or_default();
+        *errors += char_error.count;
+        *typed.entry(&char_error.typed).or_default() += char_error.count;
+    }
+
+    let mut worst = totals
+        .into_iter()
+        .map(|(keys, (errors, typed))| KeyErrors {
+            keys,
+            errors,
+            usual_mistake: typed
+                .into_iter()
+                .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(a.0)))
+                .map(|(typed, _)| typed.to_owned())
+                .unwrap_or_default(),
+        })
+        .collect::<Vec<KeyErrors>>();
+    worst.sort_by(|a, b| b.errors.cmp(&a.errors).then_with(|| a.keys.cmp(&b.keys)));
+    worst.truncate(limit);
+
+    worst
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn char_error(expected: &str, previous: &str, typed: &str, count: i64) -> RawCharError {
+        RawCharError {
+            expected: expected.to_owned(),
+            previous: previous.to_owned(),
+            typed: typed.to_owned(),
+            count,
+        }
+    }
+
+    fn key_errors(keys: &str, errors: i64, usual_mistake: &str) -> KeyErrors {
+        KeyErrors {
+            keys: keys.to_owned(),
+            errors,
+            usual_mistake: usual_mistake.to_owned(),
+        }
+    }
+
+    #[test]
+    fn worst_keys_are_totalled_and_sorted() {
+        let char_errors = vec![
+            char_error("e", "h", "r", 2),
+            char_error("e", "t", "w", 3),
+            char_error("e", "h", "w", 1),
+            char_error("t", "", "r", 4),
+            char_error("o", "t", "p", 1),
+        ];
+
+        let worst_keys = as_worst_keys(&char_errors, 2);
+
+        assert_eq!(
+            worst_keys,
+            WorstKeys {
+                chars: vec![key_errors("e", 6, "w"), key_errors("t", 4, "r")],
+                bigrams: vec![key_errors("he", 3, "r"), key_errors("te", 3, "w")],
+            }
+        );
+    }
+}
@@ -9,6 +9,7 @@ use tui::{backend::Backend, terminal::Terminal};
 use crate::game::GameMode;
 
 mod graphs_render;
+mod key_errors_mapper;
 mod user_result_mapper;
 
 pub mod graphs_db;
@@ -19,6 +20,9 @@ pub struct RawUserResults {
     accuracy: f64,
     highest_combo: i64,
     when_played_secs: i64,
+    raw_wpm: Option<i64>,
+    net_wpm: Option<i64>,
+    consistency: Option<f64>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -27,15 +31,58 @@ pub struct UserResults {
     accuracy: f64,
     highest_combo: i64,
     days_back_played: f64,
+    /// Races from before raw wpm, net wpm and consistency were kept don't have them
+    raw_wpm: Option<i64>,
+    net_wpm: Option<i64>,
+    consistency: Option<f64>,
+}
+
+/// A count of how many times the user typed `typed` when they should have typed `expected`.
+/// `previous` is the character before `expected` in the passage, empty at the very start.
+#[derive(Clone)]
+pub struct RawCharError {
+    expected: String,
+    previous: String,
+    typed: String,
+    count: i64,
+}
+
+/// How often a key (or a pair of keys) was missed, and what was most often typed instead
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyErrors {
+    keys: String,
+    errors: i64,
+    usual_mistake: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorstKeys {
+    chars: Vec<KeyErrors>,
+    bigrams: Vec<KeyErrors>,
 }
 
 pub enum Mode {
     Accuracy,
     Combo,
     Wpm,
+    RawWpm,
+    NetWpm,
+    Consistency,
+    Mistakes,
 }
 
-const MODES: [Mode; 3] = [Mode::Wpm, Mode::Accuracy, Mode::Combo];
+const MODES: [Mode; 7] = [
+    Mode::Wpm,
+    Mode::RawWpm,
+    Mode::NetWpm,
+    Mode::Accuracy,
+    Mode::Consistency,
+    Mode::Combo,
+    Mode::Mistakes,
+];
+
+/// How many of the worst keys and pairs to show
+const WORST_KEYS_SHOWN: usize = 15;
 
 pub fn show_graphs<B: Backend>(
     terminal: &mut Terminal<B>,
@@ -49,6 +96,7 @@ pub fn show_graphs<B: Backend>(
     let mut current_mode = 0;
 
     let mut results_map = HashMap::new();
+    let mut worst_keys_map = HashMap::new();
     let mut terminal_size = (0, 0);
     let mut should_re_render = true;
     loop {
@@ -64,6 +112,19 @@ pub fn show_graphs<B: Backend>(
                 &results_map[&game_mode]
             }
         };
+        let worst_keys = match worst_keys_map.get(&game_mode) {
+            Some(worst_keys) => worst_keys,
+            None => {
+                worst_keys_map.insert(
+                    game_mode,
+                    key_errors_mapper::as_worst_keys(
+                        &graphs_db::char_error_data(&conn, game_mode)?,
+                        WORST_KEYS_SHOWN,
+                    ),
+                );
+                &worst_keys_map[&game_mode]
+            }
+        };
 
         // terminal size is updated so we should re-render
         let updated_terminal_size = terminal_length_width();
@@ -74,7 +135,13 @@ pub fn show_graphs<B: Backend>(
 
         // rendering graphs is a little extra expensive, let's not re-render if we don't have to
         if should_re_render {
-            graphs_render::render(terminal, results, game_mode, &MODES[current_mode]);
+            graphs_render::render(
+                terminal,
+                results,
+                worst_keys,
+                game_mode,
+                &MODES[current_mode],
+            );
             should_re_render = false;
         }
 
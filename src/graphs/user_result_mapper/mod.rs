@@ -16,6 +16,9 @@ pub fn as_user_results(ordered_user_results: &[RawUserResults]) -> Vec<UserResul
             wpm: raw_user_result.wpm,
             accuracy: raw_user_result.accuracy,
             highest_combo: raw_user_result.highest_combo,
+            raw_wpm: raw_user_result.raw_wpm,
+            net_wpm: raw_user_result.net_wpm,
+            consistency: raw_user_result.consistency,
             days_back_played: days_played_back(
                 *days_played_for,
                 first_played_time,
@@ -88,18 +91,27 @@ mod tests {
                 accuracy: 100.0,
                 highest_combo: 100,
                 when_played_secs: 1588464000,
+                raw_wpm: None,
+                net_wpm: None,
+                consistency: None,
             },
             RawUserResults {
                 wpm: 80,
                 accuracy: 100.0,
                 highest_combo: 100,
                 when_played_secs: 1588550400,
+                raw_wpm: Some(85),
+                net_wpm: Some(85),
+                consistency: Some(75.0),
             },
             RawUserResults {
                 wpm: 80,
                 accuracy: 100.0,
                 highest_combo: 100,
                 when_played_secs: 1588636800,
+                raw_wpm: Some(90),
+                net_wpm: Some(80),
+                consistency: Some(70.0),
             },
         ];
 
@@ -0,0 +1,92 @@
+use rusqlite::{params, Connection, Result};
+
+use crate::{game::GameMode, heatmap::RawKeyStats};
+
+/// Gaps between keystrokes longer than this are the user taking a break rather than hunting for
+/// the key, so they're left out of the average latency.
+pub const MAX_LATENCY_MILLIS: i64 = 3000;
+
+/// Totals up every logged keystroke aimed at each character, across every race in a game mode.
+/// Latency is the time since the keystroke before it in the same race, only counted when the
+/// right key was hit.
+pub fn key_stats_data(
+    conn: &Connection,
+    game_mode: GameMode,
+) -> Result<Vec<RawKeyStats>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT expected,
+                COUNT(*),
+                SUM(typed != expected),
+                SUM(CASE WHEN typed = expected AND latency <= ?2 THEN latency END),
+                COUNT(CASE WHEN typed = expected AND latency <= ?2 THEN latency END)
+            FROM (
+                SELECT keystrokes.expected,
+                       keystrokes.typed,
+                       keystrokes.backspace,
+                       keystrokes.offset_millis - LAG(keystrokes.offset_millis) OVER (
+                           PARTITION BY keystrokes.passage_stats_id
+                           ORDER BY keystrokes.row_id
+                       ) AS latency
+                    FROM keystrokes
+                    JOIN passage_stats ON passage_stats.row_id = keystrokes.passage_stats_id
+                    WHERE passage_stats.game_mode = ?1
+            )
+            WHERE backspace = 0 AND expected IS NOT NULL
+            GROUP BY expected",
+    )?;
+
+    let key_stats_iter = stmt.query_map(params![game_mode as i64, MAX_LATENCY_MILLIS], |row| {
+        Ok(RawKeyStats {
+            expected: row.get(0)?,
+            attempts: row.get(1)?,
+            misses: row.get(2)?,
+            total_latency_millis: row.get::<_, Option<i64>>(3)?.unwrap_or(0),
+            latency_samples: row.get(4)?,
+        })
+    })?;
+
+    key_stats_iter.collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDb;
+
+    #[test]
+    fn latency_is_time_since_the_previous_keystroke() {
+        let db = TempDb::new("heatmap");
+        let db_path = db.path();
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute_batch(
+            "INSERT INTO passages (passage, passage_len) VALUES ('/default/a', 2);
+            INSERT INTO passage_stats (row_id, passage, game_mode) VALUES (1, '/default/a', 0);
+            INSERT INTO keystrokes
+                (passage_stats_id, offset_millis, position, expected, typed, correct, backspace)
+                VALUES (1, 0, 0, 'a', 'a', 1, 0),
+                       (1, 100, 1, 'b', 'v', 0, 0),
+                       (1, 250, 1, 'b', '\x08', 0, 1),
+                       (1, 400, 1, 'b', 'b', 1, 0),
+                       (1, 9000, 2, 'a', 'a', 1, 0);",
+        )
+        .unwrap();
+
+        let mut key_stats = key_stats_data(&conn, GameMode::Default).unwrap();
+        key_stats.sort_by(|a, b| a.expected.cmp(&b.expected));
+
+        let totals: Vec<(&str, i64, i64, i64, i64)> = key_stats
+            .iter()
+            .map(|stats| {
+                (
+                    stats.expected.as_str(),
+                    stats.attempts,
+                    stats.misses,
+                    stats.total_latency_millis,
+                    stats.latency_samples,
+                )
+            })
+            .collect();
+        // The first keystroke has nothing before it and the last one came after a long break
+        assert_eq!(totals, vec![("a", 2, 0, 0, 0), ("b", 2, 1, 150, 1)]);
+    }
+}
@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    terminal::{Frame, Terminal},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::game::GameMode;
+use crate::heatmap::{KeyStats, KeyboardLayout};
+
+/// Colors from the best keys to the worst
+const HEAT_COLORS: [Color; 4] = [Color::Green, Color::Yellow, Color::LightRed, Color::Red];
+/// How far each row is shifted right, to look roughly like a real keyboard
+const ROW_OFFSETS: [usize; 5] = [0, 2, 3, 5, 16];
+const SPACE_BAR_WIDTH: usize = 27;
+
+pub fn render<B: Backend>(
+    terminal: &mut Terminal<B>,
+    key_stats: &HashMap<char, KeyStats>,
+    game_mode: GameMode,
+    layout: KeyboardLayout,
+) {
+    terminal
+        .draw(|f| {
+            let padding_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(90),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
+
+            let main_layout = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(40),
+                        Constraint::Percentage(40),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(5),
+                    ]
+                    .as_ref(),
+                )
+                .split(padding_layout[1]);
+
+            render_keyboard(
+                f,
+                key_stats,
+                layout,
+                format!("{}: Error rate ({})", game_mode, layout),
+                |stats| stats.error_rate(),
+                |error_rate| format!("{:.1}%", error_rate),
+                game_mode,
+                main_layout[1],
+            );
+            render_keyboard(
+                f,
+                key_stats,
+                layout,
+                format!("{}: Average latency ({})", game_mode, layout),
+                |stats| stats.average_latency_millis(),
+                |latency| format!("{:.0}ms", latency),
+                game_mode,
+                main_layout[2],
+            );
+
+            f.render_widget(
+                Paragraph::new(Span::raw(
+                    "^C to go back  ⇕ cycle game mode  ⇔ switch keyboard layout",
+                ))
+                .alignment(Alignment::Center)
+                .block(Block::default().borders(Borders::NONE)),
+                main_layout[3],
+            );
+        })
+        .expect("Failed to draw to terminal");
+}
+
+/// Draw the keyboard with every key colored by where its value falls between the user's best and
+/// worst keys, followed by a legend for the colors
+#[allow(clippy::too_many_arguments)]
+fn render_keyboard<B: Backend>(
+    f: &mut Frame<B>,
+    key_stats: &HashMap<char, KeyStats>,
+    layout: KeyboardLayout,
+    title: String,
+    value_of: impl Fn(&KeyStats) -> Option<f64>,
+    describe: impl Fn(f64) -> String,
+    game_mode: GameMode,
+    area: Rect,
+) {
+    let rows = layout.rows();
+    let values: HashMap<char, f64> = rows
+        .concat()
+        .chars()
+        .filter_map(|key| {
+            key_stats
+                .get(&key)
+                .and_then(&value_of)
+                .map(|value| (key, value))
+        })
+        .collect();
+    let lowest = values.values().cloned().fold(f64::INFINITY, f64::min);
+    let highest = values.values().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    // Rows are staggered, so the keyboard is centered as a whole rather than line by line
+    let keyboard_width = rows
+        .iter()
+        .zip(ROW_OFFSETS.iter())
+        .map(|(row, offset)| {
+            offset
+                + row
+                    .chars()
+                    .map(|key| key_label(key).width() + 1)
+                    .sum::<usize>()
+        })
+        .max()
+        .unwrap_or(0);
+    let margin = " ".repeat((usize::from(area.width).saturating_sub(2 + keyboard_width)) / 2);
+
+    let mut lines = vec![];
+    for (row, offset) in rows.iter().zip(ROW_OFFSETS.iter()) {
+        let mut spans = vec![Span::raw(format!("{}{}", margin, " ".repeat(*offset)))];
+        for key in row.chars() {
+            let style = match values.get(&key) {
+                Some(value) => Style::default()
+                    .fg(Color::Black)
+                    .bg(heat_color(*value, lowest, highest)),
+                None => Style::default().fg(Color::Black).bg(Color::DarkGray),
+            };
+            spans.push(Span::styled(key_label(key), style));
+            spans.push(Span::raw(" "));
+        }
+        lines.push(Spans::from(spans));
+        lines.push(Spans::from(""));
+    }
+
+    if values.is_empty() {
+        lines.push(Spans::from(format!(
+            "{}No keystrokes recorded yet! Play a few games and come back.",
+            margin
+        )));
+    } else {
+        let mut legend = vec![Span::raw(format!("{}{} ", margin, describe(lowest)))];
+        legend.extend(
+            HEAT_COLORS
+                .iter()
+                .map(|color| Span::styled("  ", Style::default().bg(*color))),
+        );
+        legend.push(Span::raw(format!(" {}   ", describe(highest))));
+        legend.push(Span::styled("  ", Style::default().bg(Color::DarkGray)));
+        legend.push(Span::raw(" not typed yet"));
+        lines.push(Spans::from(legend));
+    }
+
+    f.render_widget(
+        Paragraph::new(lines).block(
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded)
+                .border_style(borders(game_mode)),
+        ),
+        area,
+    );
+}
+
+fn key_label(key: char) -> String {
+    match key {
+        ' ' => " ".repeat(SPACE_BAR_WIDTH),
+        '\n' => " ↵ ".to_owned(),
+        key => format!(" {} ", key.to_uppercase()),
+    }
+}
+
+/// Bucket a value into one of the heat colors, by how close it is to the highest value
+fn heat_color(value: f64, lowest: f64, highest: f64) -> Color {
+    if highest <= lowest {
+        return HEAT_COLORS[0];
+    }
+    let bucket = ((value - lowest) / (highest - lowest) * HEAT_COLORS.len() as f64) as usize;
+    HEAT_COLORS[bucket.min(HEAT_COLORS.len() - 1)]
+}
+
+fn borders(game_mode: GameMode) -> Style {
+    if game_mode == GameMode::InstantDeath {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heat_color_spreads_values_between_best_and_worst() {
+        assert_eq!(heat_color(0.0, 0.0, 8.0), Color::Green);
+        assert_eq!(heat_color(3.0, 0.0, 8.0), Color::Yellow);
+        assert_eq!(heat_color(5.0, 0.0, 8.0), Color::LightRed);
+        assert_eq!(heat_color(8.0, 0.0, 8.0), Color::Red);
+        // Every key being as good as each other is a good thing
+        assert_eq!(heat_color(2.0, 2.0, 2.0), Color::Green);
+    }
+}
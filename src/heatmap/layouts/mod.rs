@@ -0,0 +1,110 @@
+use std::fmt;
+
+/// Keyboard layouts the heatmap can be drawn as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Dvorak,
+    Colemak,
+}
+
+/// Unshifted keys, in the same order as `SHIFTED_KEYS`
+const UNSHIFTED_KEYS: &str = "`1234567890-=[]\\;',./";
+/// What each key in `UNSHIFTED_KEYS` types with shift held, going by a US keyboard
+const SHIFTED_KEYS: &str = "~!@#$%^&*()_+{}|:\"<>?";
+
+impl KeyboardLayout {
+    // Cycles like GameMode::next and GameMode::prev
+    pub fn next(self) -> Self {
+        match self {
+            KeyboardLayout::Qwerty => KeyboardLayout::Dvorak,
+            KeyboardLayout::Dvorak => KeyboardLayout::Colemak,
+            KeyboardLayout::Colemak => KeyboardLayout::Qwerty,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            KeyboardLayout::Qwerty => KeyboardLayout::Colemak,
+            KeyboardLayout::Colemak => KeyboardLayout::Dvorak,
+            KeyboardLayout::Dvorak => KeyboardLayout::Qwerty,
+        }
+    }
+
+    /// Each row of keys from the top of the keyboard down, without shift held.
+    /// Enter ('\n') closes out the home row and the last row is just the space bar.
+    pub fn rows(self) -> [&'static str; 5] {
+        match self {
+            KeyboardLayout::Qwerty => [
+                "`1234567890-=",
+                "qwertyuiop[]\\",
+                "asdfghjkl;'\n",
+                "zxcvbnm,./",
+                " ",
+            ],
+            KeyboardLayout::Dvorak => [
+                "`1234567890[]",
+                "',.pyfgcrl/=\\",
+                "aoeuidhtns-\n",
+                ";qjkxbmwvz",
+                " ",
+            ],
+            KeyboardLayout::Colemak => [
+                "`1234567890-=",
+                "qwfpgjluy;[]\\",
+                "arstdhneio'\n",
+                "zxcvbkm,./",
+                " ",
+            ],
+        }
+    }
+}
+
+impl fmt::Display for KeyboardLayout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyboardLayout::Qwerty => write!(f, "QWERTY"),
+            KeyboardLayout::Dvorak => write!(f, "Dvorak"),
+            KeyboardLayout::Colemak => write!(f, "Colemak"),
+        }
+    }
+}
+
+/// The key that types a character, so shifted characters count towards the key they're on.
+/// e.g. 'A' is typed with 'a' and '?' is typed with '/'
+pub fn base_key(c: char) -> char {
+    if c.is_uppercase() {
+        return c.to_lowercase().next().unwrap_or(c);
+    }
+    match SHIFTED_KEYS.chars().position(|shifted| shifted == c) {
+        Some(idx) => UNSHIFTED_KEYS.chars().nth(idx).unwrap_or(c),
+        None => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shifted_characters_map_to_their_key() {
+        assert_eq!(base_key('A'), 'a');
+        assert_eq!(base_key('?'), '/');
+        assert_eq!(base_key('"'), '\'');
+        assert_eq!(base_key('~'), '`');
+        assert_eq!(base_key('q'), 'q');
+        assert_eq!(base_key('你'), '你');
+    }
+
+    #[test]
+    fn layouts_have_the_same_keys() {
+        let mut qwerty_keys: Vec<char> = KeyboardLayout::Qwerty.rows().concat().chars().collect();
+        qwerty_keys.sort_unstable();
+
+        for layout in [KeyboardLayout::Dvorak, KeyboardLayout::Colemak] {
+            let mut keys: Vec<char> = layout.rows().concat().chars().collect();
+            keys.sort_unstable();
+            assert_eq!(keys, qwerty_keys, "{} is missing keys", layout);
+        }
+    }
+}
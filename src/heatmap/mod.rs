@@ -0,0 +1,165 @@
+use crossbeam_channel::Receiver;
+use rusqlite::{Connection, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use termion::event::Key;
+use tui::{backend::Backend, terminal::Terminal};
+
+use crate::game::GameMode;
+
+mod heatmap_render;
+mod layouts;
+
+pub mod heatmap_db;
+
+pub use layouts::KeyboardLayout;
+
+/// Keystroke totals for a single character, straight from the db
+#[derive(Clone)]
+pub struct RawKeyStats {
+    expected: String,
+    attempts: i64,
+    misses: i64,
+    total_latency_millis: i64,
+    latency_samples: i64,
+}
+
+/// How the user does on a single key of the keyboard, with shifted characters counted towards
+/// the key they're typed with
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct KeyStats {
+    attempts: i64,
+    misses: i64,
+    total_latency_millis: i64,
+    latency_samples: i64,
+}
+
+impl KeyStats {
+    pub fn error_rate(&self) -> Option<f64> {
+        if self.attempts == 0 {
+            None
+        } else {
+            Some(self.misses as f64 * 100.0 / self.attempts as f64)
+        }
+    }
+
+    pub fn average_latency_millis(&self) -> Option<f64> {
+        if self.latency_samples == 0 {
+            None
+        } else {
+            Some(self.total_latency_millis as f64 / self.latency_samples as f64)
+        }
+    }
+}
+
+pub fn show_heatmap<B: Backend>(
+    terminal: &mut Terminal<B>,
+    input_receiver: &Receiver<Key>,
+    db_path: &Path,
+    game_mode_from_game: GameMode,
+) -> Result<(), rusqlite::Error> {
+    let conn = Connection::open(db_path)?;
+
+    let mut game_mode = game_mode_from_game;
+    let mut layout = KeyboardLayout::Qwerty;
+
+    let mut key_stats_map = HashMap::new();
+    let mut terminal_size = (0, 0);
+    let mut should_re_render = true;
+    loop {
+        let key_stats = match key_stats_map.get(&game_mode) {
+            Some(key_stats) => key_stats,
+            None => {
+                key_stats_map.insert(
+                    game_mode,
+                    as_key_stats(&heatmap_db::key_stats_data(&conn, game_mode)?),
+                );
+                &key_stats_map[&game_mode]
+            }
+        };
+
+        let updated_terminal_size = terminal_length_width();
+        if terminal_size != updated_terminal_size {
+            should_re_render = true;
+            terminal_size = updated_terminal_size;
+        }
+
+        if should_re_render {
+            heatmap_render::render(terminal, key_stats, game_mode, layout);
+            should_re_render = false;
+        }
+
+        // Like the graphs, only re-render when something changes but check in every so often
+        // in case the terminal was resized
+        let recv_result = input_receiver.recv_timeout(Duration::from_secs(1));
+        if recv_result.is_err() {
+            continue;
+        }
+
+        let key = recv_result.unwrap();
+        match key {
+            Key::Up | Key::Down | Key::Left | Key::Right => should_re_render = true,
+            _ => (),
+        }
+        match key {
+            Key::Ctrl('c') => return Ok(()),
+            Key::Up => game_mode = game_mode.prev(),
+            Key::Down => game_mode = game_mode.next(),
+            Key::Left => layout = layout.prev(),
+            Key::Right => layout = layout.next(),
+            _ => (),
+        }
+    }
+}
+
+/// Combine the stats for every character typed with the same key
+fn as_key_stats(raw_key_stats: &[RawKeyStats]) -> HashMap<char, KeyStats> {
+    let mut key_stats: HashMap<char, KeyStats> = HashMap::new();
+    for raw in raw_key_stats {
+        let expected = match raw.expected.chars().next() {
+            Some(c) => c,
+            None => continue,
+        };
+        let stats = key_stats.entry(layouts::base_key(expected)).or_default();
+        stats.attempts += raw.attempts;
+        stats.misses += raw.misses;
+        stats.total_latency_millis += raw.total_latency_millis;
+        stats.latency_samples += raw.latency_samples;
+    }
+    key_stats
+}
+
+fn terminal_length_width() -> (u16, u16) {
+    termion::terminal_size().expect("Unable to get terminal size")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw(expected: &str, attempts: i64, misses: i64, latencies: &[i64]) -> RawKeyStats {
+        RawKeyStats {
+            expected: expected.to_owned(),
+            attempts,
+            misses,
+            total_latency_millis: latencies.iter().sum(),
+            latency_samples: latencies.len() as i64,
+        }
+    }
+
+    #[test]
+    fn shifted_characters_count_towards_their_key() {
+        let key_stats = as_key_stats(&[
+            raw("a", 6, 1, &[100, 200, 150]),
+            raw("A", 2, 1, &[350]),
+            raw("?", 1, 0, &[]),
+        ]);
+
+        let a_stats = &key_stats[&'a'];
+        assert_eq!(a_stats.error_rate(), Some(25.0));
+        assert_eq!(a_stats.average_latency_millis(), Some(200.0));
+        assert_eq!(key_stats[&'/'].average_latency_millis(), None);
+        assert!(!key_stats.contains_key(&'A'));
+    }
+}
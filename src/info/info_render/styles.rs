@@ -1,18 +1,18 @@
 use tui::layout::Constraint;
 
 const LARGE_LAYOUT: [Constraint; 4] = [
-    Constraint::Percentage(20),
-    Constraint::Percentage(40),
     Constraint::Percentage(10),
-    Constraint::Percentage(30),
+    Constraint::Percentage(60),
+    Constraint::Percentage(5),
+    Constraint::Percentage(25),
 ];
 
 const LARGE_MARGIN: u16 = 5;
 
 const MEDIUM_LAYOUT: [Constraint; 4] = [
     Constraint::Percentage(2),
-    Constraint::Percentage(68),
-    Constraint::Percentage(5),
+    Constraint::Percentage(73),
+    Constraint::Percentage(0),
     Constraint::Percentage(25),
 ];
 
@@ -2,7 +2,7 @@ use crossbeam_channel::Receiver;
 use info_render::render;
 use itertools::izip;
 use std::time::Duration;
-use std::{thread, time};
+use std::{path::Path, thread, time};
 use termion::event::Key;
 use tui::{
     backend::Backend,
@@ -11,6 +11,8 @@ use tui::{
     text::Text,
 };
 
+use crate::{config::Goals, goals};
+
 mod info_render;
 
 pub struct InfoData<'a> {
@@ -26,6 +28,8 @@ static TYPING_DELAY: [u64; MAGIC_AMT] = [144, 80, 144, 144, 144, 100, 105, 95, 8
 pub fn show_info<B: Backend>(
     terminal: &mut Terminal<B>,
     input_receiver: &Receiver<Key>,
+    db_path: &Path,
+    goals: &Goals,
     typeracer_version: &str,
 ) {
     let version_string = get_version_string(typeracer_version);
@@ -65,6 +69,17 @@ pub fn show_info<B: Backend>(
     top_text.extend(Text::raw(
         "Type through passages to see what the fastest times are you can get!\n\n",
     ));
+    // Nothing to show if the stats db can't be read, the rest of the page is still useful
+    if let Ok(progress) = goals::todays_progress(db_path, goals) {
+        top_text.extend(Text::styled(
+            progress.lines().join("\n") + "\n\n",
+            Style::default().fg(if progress.goals_met_today {
+                Color::Green
+            } else {
+                Color::Yellow
+            }),
+        ));
+    }
     top_text.extend(Text::raw(
         "repo: https://gitlab.com/ttyperacer/terminal-typeracer\n",
     ));
@@ -0,0 +1,629 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    game::split, lang_pack::lint::typeable_replacement,
+    passage_controller::passage_file::flatten_prose,
+};
+
+/// Version written to the version file of created packs
+pub const CREATED_PACK_VERSION: &str = "1.0.0";
+
+/// What the source file is written in, which decides what gets stripped out before it's split up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextFormat {
+    Plain,
+    Markdown,
+    /// Plain text with the Project Gutenberg license wrapped around the book
+    Gutenberg,
+}
+
+/// A paragraph of text along with the heading of the section it's in
+#[derive(Debug, PartialEq, Eq)]
+struct Paragraph {
+    heading: Option<String>,
+    text: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Passage {
+    text: String,
+    attribution: String,
+}
+
+/// Split a text file into passages and write them into `pack_dir` laid out as a lang pack, with
+/// one quote directory named after the file. Passages are made of whole sentences where possible
+/// and stick to paragraphs once they're at least `min_chars` long. Returns how many passages were
+/// written. A pack already at `pack_dir` might have been edited by hand, so it's never replaced.
+pub fn create_pack(
+    source: &Path,
+    pack_dir: &Path,
+    min_chars: usize,
+    max_chars: usize,
+) -> Result<usize, Error> {
+    if min_chars == 0 || min_chars > max_chars {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Passage lengths need a minimum above 0 and no bigger than the maximum",
+        ));
+    }
+    if pack_dir.exists() {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!(
+                "{} already exists, delete it first or pick another name",
+                pack_dir.display()
+            ),
+        ));
+    }
+
+    let contents = String::from_utf8(fs::read(source)?).map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("{} isn't UTF-8 text", source.display()),
+        )
+    })?;
+    let stem = source
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "passages".to_owned());
+    let format = match source.extension().and_then(|ext| ext.to_str()) {
+        Some("md" | "markdown") => TextFormat::Markdown,
+        _ if gutenberg_body(&contents).is_some() => TextFormat::Gutenberg,
+        _ => TextFormat::Plain,
+    };
+
+    let (title, paragraphs) = paragraphs(&contents, format);
+    let passages = to_passages(
+        &paragraphs,
+        title.as_deref().unwrap_or(&stem),
+        min_chars,
+        max_chars,
+    );
+    if passages.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Couldn't find any text in {}", source.display()),
+        ));
+    }
+
+    let quote_dir = pack_dir.join(&stem);
+    fs::create_dir_all(&quote_dir)?;
+    fs::write(
+        pack_dir.join("version"),
+        format!("{}\n{}\n", CREATED_PACK_VERSION, random_uuid()),
+    )?;
+    // Zero padded so they list in the same order they're in the book
+    let width = passages.len().to_string().len();
+    for (idx, passage) in passages.iter().enumerate() {
+        fs::write(
+            quote_dir.join(format!("{:0width$}", idx + 1, width = width)),
+            format!("{}\n{}\n", passage.text, passage.attribution),
+        )?;
+    }
+    Ok(passages.len())
+}
+
+/// Version files end with a uuid so packs on the same version can still be told apart
+fn random_uuid() -> String {
+    // Version 4 and the RFC 4122 variant
+    let bits = (rand::random::<u128>() & !(0xF000 << 64) & !(0xC000 << 48))
+        | (0x4000 << 64)
+        | (0x8000 << 48);
+    let hex = format!("{:032x}", bits);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// The book between Project Gutenberg's start and end markers, if it has them
+fn gutenberg_body(contents: &str) -> Option<(&str, &str)> {
+    let is_marker = |line: &str, marker: &str| {
+        line.starts_with(marker) && line.to_uppercase().contains("PROJECT GUTENBERG")
+    };
+    let start = contents
+        .lines()
+        .find(|line| is_marker(line, "*** START OF"))?;
+    let header_end = contents.find(start)?;
+    let body_start = header_end + start.len();
+    let body_end = contents[body_start..]
+        .lines()
+        .find(|line| is_marker(line, "*** END OF"))
+        .and_then(|end| contents[body_start..].find(end))
+        .map_or(contents.len(), |end| body_start + end);
+    Some((&contents[..header_end], &contents[body_start..body_end]))
+}
+
+/// Title of a Project Gutenberg book from the header above the start marker
+fn gutenberg_title(header: &str) -> Option<String> {
+    let field = |name: &str| {
+        header
+            .lines()
+            .find_map(|line| line.strip_prefix(name))
+            .map(|value| value.trim().to_owned())
+            .filter(|value| !value.is_empty())
+    };
+    match (field("Title:"), field("Author:")) {
+        (Some(title), Some(author)) => Some(format!("{} by {}", title, author)),
+        (title, _) => title,
+    }
+}
+
+/// Break the file up into paragraphs of normalized text, along with the title of the whole thing
+/// if the file says what it is
+fn paragraphs(contents: &str, format: TextFormat) -> (Option<String>, Vec<Paragraph>) {
+    let (title, raw_paragraphs) = match format {
+        TextFormat::Plain => (None, blank_line_paragraphs(contents)),
+        TextFormat::Gutenberg => {
+            let (header, body) = gutenberg_body(contents).unwrap_or(("", contents));
+            let paragraphs = blank_line_paragraphs(body)
+                .into_iter()
+                // Chapter headings and the like are all caps, illustrations are in brackets
+                .filter(|paragraph| {
+                    paragraph.text.chars().any(char::is_lowercase)
+                        && !(paragraph.text.starts_with('[') && paragraph.text.ends_with(']'))
+                })
+                .collect();
+            (gutenberg_title(header), paragraphs)
+        }
+        TextFormat::Markdown => markdown_paragraphs(contents),
+    };
+
+    let paragraphs = raw_paragraphs
+        .into_iter()
+        .map(|paragraph| Paragraph {
+            heading: paragraph.heading,
+            text: match format {
+                TextFormat::Plain => normalize(&paragraph.text),
+                _ => normalize(&strip_inline_markdown(&paragraph.text)),
+            },
+        })
+        .filter(|paragraph| !paragraph.text.is_empty())
+        .collect();
+    (title, paragraphs)
+}
+
+fn blank_line_paragraphs(text: &str) -> Vec<Paragraph> {
+    let mut paragraphs = vec![];
+    let mut lines: Vec<&str> = vec![];
+    for line in text.lines().chain(std::iter::once("")) {
+        if line.trim().is_empty() {
+            if !lines.is_empty() {
+                paragraphs.push(Paragraph {
+                    heading: None,
+                    text: lines.join("\n"),
+                });
+                lines.clear();
+            }
+        } else {
+            lines.push(line);
+        }
+    }
+    paragraphs
+}
+
+/// Paragraphs of prose from a Markdown file, leaving out code, tables and html. The first top
+/// level heading is taken as the title.
+fn markdown_paragraphs(contents: &str) -> (Option<String>, Vec<Paragraph>) {
+    let mut title = None;
+    let mut heading: Option<String> = None;
+    let mut paragraphs = vec![];
+    let mut lines: Vec<&str> = vec![];
+    let mut in_code_block = false;
+
+    let mut end_paragraph = |lines: &mut Vec<&str>, heading: &Option<String>| {
+        if !lines.is_empty() {
+            paragraphs.push(Paragraph {
+                heading: heading.clone(),
+                text: lines.join("\n"),
+            });
+            lines.clear();
+        }
+    };
+
+    for line in contents.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            end_paragraph(&mut lines, &heading);
+        } else if in_code_block {
+            continue;
+        } else if let Some(text) = trimmed.strip_prefix('#') {
+            end_paragraph(&mut lines, &heading);
+            let text = text.trim_start_matches('#').trim().to_owned();
+            if title.is_none() && trimmed.starts_with("# ") {
+                title = Some(text);
+                heading = None;
+            } else {
+                heading = Some(text);
+            }
+        } else if trimmed.len() >= 3 && trimmed.chars().all(|c| c == '=' || c == '-') {
+            // The line above is a heading, rather than the end of a paragraph
+            if let Some(text) = lines.pop() {
+                end_paragraph(&mut lines, &heading);
+                heading = Some(text.trim().to_owned());
+            }
+        } else if trimmed.is_empty()
+            || trimmed.starts_with('|')
+            || trimmed.starts_with('<')
+            || (trimmed.len() >= 3 && trimmed.chars().all(|c| "*_ ".contains(c)))
+            || (lines.is_empty() && (line.starts_with("    ") || line.starts_with('\t')))
+        {
+            end_paragraph(&mut lines, &heading);
+        } else {
+            let text = trimmed.trim_start_matches(['>', ' ']);
+            match strip_list_marker(text) {
+                Some(item) => {
+                    // Every item in a list stands on its own
+                    end_paragraph(&mut lines, &heading);
+                    lines.push(item);
+                }
+                None => lines.push(text),
+            }
+        }
+    }
+    end_paragraph(&mut lines, &heading);
+    (title, paragraphs)
+}
+
+fn strip_list_marker(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+        .or_else(|| {
+            let digits = line.chars().take_while(char::is_ascii_digit).count();
+            if digits > 0 {
+                line[digits..]
+                    .strip_prefix(". ")
+                    .or_else(|| line[digits..].strip_prefix(") "))
+            } else {
+                None
+            }
+        })
+}
+
+/// Keep the text of links and emphasis, dropping the markup around it and images altogether
+fn strip_inline_markdown(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut stripped = String::new();
+    let mut idx = 0;
+    while idx < chars.len() {
+        let c = chars[idx];
+        let is_image = c == '!' && chars.get(idx + 1) == Some(&'[');
+        if c == '[' || is_image {
+            let text_start = if is_image { idx + 2 } else { idx + 1 };
+            if let Some((link_text, link_end)) = markdown_link(&chars, text_start) {
+                if !is_image {
+                    stripped.push_str(&strip_inline_markdown(&link_text));
+                }
+                idx = link_end;
+                continue;
+            }
+        }
+
+        let is_emphasis = match c {
+            '*' | '`' => true,
+            // Underscores in the middle of words are part of them, like snake_case
+            '_' => {
+                let before = idx.checked_sub(1).map(|before| chars[before]);
+                let after = chars.get(idx + 1);
+                !before.is_some_and(char::is_alphanumeric)
+                    || !after.is_some_and(|after| after.is_alphanumeric())
+            }
+            _ => false,
+        };
+        if !is_emphasis {
+            stripped.push(c);
+        }
+        idx += 1;
+    }
+    stripped
+}
+
+/// The text of a `[text](url)` link starting at `text_start`, and where the link ends.
+/// The text can hold links of its own, like badges made of an image wrapped in a link.
+fn markdown_link(chars: &[char], text_start: usize) -> Option<(String, usize)> {
+    let mut depth = 0;
+    let text_end = text_start
+        + chars[text_start..].iter().position(|&c| {
+            match c {
+                '[' => depth += 1,
+                ']' if depth == 0 => return true,
+                ']' => depth -= 1,
+                _ => {}
+            }
+            false
+        })?;
+    if chars.get(text_end + 1) != Some(&'(') {
+        return None;
+    }
+    let url_end = text_end + chars[text_end..].iter().position(|&c| c == ')')?;
+    Some((chars[text_start..text_end].iter().collect(), url_end + 1))
+}
+
+/// One line of text with nothing in it that's hard to type
+fn normalize(text: &str) -> String {
+    let typeable: String = text
+        .chars()
+        .map(|c| match typeable_replacement(c) {
+            Some(replacement) => replacement.to_owned(),
+            None if c.is_control() && c != '\n' => " ".to_owned(),
+            None => c.to_string(),
+        })
+        .collect();
+    flatten_prose(&typeable)
+}
+
+/// Where a sentence ends. Closing quotes and brackets stay with the sentence they close.
+fn sentences(text: &str) -> Vec<String> {
+    if split::is_wide_character(text) {
+        let mut sentences = vec![];
+        let mut sentence = String::new();
+        for c in text.chars() {
+            if ['」', '』', '”', '"', ')', '）'].contains(&c)
+                || !sentence.ends_with(['。', '！', '？', '!', '?'])
+            {
+                sentence.push(c);
+            } else {
+                sentences.push(std::mem::take(&mut sentence));
+                sentence.push(c);
+            }
+        }
+        sentences.push(sentence);
+        sentences
+            .into_iter()
+            .map(|sentence| sentence.trim().to_owned())
+            .filter(|sentence| !sentence.is_empty())
+            .collect()
+    } else {
+        let mut sentences = vec![];
+        let mut words: Vec<&str> = vec![];
+        for word in text.split_whitespace() {
+            words.push(word);
+            if word
+                .trim_end_matches(['"', '\'', ')', ']'])
+                .ends_with(['.', '!', '?'])
+            {
+                sentences.push(words.join(" "));
+                words.clear();
+            }
+        }
+        if !words.is_empty() {
+            sentences.push(words.join(" "));
+        }
+        sentences
+    }
+}
+
+/// Break a sentence that's too long to be a passage on its own at word boundaries
+fn fit_sentence(sentence: &str, max_chars: usize) -> Vec<String> {
+    let wide = split::is_wide_character(sentence);
+    let mut pieces = vec![];
+    let mut piece = String::new();
+    for word in split::to_words(sentence, false) {
+        let separator = if piece.is_empty() || wide { 0 } else { 1 };
+        if !piece.is_empty() && piece.chars().count() + separator + word.chars().count() > max_chars
+        {
+            pieces.push(std::mem::take(&mut piece));
+        } else if separator == 1 {
+            piece.push(' ');
+        }
+        piece.push_str(word);
+    }
+    if !piece.is_empty() {
+        pieces.push(piece);
+    }
+    pieces
+}
+
+/// Group sentences into passages between `min_chars` and `max_chars` long. Passages end at the end
+/// of a paragraph when they're long enough, and never carry on into a new section.
+fn to_passages(
+    paragraphs: &[Paragraph],
+    title: &str,
+    min_chars: usize,
+    max_chars: usize,
+) -> Vec<Passage> {
+    let attribution = |heading: &Option<String>| match heading {
+        Some(heading) => format!("{} - {}", title, heading),
+        None => title.to_owned(),
+    };
+
+    let mut passages = vec![];
+    let mut text = String::new();
+    let mut heading = &None;
+    let mut end_passage = |text: &mut String, heading: &Option<String>| {
+        if !text.is_empty() {
+            passages.push(Passage {
+                text: std::mem::take(text),
+                attribution: attribution(heading),
+            });
+        }
+    };
+
+    for paragraph in paragraphs {
+        if paragraph.heading != *heading {
+            end_passage(&mut text, heading);
+            heading = &paragraph.heading;
+        }
+        let wide = split::is_wide_character(&paragraph.text);
+        for sentence in sentences(&paragraph.text) {
+            for piece in fit_sentence(&sentence, max_chars) {
+                let separator = if text.is_empty() || wide { 0 } else { 1 };
+                if text.chars().count() + separator + piece.chars().count() > max_chars {
+                    end_passage(&mut text, heading);
+                } else if separator == 1 {
+                    text.push(' ');
+                }
+                text.push_str(&piece);
+            }
+        }
+        if text.chars().count() >= min_chars {
+            end_passage(&mut text, heading);
+        }
+    }
+    end_passage(&mut text, heading);
+    passages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lang_pack::lint::{lint_pack, Severity};
+    use crate::test_utils::TempDir;
+
+    fn passage_texts(paragraphs: &[Paragraph], min_chars: usize, max_chars: usize) -> Vec<String> {
+        to_passages(paragraphs, "Book", min_chars, max_chars)
+            .into_iter()
+            .map(|passage| passage.text)
+            .collect()
+    }
+
+    fn paragraph(text: &str) -> Paragraph {
+        Paragraph {
+            heading: None,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn groups_sentences_into_passages() {
+        let paragraphs = [
+            paragraph("One two. Three four five. Six seven eight nine ten."),
+            paragraph("Short."),
+            paragraph("Another paragraph here."),
+        ];
+        // Sentences are kept whole, and the short paragraph is carried into the next one
+        assert_eq!(
+            passage_texts(&paragraphs, 10, 30),
+            vec![
+                "One two. Three four five.",
+                "Six seven eight nine ten.",
+                "Short. Another paragraph here."
+            ]
+        );
+        // Sentences too long for a passage are broken between words
+        assert_eq!(
+            passage_texts(&paragraphs[..1], 1, 12),
+            vec![
+                "One two.",
+                "Three four",
+                "five.",
+                "Six seven",
+                "eight nine",
+                "ten."
+            ]
+        );
+    }
+
+    #[test]
+    fn sections_are_attributed() {
+        let paragraphs = [
+            paragraph("Before any heading."),
+            Paragraph {
+                heading: Some("Usage".to_owned()),
+                text: "Run it.".to_owned(),
+            },
+        ];
+        let passages = to_passages(&paragraphs, "Guide", 1000, 1000);
+        assert_eq!(
+            passages,
+            vec![
+                Passage {
+                    text: "Before any heading.".to_owned(),
+                    attribution: "Guide".to_owned()
+                },
+                Passage {
+                    text: "Run it.".to_owned(),
+                    attribution: "Guide - Usage".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_markdown() {
+        let (title, paragraphs) = paragraphs(
+            "# Typeracer\n\n[![build](badge.svg)](https://ci.example.com)\n\nA **fast** typing game, see [the docs](docs/config.md).\n![logo](logo.png)\n\n## Running\n\n```bash\n$ typeracer\n```\n\n- Run `typeracer` to play.\n- Hit ^C to quit\n\n| Key | Action |\n|-----|--------|\n\n> Use snake_case _please_.\n",
+            TextFormat::Markdown,
+        );
+        assert_eq!(title.as_deref(), Some("Typeracer"));
+        assert_eq!(
+            paragraphs,
+            vec![
+                paragraph("A fast typing game, see the docs."),
+                Paragraph {
+                    heading: Some("Running".to_owned()),
+                    text: "Run typeracer to play.".to_owned()
+                },
+                Paragraph {
+                    heading: Some("Running".to_owned()),
+                    text: "Hit ^C to quit".to_owned()
+                },
+                Paragraph {
+                    heading: Some("Running".to_owned()),
+                    text: "Use snake_case please.".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_gutenberg_license() {
+        let book = "The Project Gutenberg eBook of Fables\n\nTitle: Fables\n\nAuthor: Aesop\n\n*** START OF THE PROJECT GUTENBERG EBOOK FABLES ***\n\nTHE FOX AND THE GRAPES\n\n[Illustration]\n\nA hungry _Fox_ saw some\n“fine” bunches of Grapes.\n\n*** END OF THE PROJECT GUTENBERG EBOOK FABLES ***\n\nLicense text.\n";
+        let (title, paragraphs) = paragraphs(book, TextFormat::Gutenberg);
+        assert_eq!(title.as_deref(), Some("Fables by Aesop"));
+        assert_eq!(
+            paragraphs,
+            vec![paragraph(
+                "A hungry Fox saw some \"fine\" bunches of Grapes."
+            )]
+        );
+    }
+
+    #[test]
+    fn created_packs_pass_lint() {
+        let dir = TempDir::new("create");
+        dir.write(
+            "notes.txt",
+            "The first paragraph\nis wrapped — like a book.\n\nThe second one isn’t.\n",
+        );
+        let source = dir.join("notes.txt");
+
+        let pack_dir = dir.join("pack");
+        let count = create_pack(&source, &pack_dir, 1, 100).unwrap();
+        let report = lint_pack(&pack_dir);
+        let first = fs::read_to_string(pack_dir.join("notes").join("1")).unwrap();
+
+        assert_eq!(count, 2);
+        assert_eq!(
+            report.count(Severity::Error) + report.count(Severity::Warning),
+            0
+        );
+        assert_eq!(
+            first,
+            "The first paragraph is wrapped - like a book.\nnotes\n"
+        );
+    }
+
+    #[test]
+    fn existing_packs_are_not_replaced() {
+        let dir = TempDir::new("create-existing");
+        dir.write("notes.txt", "Something new.\n");
+        dir.write("pack/notes/1", "Edited by hand.\nnotes\n");
+
+        let created = create_pack(&dir.join("notes.txt"), &dir.join("pack"), 1, 100);
+        let kept = fs::read_to_string(dir.join("pack").join("notes").join("1")).unwrap();
+
+        assert_eq!(created.unwrap_err().kind(), ErrorKind::AlreadyExists);
+        assert_eq!(kept, "Edited by hand.\nnotes\n");
+    }
+}
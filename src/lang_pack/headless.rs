@@ -0,0 +1,322 @@
+use std::{
+    fmt,
+    fs::{read_to_string, remove_dir_all},
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::{
+    config::{editor, ExtraRepo, TyperacerConfig},
+    dirs::setup_dirs,
+    lang_pack::{
+        check_proper_version, clean_extra_repos, create, download_and_checkout, install_extra_pack,
+        lint::{lint_pack, Severity},
+        local_pack, repos_to_clean,
+    },
+};
+
+/// What state an installed pack is in compared to the version in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PackStatus {
+    Ready,
+    Outdated,
+    Missing,
+}
+
+impl fmt::Display for PackStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PackStatus::Ready => write!(f, "ready"),
+            PackStatus::Outdated => write!(f, "needs update"),
+            PackStatus::Missing => write!(f, "not installed"),
+        }
+    }
+}
+
+fn pack_status(pack_dir: &Path, version: &str) -> PackStatus {
+    if !pack_dir.exists() {
+        PackStatus::Missing
+    } else if check_proper_version(version, pack_dir) {
+        PackStatus::Ready
+    } else {
+        PackStatus::Outdated
+    }
+}
+
+/// The version on the first line of a pack's version file, if it has one
+fn installed_version(pack_dir: &Path) -> Option<String> {
+    read_to_string(pack_dir.join("version"))
+        .ok()
+        .and_then(|version| version.lines().next().map(|line| line.trim().to_owned()))
+}
+
+/// Print every configured pack along with what version is installed, and any installed packs that
+/// are no longer in the config.
+pub fn list(config: &TyperacerConfig) -> Result<(), Error> {
+    let quote_dirs = setup_dirs::get_quote_dirs();
+    let packs = std::iter::once((
+        "main",
+        config.repo.as_str(),
+        config.repo_version.as_str(),
+        quote_dirs.main_pack_dir.clone(),
+    ))
+    .chain(config.extra_repos.iter().map(|repo| {
+        (
+            repo.name.as_str(),
+            repo.url.as_str(),
+            repo.version.as_str(),
+            quote_dirs.extra_pack_dir.join(&repo.name),
+        )
+    }));
+
+    println!(
+        "{:<16} {:<10} {:<10} {:<14} Source",
+        "Name", "Version", "Installed", "Status"
+    );
+    for (name, url, version, pack_dir) in packs {
+        println!(
+            "{:<16} {:<10} {:<10} {:<14} {}",
+            name,
+            version,
+            installed_version(&pack_dir).unwrap_or_else(|| "-".to_owned()),
+            pack_status(&pack_dir, version).to_string(),
+            url
+        );
+    }
+
+    let stale_repos = repos_to_clean(config);
+    if !stale_repos.is_empty() {
+        println!("\nUnreferenced packs, delete them with `typeracer packs clean`:");
+        for stale_repo in stale_repos.iter() {
+            println!("- {}", stale_repo.display());
+        }
+    }
+    Ok(())
+}
+
+/// Download the main pack and install every extra pack at the versions in the config.
+/// Keeps going when one fails so a single bad repo doesn't hold up the rest.
+pub fn update(config: &TyperacerConfig) -> Result<(), Error> {
+    setup_dirs::create_data_dir(None);
+    let quote_dirs = setup_dirs::get_quote_dirs();
+    let mut failures = 0;
+
+    println!(
+        "Updating main lang pack from {} to {}",
+        config.repo, config.repo_version
+    );
+    if let Err(e) = download_and_checkout(
+        &config.repo,
+        &quote_dirs.main_pack_dir,
+        &config.repo_version,
+    ) {
+        eprintln!("Trouble downloading main repo: {}", e);
+        failures += 1;
+    }
+
+    for repo in config.extra_repos.iter() {
+        println!(
+            "Installing {} from {} at {}",
+            repo.name, repo.url, repo.version
+        );
+        if let Err(e) = install_extra_pack(repo, &quote_dirs.extra_pack_dir.join(&repo.name)) {
+            eprintln!("Trouble installing extra repo {}: {}", repo.name, e);
+            failures += 1;
+        }
+    }
+
+    let stale_repos = repos_to_clean(config);
+    if !stale_repos.is_empty() {
+        println!(
+            "{} unreferenced pack(s) left behind, delete them with `typeracer packs clean`",
+            stale_repos.len()
+        );
+    }
+
+    if failures == 0 {
+        println!("Lang packs are up to date");
+        Ok(())
+    } else {
+        Err(Error::other(format!(
+            "{} lang pack(s) failed to update",
+            failures
+        )))
+    }
+}
+
+/// Packs are installed into a folder named after them, so the name has to make a good folder name
+/// that isn't taken already.
+fn check_new_pack_name(config: &TyperacerConfig, name: &str) -> Result<(), Error> {
+    if name.is_empty() || name == "." || name == ".." || name.contains(std::path::is_separator) {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("{} can't be used as a pack name", name),
+        ));
+    }
+    if config
+        .extra_repos
+        .iter()
+        .any(|existing| existing.name == name)
+    {
+        return Err(Error::new(
+            ErrorKind::AlreadyExists,
+            format!("An extra repo named {} is already configured", name),
+        ));
+    }
+    Ok(())
+}
+
+/// Install an extra pack and add it to the config file once it's installed.
+pub fn add(config: &TyperacerConfig, repo: ExtraRepo) -> Result<(), Error> {
+    check_new_pack_name(config, &repo.name)?;
+    let repo = ExtraRepo {
+        url: local_pack::config_url(&repo.url)?,
+        ..repo
+    };
+
+    let pack_dir = setup_dirs::get_quote_dirs().extra_pack_dir.join(&repo.name);
+    install_extra_pack(&repo, &pack_dir)?;
+    if let Err(e) = editor::add_extra_repo(&repo) {
+        // Don't leave behind a pack the config doesn't know about
+        remove_dir_all(&pack_dir)?;
+        return Err(e);
+    }
+    println!("Added {} at version {}", repo.name, repo.version);
+    Ok(())
+}
+
+/// Split a text file into a new pack and install it as an extra pack.
+/// The pack is kept in the created packs folder so it can be edited or shared afterwards.
+pub fn create(
+    config: &TyperacerConfig,
+    source: &Path,
+    name: &str,
+    min_chars: usize,
+    max_chars: usize,
+) -> Result<(), Error> {
+    check_new_pack_name(config, name)?;
+
+    let pack_dir = setup_dirs::get_created_packs_dir().join(name);
+    let passages = create::create_pack(source, &pack_dir, min_chars, max_chars)?;
+    println!(
+        "Split {} into {} passage(s) in {}",
+        source.display(),
+        passages,
+        pack_dir.display()
+    );
+    let added = add(
+        config,
+        ExtraRepo {
+            name: name.to_owned(),
+            url: pack_dir.to_string_lossy().into_owned(),
+            version: create::CREATED_PACK_VERSION.to_owned(),
+        },
+    );
+    if added.is_err() {
+        // Nothing refers to the pack if it couldn't be added
+        let _ = remove_dir_all(&pack_dir);
+    }
+    added
+}
+
+/// Take an extra pack out of the config file and delete it.
+/// A created pack is only deleted from the extra packs, the copy in the created packs folder is
+/// left alone.
+pub fn remove(name: &str) -> Result<(), Error> {
+    editor::remove_extra_repo(name)?;
+    let pack_dir = setup_dirs::get_quote_dirs().extra_pack_dir.join(name);
+    if pack_dir.exists() {
+        remove_dir_all(&pack_dir)?;
+    }
+    println!("Removed {}", name);
+    let created_pack_dir = setup_dirs::get_created_packs_dir().join(name);
+    if created_pack_dir.exists() {
+        println!(
+            "Left the pack it was created from in {}, delete it before creating another pack named {}",
+            created_pack_dir.display(),
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Delete installed packs that are no longer in the config.
+pub fn clean(config: &TyperacerConfig) -> Result<(), Error> {
+    let stale_repos = repos_to_clean(config);
+    if stale_repos.is_empty() {
+        println!("No unreferenced packs to clean up");
+        return Ok(());
+    }
+
+    for stale_repo in stale_repos.iter() {
+        println!("Deleting {}", stale_repo.display());
+    }
+    if clean_extra_repos(&stale_repos) {
+        Ok(())
+    } else {
+        Err(Error::other("Issue deleting old repos"))
+    }
+}
+
+/// Check a pack for problems before sharing it, failing if any passage can't be played.
+pub fn lint(pack_dir: &Path) -> Result<(), Error> {
+    if !pack_dir.is_dir() {
+        return Err(Error::new(
+            ErrorKind::NotFound,
+            format!("{} is not a directory", pack_dir.display()),
+        ));
+    }
+
+    let report = lint_pack(pack_dir);
+    for problem in report.problems.iter() {
+        println!("{}", problem);
+    }
+    let errors = report.count(Severity::Error);
+    println!(
+        "Checked {} passage(s) in {} quote folder(s): {} error(s), {} warning(s)",
+        report.passages,
+        report.quote_dirs,
+        errors,
+        report.count(Severity::Warning)
+    );
+
+    if errors == 0 {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "{} has problems that stop passages being played",
+                pack_dir.display()
+            ),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+    use std::fs;
+
+    #[test]
+    fn status_compares_installed_version() {
+        let dir = TempDir::new("pack-status");
+        let pack_dir = dir.join("pack");
+        let missing = pack_status(&pack_dir, "1.0.0");
+        fs::create_dir_all(&pack_dir).unwrap();
+        fs::write(pack_dir.join("version"), "1.0.0\nx").unwrap();
+        let statuses = (
+            missing,
+            pack_status(&pack_dir, "1.0.0"),
+            pack_status(&pack_dir, "1.1.0"),
+        );
+        let version = installed_version(&pack_dir);
+
+        assert_eq!(
+            statuses,
+            (PackStatus::Missing, PackStatus::Ready, PackStatus::Outdated)
+        );
+        assert_eq!(version.as_deref(), Some("1.0.0"));
+    }
+}
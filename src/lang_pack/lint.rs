@@ -0,0 +1,461 @@
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use crate::passage_controller::passage_file::{self, CODE_PACK_MARKER};
+
+/// Prose is typed as one long line, past this it takes more than a screen to show
+const MAX_PASSAGE_CHARS: usize = 1000;
+/// Code is typed line by line, past this lines wrap on most terminals
+const MAX_CODE_LINE_CHARS: usize = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Typeracer won't use the pack or passage at all
+    Error,
+    /// The passage will be played, but probably not the way the author meant it to be
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    pub severity: Severity,
+    /// Relative to the pack, empty for problems with the pack as a whole
+    pub path: PathBuf,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.as_os_str().is_empty() {
+            write!(f, "{}: {}", self.severity, self.message)
+        } else {
+            write!(
+                f,
+                "{}: {}: {}",
+                self.severity,
+                self.path.display(),
+                self.message
+            )
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct LintReport {
+    pub quote_dirs: usize,
+    /// Passage files that can be played, problems or not
+    pub passages: usize,
+    pub problems: Vec<Problem>,
+}
+
+impl LintReport {
+    pub fn count(&self, severity: Severity) -> usize {
+        self.problems
+            .iter()
+            .filter(|problem| problem.severity == severity)
+            .count()
+    }
+
+    fn add(&mut self, severity: Severity, path: &Path, message: String) {
+        self.problems.push(Problem {
+            severity,
+            path: path.to_path_buf(),
+            message,
+        });
+    }
+}
+
+/// Check a lang pack against docs/lang-pack-format.md, reporting anything that would stop a
+/// passage from being played or make it a pain to type.
+pub fn lint_pack(pack_dir: &Path) -> LintReport {
+    let mut report = LintReport::default();
+    lint_version_file(pack_dir, &mut report);
+
+    let mut seen_passages: HashMap<String, PathBuf> = HashMap::new();
+    for entry in visible_entries(pack_dir, Path::new(""), &mut report) {
+        let path = pack_dir.join(&entry);
+        if !path.is_dir() {
+            if entry != Path::new("version") {
+                report.add(
+                    Severity::Warning,
+                    &entry,
+                    "ignored, passages go inside a quote directory".to_owned(),
+                );
+            }
+            continue;
+        }
+
+        report.quote_dirs += 1;
+        let is_code_pack = path.join(CODE_PACK_MARKER).exists();
+        let quote_files = visible_entries(&path, &entry, &mut report);
+        if quote_files.is_empty() {
+            report.add(
+                Severity::Warning,
+                &entry,
+                "quote directory has no passages".to_owned(),
+            );
+        }
+        for quote_file in quote_files {
+            lint_passage(
+                pack_dir,
+                &quote_file,
+                is_code_pack,
+                &mut seen_passages,
+                &mut report,
+            );
+        }
+    }
+
+    if report.quote_dirs == 0 {
+        report.add(
+            Severity::Error,
+            Path::new(""),
+            "no quote directories, a pack needs at least one folder of passages".to_owned(),
+        );
+    } else if report.passages == 0 {
+        report.add(
+            Severity::Error,
+            Path::new(""),
+            "no passages that can be played".to_owned(),
+        );
+    }
+    report
+}
+
+/// Entries of a directory relative to the pack, sorted so the output is stable. Hidden files like
+/// `.git` and the code pack marker are skipped the same as they are in game.
+fn visible_entries(dir: &Path, relative_dir: &Path, report: &mut LintReport) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.add(
+                Severity::Error,
+                relative_dir,
+                format!("unable to read directory: {}", e),
+            );
+            return vec![];
+        }
+    };
+    let mut names: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name())
+        .filter(|name| !name.to_string_lossy().starts_with('.'))
+        .map(|name| relative_dir.join(name))
+        .collect();
+    names.sort();
+    names
+}
+
+fn lint_version_file(pack_dir: &Path, report: &mut LintReport) {
+    let version_path = Path::new("version");
+    let contents = match fs::read_to_string(pack_dir.join(version_path)) {
+        Ok(contents) => contents,
+        Err(_) => {
+            report.add(
+                Severity::Error,
+                version_path,
+                "missing, every pack needs a version file".to_owned(),
+            );
+            return;
+        }
+    };
+
+    let lines: Vec<&str> = contents.lines().collect();
+    if lines
+        .first()
+        .map_or("", |version| version.trim())
+        .is_empty()
+    {
+        report.add(
+            Severity::Error,
+            version_path,
+            "the first line should be the pack's version".to_owned(),
+        );
+    } else if lines.len() < 2 {
+        report.add(
+            Severity::Error,
+            version_path,
+            "needs a second line with a random uuid".to_owned(),
+        );
+    } else if lines[2..].iter().any(|line| !line.trim().is_empty()) {
+        report.add(
+            Severity::Warning,
+            version_path,
+            "only the version and a uuid are read, anything after the second line is ignored"
+                .to_owned(),
+        );
+    }
+}
+
+fn lint_passage(
+    pack_dir: &Path,
+    quote_file: &Path,
+    is_code_pack: bool,
+    seen_passages: &mut HashMap<String, PathBuf>,
+    report: &mut LintReport,
+) {
+    let path = pack_dir.join(quote_file);
+    if path.is_dir() {
+        report.add(
+            Severity::Error,
+            quote_file,
+            "quote directories can't be nested, move its passages up a level".to_owned(),
+        );
+        return;
+    }
+
+    let passage_info = match passage_file::read_passage_file(&path) {
+        Ok(passage_info) => passage_info,
+        Err(e) => {
+            report.add(Severity::Error, quote_file, e.to_string());
+            return;
+        }
+    };
+    report.passages += 1;
+
+    let is_structured = matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("toml" | "json")
+    );
+    if !is_structured {
+        if passage_info.title.trim().is_empty() {
+            report.add(
+                Severity::Warning,
+                quote_file,
+                "attribution is empty".to_owned(),
+            );
+        }
+        if !is_code_pack {
+            let extra_lines = fs::read_to_string(&path)
+                .map(|contents| {
+                    contents
+                        .lines()
+                        .skip(2)
+                        .filter(|line| !line.trim().is_empty())
+                        .count()
+                })
+                .unwrap_or(0);
+            if extra_lines > 0 {
+                report.add(
+                    Severity::Warning,
+                    quote_file,
+                    format!(
+                        "only the first two lines are read, {} more line(s) are ignored",
+                        extra_lines
+                    ),
+                );
+            }
+        }
+    }
+
+    for message in unusual_characters(&passage_info.passage, is_code_pack) {
+        report.add(Severity::Warning, quote_file, message);
+    }
+
+    if is_code_pack {
+        for (line_num, line) in passage_info.passage.lines().enumerate() {
+            let length = line.chars().count();
+            if length > MAX_CODE_LINE_CHARS {
+                report.add(
+                    Severity::Warning,
+                    quote_file,
+                    format!(
+                        "line {} of the code is {} characters long, keep lines under {}",
+                        line_num + 1,
+                        length,
+                        MAX_CODE_LINE_CHARS
+                    ),
+                );
+            }
+        }
+    } else {
+        let length = passage_info.passage.chars().count();
+        if length > MAX_PASSAGE_CHARS {
+            report.add(
+                Severity::Warning,
+                quote_file,
+                format!(
+                    "passage is {} characters long, keep passages under {} so they fit on screen",
+                    length, MAX_PASSAGE_CHARS
+                ),
+            );
+        }
+    }
+
+    if let Some(first_seen) = seen_passages.get(&passage_info.passage) {
+        report.add(
+            Severity::Warning,
+            quote_file,
+            format!("same passage as {}", first_seen.display()),
+        );
+    } else {
+        seen_passages.insert(passage_info.passage, quote_file.to_path_buf());
+    }
+}
+
+/// What to type in place of a character that looks like something on the keyboard without being
+/// it. Invisible characters are replaced with nothing.
+pub fn typeable_replacement(c: char) -> Option<&'static str> {
+    match c {
+        '\u{200B}'..='\u{200D}' | '\u{2060}' | '\u{FEFF}' | '\u{00AD}' => Some(""),
+        '\u{00A0}' | '\u{2000}'..='\u{200A}' | '\u{202F}' | '\u{205F}' => Some(" "),
+        '‘' | '’' | '‚' | '′' => Some("'"),
+        '“' | '”' | '„' | '″' => Some("\""),
+        '‐' | '‑' | '‒' | '–' | '—' | '―' | '−' => Some("-"),
+        '…' => Some("..."),
+        _ => None,
+    }
+}
+
+/// What's wrong with typing `c`, if anything. Wide characters are fine, plenty of packs are
+/// written in languages without a latin alphabet. It's characters that look like something on the
+/// keyboard without being it, or that can't be seen at all, that trip people up.
+fn character_problem(c: char, is_code_pack: bool) -> Option<String> {
+    match (c, typeable_replacement(c)) {
+        ('\n', _) if is_code_pack => None,
+        ('\u{FFFD}', _) => Some("the file might not be saved as UTF-8".to_owned()),
+        (_, Some("")) => Some("it's invisible".to_owned()),
+        (_, Some(" ")) => Some("use a regular space instead".to_owned()),
+        (_, Some(replacement)) => Some(format!("use {} instead", replacement)),
+        (c, None) if c.is_control() => Some("it can't be typed".to_owned()),
+        (_, None) => None,
+    }
+}
+
+/// One message per unusual character in the passage, in the order they first show up
+fn unusual_characters(passage: &str, is_code_pack: bool) -> Vec<String> {
+    let mut found: Vec<(char, usize, String)> = vec![];
+    for c in passage.chars() {
+        if let Some(existing) = found.iter_mut().find(|(seen, _, _)| *seen == c) {
+            existing.1 += 1;
+        } else if let Some(problem) = character_problem(c, is_code_pack) {
+            found.push((c, 1, problem));
+        }
+    }
+
+    found
+        .into_iter()
+        .map(|(c, count, problem)| format!("{} x {:?} (U+{:04X}), {}", count, c, c as u32, problem))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    /// A fresh pack dir holding the files
+    fn pack_with(files: &[(&str, &str)]) -> TempDir {
+        let pack_dir = TempDir::new("lint");
+        for (name, contents) in files {
+            pack_dir.write(name, contents);
+        }
+        pack_dir
+    }
+
+    fn lint_messages(files: &[(&str, &str)]) -> Vec<String> {
+        lint_pack(pack_with(files).path())
+            .problems
+            .iter()
+            .map(|problem| problem.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn well_formed_pack_has_no_problems() {
+        let pack_dir = pack_with(&[
+            ("version", "1.0.0\nc725044b-f4af-45d6-8cc0-dcc7e48fa999"),
+            (
+                "default/voltaire",
+                "The best is the enemy of the good.\nVoltaire",
+            ),
+            ("chinese/proverb", "千里之行，始于足下。\n老子"),
+            ("rust/.code", ""),
+            (
+                "rust/hello",
+                "Hello world\nfn main() {\n\tprintln!(\"hi\");\n}\n",
+            ),
+        ]);
+        let report = lint_pack(pack_dir.path());
+
+        assert_eq!(report.problems, vec![]);
+        assert_eq!((report.quote_dirs, report.passages), (3, 3));
+    }
+
+    #[test]
+    fn version_file_is_checked() {
+        let passage = (
+            "default/voltaire",
+            "The best is the enemy of the good.\nVoltaire",
+        );
+        assert_eq!(
+            lint_messages(&[passage]),
+            vec!["error: version: missing, every pack needs a version file"]
+        );
+        assert_eq!(
+            lint_messages(&[passage, ("version", "1.0.0")]),
+            vec!["error: version: needs a second line with a random uuid"]
+        );
+        assert_eq!(
+            lint_messages(&[passage, ("version", "\nuuid")]),
+            vec!["error: version: the first line should be the pack's version"]
+        );
+        assert_eq!(
+            lint_messages(&[("version", "1.0.0\nuuid")]),
+            vec!["error: no quote directories, a pack needs at least one folder of passages"]
+        );
+    }
+
+    #[test]
+    fn passage_problems_are_reported() {
+        let long_passage = format!("{}\nWordy", "word ".repeat(MAX_PASSAGE_CHARS / 5 + 1));
+        let messages = lint_messages(&[
+            ("version", "1.0.0\nuuid"),
+            ("README", "All about the pack"),
+            ("default/a", "The best is the enemy of the good.\nVoltaire"),
+            (
+                "default/b",
+                "The best is the enemy of the good.\nVoltaire again",
+            ),
+            ("default/short", "Just a passage"),
+            ("default/empty", "   \nNobody"),
+            ("default/extra", "Typing is fun\nMe\nAnd a third line"),
+            (
+                "default/quotes",
+                "“Don’t panic” he said…\u{200B}\nDouglas Adams",
+            ),
+            ("default/long", &long_passage),
+            ("empty/.keep", ""),
+        ]);
+
+        assert_eq!(
+            messages,
+            vec![
+                "warning: README: ignored, passages go inside a quote directory",
+                "warning: default/b: same passage as default/a",
+                "error: default/empty: passage has no text to type",
+                "warning: default/extra: only the first two lines are read, 1 more line(s) are ignored",
+                "warning: default/long: passage is 1004 characters long, keep passages under 1000 so they fit on screen",
+                "warning: default/quotes: 1 x '“' (U+201C), use \" instead",
+                "warning: default/quotes: 1 x '’' (U+2019), use ' instead",
+                "warning: default/quotes: 1 x '”' (U+201D), use \" instead",
+                "warning: default/quotes: 1 x '…' (U+2026), use ... instead",
+                "warning: default/quotes: 1 x '\\u{200b}' (U+200B), it's invisible",
+                "error: default/short: needs a passage on the first line and attribution on the second",
+                "warning: empty: quote directory has no passages",
+            ]
+        );
+    }
+}
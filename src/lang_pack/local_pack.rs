@@ -0,0 +1,255 @@
+use flate2::read::GzDecoder;
+use std::{
+    fs::{self, File},
+    io::{Error, ErrorKind},
+    path::Path,
+};
+
+use crate::lang_pack::check_proper_version;
+
+/// Where an extra lang pack is installed from
+#[derive(Debug, PartialEq, Eq)]
+pub enum PackSource<'a> {
+    Git(&'a str),
+    Directory(&'a Path),
+    TarGz(&'a Path),
+    Zip(&'a Path),
+}
+
+impl<'a> PackSource<'a> {
+    /// Anything that isn't an archive or a plain directory on disk is treated as a git remote.
+    /// Directories with a .git folder are still cloned so they keep tracking the configured version.
+    pub fn from_url(url: &'a str) -> PackSource<'a> {
+        let path = Path::new(url);
+        let lowercase_url = url.to_lowercase();
+        if lowercase_url.ends_with(".tar.gz") || lowercase_url.ends_with(".tgz") {
+            PackSource::TarGz(path)
+        } else if lowercase_url.ends_with(".zip") {
+            PackSource::Zip(path)
+        } else if path.is_dir() && !path.join(".git").exists() {
+            PackSource::Directory(path)
+        } else {
+            PackSource::Git(url)
+        }
+    }
+}
+
+/// The url to keep in the config file for a pack. Paths on disk are made absolute, so the pack
+/// can still be found when typeracer is run from somewhere else.
+pub fn config_url(url: &str) -> Result<String, Error> {
+    match PackSource::from_url(url) {
+        PackSource::Git(_) if !Path::new(url).exists() => Ok(url.to_owned()),
+        _ => fs::canonicalize(url)
+            .map(|path| path.to_string_lossy().into_owned())
+            .map_err(|e| Error::new(e.kind(), format!("Can't find {}: {}", url, e))),
+    }
+}
+
+/// Copy or unpack a lang pack from disk into `pack_dir`, replacing whatever was there before.
+/// The pack's version file has to match `version` just like a pack from git, a mismatched pack is
+/// removed again rather than left half installed.
+pub fn install(source: &PackSource, pack_dir: &Path, version: &str) -> Result<(), Error> {
+    if pack_dir.exists() {
+        fs::remove_dir_all(pack_dir)?;
+    }
+    fs::create_dir_all(pack_dir)?;
+
+    let installed = unpack(source, pack_dir).and_then(|()| {
+        if check_proper_version(version, pack_dir) {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("version file does not match version {}", version),
+            ))
+        }
+    });
+
+    if installed.is_err() {
+        fs::remove_dir_all(pack_dir)?;
+    }
+    installed
+}
+
+fn unpack(source: &PackSource, pack_dir: &Path) -> Result<(), Error> {
+    match source {
+        PackSource::Git(url) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("{} is not on disk", url),
+            ))
+        }
+        PackSource::Directory(dir) => copy_dir(dir, pack_dir)?,
+        PackSource::TarGz(archive) => {
+            tar::Archive::new(GzDecoder::new(File::open(archive)?)).unpack(pack_dir)?
+        }
+        PackSource::Zip(archive) => zip::ZipArchive::new(File::open(archive)?)
+            .and_then(|mut zip| zip.extract(pack_dir))
+            .map_err(Error::other)?,
+    }
+    flatten(pack_dir)
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), Error> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            copy_dir(&entry.path(), &to.join(entry.file_name()))?;
+        } else {
+            fs::copy(entry.path(), to.join(entry.file_name()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Archives usually hold a single folder with the pack inside, rather than the pack itself.
+/// Move the contents of that folder up so the version file sits at the top of the pack.
+fn flatten(pack_dir: &Path) -> Result<(), Error> {
+    if pack_dir.join("version").exists() {
+        return Ok(());
+    }
+    let entries = fs::read_dir(pack_dir)?.collect::<Result<Vec<_>, _>>()?;
+    if entries.len() != 1 || !entries[0].file_type()?.is_dir() {
+        return Ok(());
+    }
+
+    // Out of the way first, the folder might hold something with the same name as itself
+    let nested = pack_dir.join(".typeracer-unpacking");
+    fs::rename(entries[0].path(), &nested)?;
+    for entry in fs::read_dir(&nested)? {
+        let entry = entry?;
+        fs::rename(entry.path(), pack_dir.join(entry.file_name()))?;
+    }
+    fs::remove_dir(nested)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    /// A pack at version 1.0.0 with a single passage
+    fn write_pack(dir: &Path) {
+        fs::create_dir_all(dir.join("quotes")).unwrap();
+        fs::write(dir.join("version"), "1.0.0\nx").unwrap();
+        fs::write(
+            dir.join("quotes").join("cat"),
+            "the cat sat on the mat\nmat",
+        )
+        .unwrap();
+    }
+
+    fn installed_passage(pack_dir: &Path) -> String {
+        fs::read_to_string(pack_dir.join("quotes").join("cat")).unwrap()
+    }
+
+    #[test]
+    fn sources_are_told_apart() {
+        let dir = TempDir::new("pack-source");
+        assert_eq!(
+            PackSource::from_url("https://gitlab.com/ttyperacer/lang-packs.git"),
+            PackSource::Git("https://gitlab.com/ttyperacer/lang-packs.git")
+        );
+        assert_eq!(
+            PackSource::from_url("/mnt/shared/passages.tar.gz"),
+            PackSource::TarGz(Path::new("/mnt/shared/passages.tar.gz"))
+        );
+        assert_eq!(
+            PackSource::from_url("/mnt/shared/passages.ZIP"),
+            PackSource::Zip(Path::new("/mnt/shared/passages.ZIP"))
+        );
+        let url = dir.path().to_str().unwrap();
+        assert_eq!(PackSource::from_url(url), PackSource::Directory(dir.path()));
+
+        // Local git repos are still cloned
+        fs::create_dir(dir.join(".git")).unwrap();
+        assert_eq!(PackSource::from_url(url), PackSource::Git(url));
+    }
+
+    #[test]
+    fn local_paths_are_made_absolute() {
+        let cwd = std::env::current_dir().unwrap();
+        let url = "https://gitlab.com/ttyperacer/lang-packs.git";
+
+        assert_eq!(config_url(url).unwrap(), url);
+        assert_eq!(
+            config_url("src").unwrap(),
+            fs::canonicalize(cwd.join("src")).unwrap().to_string_lossy()
+        );
+        assert_eq!(
+            config_url("missing.tar.gz").unwrap_err().kind(),
+            ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn installs_from_a_directory() {
+        let dir = TempDir::new("pack-dir");
+        write_pack(&dir.join("shared"));
+        let pack_dir = dir.join("extra").join("team");
+
+        install(
+            &PackSource::Directory(&dir.join("shared")),
+            &pack_dir,
+            "1.0.0",
+        )
+        .unwrap();
+        let passage = installed_passage(&pack_dir);
+
+        assert_eq!(passage, "the cat sat on the mat\nmat");
+    }
+
+    #[test]
+    fn installs_from_archives_with_a_folder_inside() {
+        let dir = TempDir::new("pack-archive");
+        write_pack(&dir.join("team-pack"));
+
+        let tar_path = dir.join("team-pack.tar.gz");
+        let mut tar = tar::Builder::new(GzEncoder::new(
+            File::create(&tar_path).unwrap(),
+            Compression::default(),
+        ));
+        tar.append_dir_all("team-pack", dir.join("team-pack"))
+            .unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let zip_path = dir.join("team-pack.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = zip::write::FileOptions::default();
+        zip.add_directory("team-pack/quotes/", options).unwrap();
+        zip.start_file("team-pack/version", options).unwrap();
+        zip.write_all(b"1.0.0\nx").unwrap();
+        zip.start_file("team-pack/quotes/cat", options).unwrap();
+        zip.write_all(b"the cat sat on the mat\nmat").unwrap();
+        zip.finish().unwrap();
+
+        let from_tar = dir.join("extra").join("from-tar");
+        install(&PackSource::TarGz(&tar_path), &from_tar, "1.0.0").unwrap();
+        let from_zip = dir.join("extra").join("from-zip");
+        install(&PackSource::Zip(&zip_path), &from_zip, "1.0.0").unwrap();
+        let passages = (installed_passage(&from_tar), installed_passage(&from_zip));
+
+        assert_eq!(passages.0, "the cat sat on the mat\nmat");
+        assert_eq!(passages.1, "the cat sat on the mat\nmat");
+    }
+
+    #[test]
+    fn wrong_version_is_not_installed() {
+        let dir = TempDir::new("pack-version");
+        write_pack(&dir.join("shared"));
+        let pack_dir = dir.join("extra").join("team");
+
+        let installed = install(
+            &PackSource::Directory(&dir.join("shared")),
+            &pack_dir,
+            "2.0.0",
+        );
+        let left_behind = pack_dir.exists();
+
+        assert!(installed.is_err());
+        assert!(!left_behind);
+    }
+}
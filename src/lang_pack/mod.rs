@@ -11,9 +11,17 @@ use std::{
 use termion::{event::Key, input::TermRead, raw::IntoRawMode, screen::AlternateScreen};
 use tui::{backend::TermionBackend, text::Text, Terminal};
 
-use crate::{config::TyperacerConfig, dirs::setup_dirs};
+use crate::{
+    config::{ExtraRepo, TyperacerConfig},
+    dirs::setup_dirs,
+};
+use local_pack::PackSource;
 
+pub mod create;
+pub mod headless;
 mod lang_pack_render;
+mod lint;
+mod local_pack;
 
 fn download_and_checkout(
     url: &str,
@@ -43,6 +51,17 @@ fn download_and_checkout(
     Ok(())
 }
 
+/// Install an extra pack from wherever it's configured to come from, a git remote, a directory or
+/// an archive.
+fn install_extra_pack(repo: &ExtraRepo, repo_path: &Path) -> Result<(), Error> {
+    match PackSource::from_url(&repo.url) {
+        PackSource::Git(url) => {
+            download_and_checkout(url, repo_path, &repo.version).map_err(Error::other)
+        }
+        source => local_pack::install(&source, repo_path, &repo.version),
+    }
+}
+
 fn check_proper_version(lang_pack_version: &str, data_dir: &Path) -> bool {
     // Somehow the file doesn't exist, so we should just get the right version
     let version_file = data_dir.join("version");
@@ -205,10 +224,9 @@ pub fn retrieve_lang_pack(
             3 => {
                 step_count += 1;
                 for repo in typeracer_config.extra_repos.iter() {
-                    match download_and_checkout(
-                        &repo.url,
+                    match install_extra_pack(
+                        repo,
                         &setup_dirs::get_quote_dirs().extra_pack_dir.join(&repo.name),
-                        &repo.version,
                     ) {
                         Ok(()) => {
                             step_instruction.push(format!(
@@ -217,7 +235,7 @@ pub fn retrieve_lang_pack(
                             ));
                         }
                         Err(e) => step_instruction.push(format!(
-                            "Trouble downloading extra repo: {}, error: {}. Please try again.\n",
+                            "Trouble installing extra repo: {}, error: {}. Please try again.\n",
                             repo.url, e
                         )),
                     };
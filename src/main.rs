@@ -2,22 +2,33 @@
 #![allow(clippy::match_like_matches_macro)]
 use clap::{Arg, Command};
 use crossbeam_channel::unbounded;
-use std::io::{Error, ErrorKind};
+use std::{
+    io::{Error, ErrorKind},
+    path::Path,
+    time::Duration,
+};
 
 mod game;
 mod input;
 mod lang_pack;
+mod multiplayer;
 mod passage_controller;
 mod dirs {
     pub mod setup_dirs;
 }
 
 pub mod actions;
+pub mod backup;
 pub mod config;
 pub mod db;
+pub mod goals;
 pub mod graphs;
+pub mod heatmap;
 pub mod info;
+pub mod report;
 pub mod stats;
+#[cfg(test)]
+mod test_utils;
 
 use actions::Action;
 use rusqlite::Connection;
@@ -40,9 +51,14 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 const VERSION: &str = "DEBUG";
 
 fn main() -> Result<(), Error> {
-    let typeracer_config = config::get_config();
+    // Whether to wait for the user to read about a broken config depends on the subcommand
+    let loaded_config = config::load_config();
+    let repo_version = match &loaded_config {
+        Ok(typeracer_config) => typeracer_config.repo_version.clone(),
+        Err(_) => config::TyperacerConfig::default().repo_version,
+    };
     let args = Command::new("Terminal typing game. Type through passages to see what the fastest times are you can get!")
-        .version(&*format!("Typeracer version: {}, lang pack version: {}", VERSION, typeracer_config.repo_version))
+        .version(&*format!("Typeracer version: {}, lang pack version: {}", VERSION, repo_version))
         .author("Darrien Glasser <me@darrien.dev>")
         .trailing_var_arg(true)
         .arg(
@@ -94,8 +110,312 @@ fn main() -> Result<(), Error> {
             .takes_value(false)
             .help("Play in training mode. All the words you typed wrong are back to haunt you!")
         )
+        .arg(
+            Arg::new("DRILL")
+            .long("drill")
+            .required(false)
+            .takes_value(false)
+            .help("Play in drill mode. Practice on words full of the keys you miss or are slow to hit.")
+        )
+        .arg(
+            Arg::new("TIMED")
+            .long("timed")
+            .required(false)
+            .takes_value(true)
+            .value_name("SECONDS")
+            .possible_values(["15", "30", "60", "120"])
+            .help("Play in timed mode. Type through as many passages as you can before the clock runs out!")
+        )
+        .arg(
+            Arg::new("HOST")
+            .long("host")
+            .required(false)
+            .takes_value(true)
+            .value_name("PORT")
+            .conflicts_with_all(&["JOIN", "TIMED"])
+            .help("Host a race on your local network. Others can join with --join <your ip>:<PORT>")
+        )
+        .arg(
+            Arg::new("JOIN")
+            .long("join")
+            .required(false)
+            .takes_value(true)
+            .value_name("ADDRESS")
+            .conflicts_with("TIMED")
+            .help("Join a race someone is hosting, e.g. --join 192.168.1.10:7878")
+        )
+        .arg(
+            Arg::new("YES")
+            .short('y')
+            .long("yes")
+            .required(false)
+            .takes_value(false)
+            .help("Install lang packs and clean up old ones on startup without asking. Handy for scripts.")
+        )
+        .arg(
+            Arg::new("NAME")
+            .long("name")
+            .required(false)
+            .takes_value(true)
+            .help("Name to show the other racers in multiplayer. Defaults to your username.")
+        )
+        .subcommand(
+            Command::new("stats")
+            .about("Print a summary of your races without starting a game")
+            .arg(
+                Arg::new("FORMAT")
+                .long("format")
+                .required(false)
+                .takes_value(true)
+                .default_value("table")
+                .possible_values(report::Format::values())
+                .help("How to print stats. json and csv are handy for scripts and dashboards.")
+            )
+            .arg(
+                Arg::new("LAST")
+                .long("last")
+                .required(false)
+                .takes_value(true)
+                .value_name("N")
+                .default_value("10")
+                .help("Number of recent races to list")
+            )
+        )
+        .subcommand(
+            Command::new("export")
+            .about("Print your whole race history so it can be imported somewhere else")
+            .arg(
+                Arg::new("FORMAT")
+                .long("format")
+                .required(false)
+                .takes_value(true)
+                .default_value("json")
+                .possible_values(backup::Format::values())
+                .help("Format to export to")
+            )
+        )
+        .subcommand(
+            Command::new("import")
+            .about("Add race history from a file made by the export command")
+            .arg(
+                Arg::new("FILE")
+                .required(true)
+                .takes_value(true)
+                .help("JSON or CSV file to import")
+            )
+        )
+        .subcommand(
+            Command::new("replay")
+            .about("Watch one of your races play out again, keystroke by keystroke")
+            .arg(
+                Arg::new("RACE_ID")
+                .required(true)
+                .takes_value(true)
+                .help("Id of the race to replay, `typeracer stats` lists the ids of your recent races")
+            )
+        )
+        .subcommand(
+            Command::new("packs")
+            .about("Manage lang packs without the interactive installer")
+            .subcommand_required(true)
+            .arg_required_else_help(true)
+            .subcommand(
+                Command::new("list")
+                .about("List configured lang packs and the versions installed")
+            )
+            .subcommand(
+                Command::new("update")
+                .about("Download and install every configured lang pack")
+            )
+            .subcommand(
+                Command::new("add")
+                .about("Install an extra lang pack and add it to your config")
+                .arg(
+                    Arg::new("NAME")
+                    .required(true)
+                    .takes_value(true)
+                    .help("Name the pack is installed as")
+                )
+                .arg(
+                    Arg::new("URL")
+                    .required(true)
+                    .takes_value(true)
+                    .help("Git url, or the path to a directory or .tar.gz/.zip archive of the pack")
+                )
+                .arg(
+                    Arg::new("VERSION")
+                    .long("version")
+                    .required(true)
+                    .takes_value(true)
+                    .help("Branch or tag to check out, has to match the pack's version file")
+                )
+            )
+            .subcommand(
+                Command::new("remove")
+                .about("Delete an extra lang pack and take it out of your config")
+                .arg(
+                    Arg::new("NAME")
+                    .required(true)
+                    .takes_value(true)
+                    .help("Name of the pack to remove")
+                )
+            )
+            .subcommand(
+                Command::new("clean")
+                .about("Delete installed lang packs that are no longer in your config")
+            )
+            .subcommand(
+                Command::new("create")
+                .about("Split a text, Markdown or Project Gutenberg file into a new lang pack and install it")
+                .arg(
+                    Arg::new("FROM")
+                    .long("from")
+                    .required(true)
+                    .takes_value(true)
+                    .value_name("FILE")
+                    .help("File to take the passages from")
+                )
+                .arg(
+                    Arg::new("NAME")
+                    .long("name")
+                    .required(true)
+                    .takes_value(true)
+                    .help("Name the pack is installed as")
+                )
+                .arg(
+                    Arg::new("MIN_LENGTH")
+                    .long("min-length")
+                    .required(false)
+                    .takes_value(true)
+                    .value_name("CHARS")
+                    .default_value("100")
+                    .help("Shortest a passage should be, shorter paragraphs are joined together")
+                )
+                .arg(
+                    Arg::new("MAX_LENGTH")
+                    .long("max-length")
+                    .required(false)
+                    .takes_value(true)
+                    .value_name("CHARS")
+                    .default_value("350")
+                    .help("Longest a passage can be, longer paragraphs are split between sentences")
+                )
+            )
+            .subcommand(
+                Command::new("lint")
+                .about("Check a lang pack for passages that can't be played or are hard to type")
+                .arg(
+                    Arg::new("DIR")
+                    .required(true)
+                    .takes_value(true)
+                    .help("Directory of the pack, the one holding its version file")
+                )
+            )
+        )
         .get_matches();
 
+    let typeracer_config = match loaded_config {
+        Ok(typeracer_config) => typeracer_config,
+        // These are run from scripts, where nobody is around to press ENTER
+        Err(config_err)
+            if matches!(
+                args.subcommand_name(),
+                Some("packs" | "stats" | "export" | "import")
+            ) =>
+        {
+            eprintln!("{}", config_err);
+            std::process::exit(1);
+        }
+        Err(config_err) => config::default_after_error(&config_err),
+    };
+
+    if let Some(packs_args) = args.subcommand_matches("packs") {
+        // subcommand_required guarantees there is a subcommand
+        return match packs_args.subcommand().unwrap() {
+            ("list", _) => lang_pack::headless::list(&typeracer_config),
+            ("update", _) => lang_pack::headless::update(&typeracer_config),
+            ("add", add_args) => lang_pack::headless::add(
+                &typeracer_config,
+                config::ExtraRepo {
+                    name: add_args.value_of("NAME").unwrap().to_owned(),
+                    url: add_args.value_of("URL").unwrap().to_owned(),
+                    version: add_args.value_of("VERSION").unwrap().to_owned(),
+                },
+            ),
+            ("remove", remove_args) => {
+                lang_pack::headless::remove(remove_args.value_of("NAME").unwrap())
+            }
+            ("clean", _) => lang_pack::headless::clean(&typeracer_config),
+            ("create", create_args) => {
+                let length = |arg: &str| {
+                    create_args.value_of(arg).unwrap().parse().map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            "Passage lengths should be a number of characters",
+                        )
+                    })
+                };
+                lang_pack::headless::create(
+                    &typeracer_config,
+                    Path::new(create_args.value_of("FROM").unwrap()),
+                    create_args.value_of("NAME").unwrap(),
+                    length("MIN_LENGTH")?,
+                    length("MAX_LENGTH")?,
+                )
+            }
+            ("lint", lint_args) => {
+                lang_pack::headless::lint(Path::new(lint_args.value_of("DIR").unwrap()))
+            }
+            _ => unreachable!("Unknown packs subcommand"),
+        };
+    }
+
+    if let Some(stats_args) = args.subcommand_matches("stats") {
+        setup_db()?;
+        // possible_values guarantees this is a known format
+        let format = report::Format::from_name(stats_args.value_of("FORMAT").unwrap()).unwrap();
+        let last =
+            stats_args.value_of("LAST").unwrap().parse().map_err(|_| {
+                Error::new(ErrorKind::InvalidInput, "--last takes a number of races")
+            })?;
+        return report::print_report(
+            &db::db_path(&dirs::setup_dirs::get_db_dir()),
+            format,
+            last,
+            &typeracer_config.goals,
+        );
+    }
+
+    if let Some(export_args) = args.subcommand_matches("export") {
+        setup_db()?;
+        // possible_values guarantees this is a known format
+        let format = backup::Format::from_name(export_args.value_of("FORMAT").unwrap()).unwrap();
+        return backup::export(&db::db_path(&dirs::setup_dirs::get_db_dir()), format);
+    }
+
+    if let Some(import_args) = args.subcommand_matches("import") {
+        setup_db()?;
+        return backup::import(
+            &db::db_path(&dirs::setup_dirs::get_db_dir()),
+            Path::new(import_args.value_of("FILE").unwrap()),
+        );
+    }
+
+    if let Some(replay_args) = args.subcommand_matches("replay") {
+        setup_db()?;
+        let race_id = replay_args
+            .value_of("RACE_ID")
+            .unwrap()
+            .parse()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "The race id should be a number"))?;
+        return game::replay::replay_from_db(
+            &db::db_path(&dirs::setup_dirs::get_db_dir()),
+            race_id,
+            &typeracer_config,
+            VERSION,
+        );
+    }
+
     let mut passage_controller =
         passage_controller::Controller::new(typeracer_config.history_size, &typeracer_config);
 
@@ -121,13 +441,50 @@ fn main() -> Result<(), Error> {
         game::GameMode::InstantDeath
     } else if args.is_present("TRAINING") {
         game::GameMode::Training
+    } else if args.is_present("DRILL") {
+        game::GameMode::Drill
+    } else if args.is_present("TIMED") {
+        game::GameMode::Timed
     } else {
         game::GameMode::Default
     };
 
+    // possible_values guarantees this is a number if it is present
+    let time_limit = Duration::from_secs(
+        args.value_of("TIMED")
+            .map_or(0, |seconds| seconds.parse().unwrap()),
+    );
+
+    let racer_name = args.value_of("NAME").map_or_else(
+        || std::env::var("USER").unwrap_or_else(|_| "racer".to_owned()),
+        |name| name.to_owned(),
+    );
+    let race = if let Some(port) = args.value_of("HOST") {
+        let port = port
+            .parse()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "--host takes a port number"))?;
+        Some(multiplayer::Session::host(port, &racer_name)?)
+    } else if let Some(address) = args.value_of("JOIN") {
+        Some(multiplayer::Session::join(address, &racer_name)?)
+    } else {
+        None
+    };
+
+    let game_settings = game::GameSettings {
+        debug_enabled,
+        game_mode,
+        time_limit,
+        typeracer_version: VERSION,
+        typeracer_config: &typeracer_config,
+        race: race.as_ref(),
+    };
+
     let stats = &mut stats::Stats::new(legacy_wpm);
 
-    if !lang_pack::check_lang_pack(&typeracer_config) {
+    if !lang_pack::check_lang_pack(&typeracer_config) && args.is_present("YES") {
+        lang_pack::headless::update(&typeracer_config)?;
+        lang_pack::headless::clean(&typeracer_config)?;
+    } else if !lang_pack::check_lang_pack(&typeracer_config) {
         let result =
             lang_pack::retrieve_lang_pack(&typeracer_config.repo_version, &typeracer_config);
         match result {
@@ -136,18 +493,7 @@ fn main() -> Result<(), Error> {
             Ok(true) => (),
         }
     }
-    if !db::check_stats_db() {
-        match db::create_database(&db::db_path(&dirs::setup_dirs::get_db_dir())) {
-            Ok(_) => (),
-            Err(e) => return Err(Error::new(ErrorKind::ConnectionRefused, e)),
-        }
-    }
-    if !db::check_for_migration(&db::db_path(&dirs::setup_dirs::get_db_dir())) {
-        match db::do_migration(&db::db_path(&dirs::setup_dirs::get_db_dir())) {
-            Ok(_) => (),
-            Err(e) => return Err(Error::new(ErrorKind::ConnectionRefused, e)),
-        }
-    }
+    setup_db()?;
 
     // setup input
     let (input_sender, input_receiver) = unbounded::<Key>();
@@ -169,21 +515,47 @@ fn main() -> Result<(), Error> {
         };
 
         let passage_info = match game_mode {
-            game::GameMode::Training => &mistaken_words_passage,
-            _ => passage_controller.retrieve_passage(action),
+            game::GameMode::Training => mistaken_words_passage,
+            game::GameMode::Drill => match passage_controller.retrieve_drill_passage(
+                &Connection::open(db::db_path(&dirs::setup_dirs::get_db_dir())).unwrap(),
+            ) {
+                Ok(p) => p,
+                _ => {
+                    return Result::Err(Error::new(
+                        ErrorKind::NotFound,
+                        "Couldn't get your weak spots from the database!",
+                    ))
+                }
+            },
+            _ => passage_controller.retrieve_passage(action).clone(),
         };
 
         action = game::play_game(
-            passage_info,
+            &passage_info,
+            &mut passage_controller,
             &input_receiver,
             stats,
-            debug_enabled,
-            game_mode,
-            VERSION,
-            &typeracer_config,
+            &game_settings,
         );
         stats.reset();
     }
 
     Ok(())
 }
+
+/// Make sure the stats db exists and is on the latest schema
+fn setup_db() -> Result<(), Error> {
+    if !db::check_stats_db() {
+        match db::create_database(&db::db_path(&dirs::setup_dirs::get_db_dir())) {
+            Ok(_) => (),
+            Err(e) => return Err(Error::new(ErrorKind::ConnectionRefused, e)),
+        }
+    }
+    if !db::check_for_migration(&db::db_path(&dirs::setup_dirs::get_db_dir())) {
+        match db::do_migration(&db::db_path(&dirs::setup_dirs::get_db_dir())) {
+            Ok(_) => (),
+            Err(e) => return Err(Error::new(ErrorKind::ConnectionRefused, e)),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,33 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout},
+    terminal::Terminal,
+    text::Text,
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+};
+
+pub fn render<B: Backend>(terminal: &mut Terminal<B>, lobby_text: Text) {
+    terminal
+        .draw(|f| {
+            let root_layout = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(100)].as_ref())
+                .split(f.size());
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(5)
+                .constraints([Constraint::Percentage(100)].as_ref())
+                .split(root_layout[0]);
+            let lobby_block = Block::default()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Rounded);
+            f.render_widget(
+                Paragraph::new(lobby_text)
+                    .block(lobby_block.title("Multiplayer lobby"))
+                    .wrap(Wrap { trim: true })
+                    .alignment(Alignment::Left),
+                chunks[0],
+            );
+        })
+        .expect("Failed to draw terminal widgets.");
+}
@@ -0,0 +1,529 @@
+use crossbeam_channel::Receiver;
+use std::{
+    io::{BufRead, BufReader, Error, ErrorKind, Read, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+use termion::event::Key;
+use tui::{backend::Backend, terminal::Terminal, text::Text};
+
+use crate::{dirs::setup_dirs, passage_controller::PassageInfo};
+use protocol::Message;
+
+mod lobby_render;
+mod protocol;
+
+const COUNTDOWN_SECS: u64 = 3;
+/// How long a racer waits for the host to answer when joining
+const WELCOME_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest line read off a connection. Passages are the longest messages, and even long code
+/// passages come nowhere near this.
+const MAX_LINE_BYTES: u64 = 64 * 1024;
+
+/// How far a racer has made it through the current race.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlayerProgress {
+    pub name: String,
+    /// Percent of the passage typed, 0-100
+    pub percent: u16,
+    pub wpm: u64,
+    pub finished: bool,
+}
+
+impl PlayerProgress {
+    fn new(name: &str) -> Self {
+        PlayerProgress {
+            name: name.to_owned(),
+            percent: 0,
+            wpm: 0,
+            finished: false,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct RaceState {
+    /// Everyone in the race in the order they joined, the host first
+    players: Vec<PlayerProgress>,
+    passage: Option<PassageInfo>,
+    /// Goes up by one every time the host starts a race
+    race_id: usize,
+    /// The last race this player has started typing in
+    joined_race_id: usize,
+    countdown: Option<u64>,
+    started: bool,
+    host_gone: bool,
+}
+
+impl RaceState {
+    fn update_player(&mut self, progress: PlayerProgress) {
+        match self
+            .players
+            .iter_mut()
+            .find(|player| player.name == progress.name)
+        {
+            Some(player) => *player = progress,
+            None => self.players.push(progress),
+        }
+    }
+
+    fn new_race(&mut self, passage_info: PassageInfo) {
+        self.race_id += 1;
+        self.passage = Some(passage_info);
+        self.countdown = Some(COUNTDOWN_SECS);
+        self.started = false;
+        self.players = self
+            .players
+            .iter()
+            .map(|player| PlayerProgress::new(&player.name))
+            .collect();
+    }
+}
+
+type Clients = Arc<Mutex<Vec<TcpStream>>>;
+
+enum Role {
+    Host(Clients),
+    Racer(Mutex<TcpStream>),
+}
+
+/// A connection to a race over TCP, either as the host or as a racer who joined the host.
+/// The host picks passages and starts races, racers type whatever the host sends them.
+pub struct Session {
+    role: Role,
+    name: String,
+    state: Arc<Mutex<RaceState>>,
+}
+
+impl Session {
+    /// Start hosting races on the given port, for anyone who can reach it to join.
+    pub fn host(port: u16, name: &str) -> Result<Session, Error> {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        let clients: Clients = Default::default();
+        let state = Arc::new(Mutex::new(RaceState {
+            players: vec![PlayerProgress::new(name)],
+            ..Default::default()
+        }));
+
+        accept_racers(listener, clients.clone(), state.clone());
+
+        Ok(Session {
+            role: Role::Host(clients),
+            name: name.to_owned(),
+            state,
+        })
+    }
+
+    /// Join a race hosted at the given address, e.g. 192.168.1.10:7878
+    /// The host renames racers whose name is already taken, so the name raced under might not be
+    /// the one asked for.
+    pub fn join(address: &str, name: &str) -> Result<Session, Error> {
+        let mut stream = TcpStream::connect(address)?;
+        stream.write_all(Message::Hello(name.to_owned()).to_line().as_bytes())?;
+
+        // Wait to hear what the host is calling us before anything is shown
+        stream.set_read_timeout(Some(WELCOME_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let name = match read_message_line(&mut reader).and_then(|line| Message::parse(&line)) {
+            Some(Message::Welcome(name)) => name,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("{} didn't let us into the race", address),
+                ))
+            }
+        };
+        stream.set_read_timeout(None)?;
+
+        let state: Arc<Mutex<RaceState>> = Default::default();
+        let read_state = state.clone();
+        thread::spawn(move || racer_read_host(reader, read_state));
+
+        Ok(Session {
+            role: Role::Racer(Mutex::new(stream)),
+            name,
+            state,
+        })
+    }
+
+    /// Show the lobby until the next race starts.
+    /// The host starts the race on the given passage by hitting enter, racers get whichever
+    /// passage the host sent instead.
+    /// Returns the passage to race on, or None if the user wants to quit.
+    pub fn wait_for_start<B: Backend>(
+        &self,
+        terminal: &mut Terminal<B>,
+        input_receiver: &Receiver<Key>,
+        passage_info: &PassageInfo,
+    ) -> Option<PassageInfo> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.started && state.race_id > state.joined_race_id {
+                    state.joined_race_id = state.race_id;
+                    return match self.role {
+                        // The host's copy of the passage has a path only other racers can use
+                        Role::Host(_) => Some(passage_info.clone()),
+                        Role::Racer(_) => state.passage.clone(),
+                    };
+                }
+                lobby_render::render(terminal, self.lobby_text(&state));
+            }
+
+            let recv_result = input_receiver.recv_timeout(Duration::from_millis(100));
+            match recv_result {
+                Ok(Key::Ctrl('c')) => return None,
+                Ok(Key::Char('\n')) => {
+                    if let Role::Host(clients) = &self.role {
+                        let countdown_running = self.state.lock().unwrap().countdown.is_some();
+                        if !countdown_running {
+                            self.start_race(clients, passage_info);
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Let everyone else know how far along the user is.
+    pub fn report_progress(&self, percent: u16, wpm: u64, finished: bool) {
+        let progress = PlayerProgress {
+            name: self.name.clone(),
+            percent,
+            wpm,
+            finished,
+        };
+        match &self.role {
+            Role::Host(clients) => {
+                self.state.lock().unwrap().update_player(progress.clone());
+                broadcast(clients, &Message::Progress(progress));
+            }
+            Role::Racer(stream) => {
+                // If this fails the host is gone, which the read thread lets the user know about
+                let _ = stream
+                    .lock()
+                    .unwrap()
+                    .write_all(Message::Progress(progress).to_line().as_bytes());
+            }
+        }
+    }
+
+    /// Progress of everyone in the race, the user included
+    pub fn players(&self) -> Vec<PlayerProgress> {
+        self.state.lock().unwrap().players.clone()
+    }
+
+    fn start_race(&self, clients: &Clients, passage_info: &PassageInfo) {
+        let passage_info = PassageInfo {
+            passage_path: shared_passage_path(&passage_info.passage_path),
+            ..passage_info.clone()
+        };
+        self.state.lock().unwrap().new_race(passage_info.clone());
+        broadcast(clients, &Message::Passage(passage_info));
+
+        let clients = clients.clone();
+        let state = self.state.clone();
+        thread::spawn(move || {
+            for seconds in (1..=COUNTDOWN_SECS).rev() {
+                state.lock().unwrap().countdown = Some(seconds);
+                broadcast(&clients, &Message::Countdown(seconds));
+                thread::sleep(Duration::from_secs(1));
+            }
+            {
+                let mut state = state.lock().unwrap();
+                state.countdown = None;
+                state.started = true;
+            }
+            broadcast(&clients, &Message::Go);
+        });
+    }
+
+    fn lobby_text(&self, state: &RaceState) -> Text<'static> {
+        let mut lobby_text = Text::raw(match (&self.role, state.countdown) {
+            (_, Some(seconds)) => format!("Race starts in {}...\n\n", seconds),
+            (Role::Racer(_), None) if state.host_gone => {
+                "Lost connection to the host. ^C to exit.\n\n".to_owned()
+            }
+            (Role::Host(_), None) => {
+                "Press <ENTER> to start the race or ^C to exit.\n\n".to_owned()
+            }
+            (Role::Racer(_), None) => {
+                "Waiting for the host to start the race. ^C to exit.\n\n".to_owned()
+            }
+        });
+        lobby_text.extend(Text::raw("Racers:\n"));
+        for player in state.players.iter() {
+            lobby_text.extend(Text::raw(if player.name == self.name {
+                format!("- {} (you)\n", player.name)
+            } else {
+                format!("- {}\n", player.name)
+            }));
+        }
+        lobby_text
+    }
+}
+
+/// Let racers join for as long as the host is around, each read on their own thread.
+fn accept_racers(listener: TcpListener, clients: Clients, state: Arc<Mutex<RaceState>>) {
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            if let Ok(write_stream) = stream.try_clone() {
+                clients.lock().unwrap().push(write_stream);
+                let clients = clients.clone();
+                let state = state.clone();
+                thread::spawn(move || host_read_racer(stream, clients, state));
+            }
+        }
+    });
+}
+
+/// Send a message to every racer, dropping anyone we can't reach anymore.
+fn broadcast(clients: &Clients, message: &Message) {
+    let line = message.to_line();
+    clients
+        .lock()
+        .unwrap()
+        .retain_mut(|client| client.write_all(line.as_bytes()).is_ok());
+}
+
+/// Handle everything a single racer sends to the host until they disconnect.
+fn host_read_racer(stream: TcpStream, clients: Clients, state: Arc<Mutex<RaceState>>) {
+    let mut name: Option<String> = None;
+    let mut catch_up_stream = match stream.try_clone() {
+        Ok(catch_up_stream) => catch_up_stream,
+        Err(_) => return,
+    };
+
+    let mut reader = BufReader::new(stream);
+    while let Some(line) = read_message_line(&mut reader) {
+        match (Message::parse(&line), &name) {
+            (Some(Message::Hello(requested_name)), None) => {
+                let mut state = state.lock().unwrap();
+                let unique_name = unique_name(&state.players, &requested_name);
+                state.players.push(PlayerProgress::new(&unique_name));
+                name = Some(unique_name.clone());
+
+                // Tell the new racer what they're called, then catch them up on who is here and
+                // on the race about to start if the countdown is running. Races already underway
+                // have to be waited out.
+                let mut catch_up = vec![Message::Welcome(unique_name.clone())];
+                catch_up.extend(state.players.iter().cloned().map(Message::Progress));
+                if let (Some(passage_info), Some(seconds)) = (&state.passage, state.countdown) {
+                    catch_up.push(Message::Passage(passage_info.clone()));
+                    catch_up.push(Message::Countdown(seconds));
+                }
+                drop(state);
+                let catch_up_lines: String = catch_up.iter().map(Message::to_line).collect();
+                if catch_up_stream
+                    .write_all(catch_up_lines.as_bytes())
+                    .is_err()
+                {
+                    break;
+                }
+                broadcast(
+                    &clients,
+                    &Message::Progress(PlayerProgress::new(&unique_name)),
+                );
+            }
+            (Some(Message::Progress(progress)), Some(name)) => {
+                // Never trust the name a racer sends after they've joined
+                let progress = PlayerProgress {
+                    name: name.clone(),
+                    ..progress
+                };
+                state.lock().unwrap().update_player(progress.clone());
+                broadcast(&clients, &Message::Progress(progress));
+            }
+            _ => (),
+        }
+    }
+
+    // A racer who sent a line that was too long is still connected, so hang up on them
+    let _ = catch_up_stream.shutdown(Shutdown::Both);
+    if let Some(name) = name {
+        state
+            .lock()
+            .unwrap()
+            .players
+            .retain(|player| player.name != name);
+        broadcast(&clients, &Message::Leave(name));
+    }
+}
+
+/// Handle everything the host sends a racer until the connection closes.
+fn racer_read_host(mut reader: BufReader<TcpStream>, state: Arc<Mutex<RaceState>>) {
+    while let Some(line) = read_message_line(&mut reader) {
+        let mut state = state.lock().unwrap();
+        match Message::parse(&line) {
+            Some(Message::Passage(passage_info)) => state.new_race(PassageInfo {
+                passage_path: local_passage_path(&passage_info.passage_path),
+                ..passage_info
+            }),
+            Some(Message::Countdown(seconds)) => state.countdown = Some(seconds),
+            Some(Message::Go) => {
+                state.countdown = None;
+                state.started = true;
+            }
+            Some(Message::Progress(progress)) => state.update_player(progress),
+            Some(Message::Leave(name)) => state.players.retain(|player| player.name != name),
+            _ => (),
+        }
+    }
+    let _ = reader.get_ref().shutdown(Shutdown::Both);
+    state.lock().unwrap().host_gone = true;
+}
+
+/// Read the next message line from the other side. Returns None once the connection is closed,
+/// fails, or sends a line longer than any message, after which the connection should be dropped
+/// rather than read any further.
+fn read_message_line(reader: &mut impl BufRead) -> Option<String> {
+    let mut line = String::new();
+    match reader.take(MAX_LINE_BYTES).read_line(&mut line) {
+        // Every message ends in a newline, so a line without one was cut off at the limit
+        Ok(_) if line.ends_with('\n') => Some(line),
+        _ => None,
+    }
+}
+
+/// Names are how racers are told apart, so give a racer a number if their name is taken.
+fn unique_name(players: &[PlayerProgress], requested_name: &str) -> String {
+    let taken = |name: &str| players.iter().any(|player| player.name == name);
+    if !taken(requested_name) {
+        return requested_name.to_owned();
+    }
+    (2..)
+        .map(|count| format!("{} ({})", requested_name, count))
+        .find(|name| !taken(name))
+        .unwrap()
+}
+
+/// Marks passage paths sent relative to the lang pack directory, so they can be told apart from
+/// paths that aren't in a lang pack at all like "User input"
+const PACK_PATH_PREFIX: &str = "lang-pack:";
+
+/// Everyone's lang packs live in a different spot, so passage paths are sent relative to the
+/// lang pack directory.
+fn shared_passage_path(passage_path: &str) -> String {
+    let main_pack_dir = setup_dirs::get_quote_dirs().main_pack_dir;
+    match Path::new(passage_path).strip_prefix(&main_pack_dir) {
+        Ok(relative_path) => format!("{}{}", PACK_PATH_PREFIX, relative_path.to_string_lossy()),
+        Err(_) => passage_path.to_owned(),
+    }
+}
+
+/// Turn a passage path from the host back into where it lives on this machine.
+fn local_passage_path(shared_passage_path: &str) -> String {
+    match shared_passage_path.strip_prefix(PACK_PATH_PREFIX) {
+        Some(relative_path) => setup_dirs::get_quote_dirs()
+            .main_pack_dir
+            .join(relative_path)
+            .to_string_lossy()
+            .into_owned(),
+        None => shared_passage_path.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duplicate_names_get_numbered() {
+        let players = vec![
+            PlayerProgress::new("darrien"),
+            PlayerProgress::new("darrien (2)"),
+        ];
+
+        assert_eq!(unique_name(&players, "vrinda"), "vrinda");
+        assert_eq!(unique_name(&players, "darrien"), "darrien (3)");
+    }
+
+    #[test]
+    fn racers_learn_their_numbered_name() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let state = Arc::new(Mutex::new(RaceState {
+            players: vec![PlayerProgress::new("darrien")],
+            ..Default::default()
+        }));
+        accept_racers(listener, Default::default(), state.clone());
+
+        let first = Session::join(&address, "darrien").unwrap();
+        let second = Session::join(&address, "darrien").unwrap();
+
+        assert_eq!(first.name, "darrien (2)");
+        assert_eq!(second.name, "darrien (3)");
+        let names: Vec<String> = state
+            .lock()
+            .unwrap()
+            .players
+            .iter()
+            .map(|player| player.name.clone())
+            .collect();
+        assert_eq!(names, vec!["darrien", "darrien (2)", "darrien (3)"]);
+    }
+
+    #[test]
+    fn long_lines_end_the_connection() {
+        let lines = format!("GO\n{}\nGO\n", "x".repeat(MAX_LINE_BYTES as usize));
+        let mut reader = lines.as_bytes();
+
+        assert_eq!(read_message_line(&mut reader).as_deref(), Some("GO\n"));
+        assert_eq!(read_message_line(&mut reader), None);
+    }
+
+    #[test]
+    fn new_race_resets_progress() {
+        let mut state = RaceState::default();
+        state.update_player(PlayerProgress {
+            name: "darrien".to_owned(),
+            percent: 100,
+            wpm: 120,
+            finished: true,
+        });
+        state.started = true;
+
+        state.new_race(PassageInfo {
+            passage: "the quick brown fox".to_owned(),
+            title: "test".to_owned(),
+            passage_path: "/default/test".to_owned(),
+        });
+
+        assert_eq!(state.race_id, 1);
+        assert!(!state.started);
+        assert_eq!(state.countdown, Some(COUNTDOWN_SECS));
+        assert_eq!(state.players, vec![PlayerProgress::new("darrien")]);
+    }
+
+    #[test]
+    fn passage_paths_survive_the_trip() {
+        let main_pack_dir = setup_dirs::get_quote_dirs().main_pack_dir;
+        let passage_path = main_pack_dir
+            .join("default")
+            .join("0022791a-573f-414b-954f-7582f5bcd09e")
+            .to_string_lossy()
+            .into_owned();
+
+        let shared_path = shared_passage_path(&passage_path);
+        assert_eq!(
+            shared_path,
+            format!(
+                "{}{}",
+                PACK_PATH_PREFIX,
+                Path::new("default")
+                    .join("0022791a-573f-414b-954f-7582f5bcd09e")
+                    .to_string_lossy()
+            )
+        );
+        assert_eq!(local_passage_path(&shared_path), passage_path);
+
+        // Passages outside the lang packs are left alone both ways, so they still aren't saved
+        for sentinel in ["User input", "FALLBACK_PATH"] {
+            assert_eq!(local_passage_path(&shared_passage_path(sentinel)), sentinel);
+        }
+    }
+}
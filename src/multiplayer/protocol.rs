@@ -0,0 +1,156 @@
+use crate::multiplayer::PlayerProgress;
+use crate::passage_controller::PassageInfo;
+
+/// Messages sent between the host and racers.
+/// Each message is sent as a single line, with fields separated by tabs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// Sent by a racer when they first connect
+    Hello(String),
+    /// Sent by the host in reply to Hello, with the name the racer was let in under. It's only
+    /// different from the one they asked for when someone else already has it.
+    Welcome(String),
+    /// A new race is about to start on this passage
+    Passage(PassageInfo),
+    /// Seconds left until the race starts
+    Countdown(u64),
+    /// The race has started
+    Go,
+    Progress(PlayerProgress),
+    /// A racer has disconnected
+    Leave(String),
+}
+
+impl Message {
+    pub fn to_line(&self) -> String {
+        let fields = match self {
+            Message::Hello(name) => vec!["HELLO".to_owned(), clean(name)],
+            Message::Welcome(name) => vec!["WELCOME".to_owned(), clean(name)],
+            Message::Passage(passage_info) => vec![
+                "PASSAGE".to_owned(),
+                clean(&passage_info.title),
+                clean(&passage_info.passage_path),
+                escape_newlines(&passage_info.passage),
+            ],
+            Message::Countdown(seconds) => vec!["COUNTDOWN".to_owned(), seconds.to_string()],
+            Message::Go => vec!["GO".to_owned()],
+            Message::Progress(progress) => vec![
+                "PROGRESS".to_owned(),
+                clean(&progress.name),
+                progress.percent.to_string(),
+                progress.wpm.to_string(),
+                u8::from(progress.finished).to_string(),
+            ],
+            Message::Leave(name) => vec!["LEAVE".to_owned(), clean(name)],
+        };
+        format!("{}\n", fields.join("\t"))
+    }
+
+    /// Parse a line sent by the other side. Returns None for anything we don't understand, so
+    /// a bad message can be skipped rather than ending the race.
+    pub fn parse(line: &str) -> Option<Message> {
+        let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+        match fields[..] {
+            ["HELLO", name] => Some(Message::Hello(name.to_owned())),
+            ["WELCOME", name] => Some(Message::Welcome(name.to_owned())),
+            ["PASSAGE", title, passage_path, passage] => Some(Message::Passage(PassageInfo {
+                passage: unescape_newlines(passage),
+                title: title.to_owned(),
+                passage_path: passage_path.to_owned(),
+            })),
+            ["COUNTDOWN", seconds] => seconds.parse().ok().map(Message::Countdown),
+            ["GO"] => Some(Message::Go),
+            ["PROGRESS", name, percent, wpm, finished] => Some(Message::Progress(PlayerProgress {
+                name: name.to_owned(),
+                percent: percent.parse().ok()?,
+                wpm: wpm.parse().ok()?,
+                finished: finished == "1",
+            })),
+            ["LEAVE", name] => Some(Message::Leave(name.to_owned())),
+            _ => None,
+        }
+    }
+}
+
+/// Tabs and newlines would break up a message, so swap them out for plain spaces.
+fn clean(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Code passages need their newlines, so they're sent as `\n` instead and backslashes are
+/// doubled up to keep them apart.
+fn escape_newlines(field: &str) -> String {
+    clean(&field.replace('\\', "\\\\").replace('\n', "\\n"))
+}
+
+fn unescape_newlines(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_round_trip() {
+        let messages = vec![
+            Message::Hello("vrinda".to_owned()),
+            Message::Welcome("vrinda (2)".to_owned()),
+            Message::Passage(PassageInfo {
+                passage: "The best is the enemy of the good.".to_owned(),
+                title: "Voltaire".to_owned(),
+                passage_path: "/default/0022791a-573f-414b-954f-7582f5bcd09e".to_owned(),
+            }),
+            Message::Passage(PassageInfo {
+                passage: "fn main() {\n    println!(\"\\n\");\n}".to_owned(),
+                title: "Hello world".to_owned(),
+                passage_path: "/rust/hello".to_owned(),
+            }),
+            Message::Countdown(3),
+            Message::Go,
+            Message::Progress(PlayerProgress {
+                name: "darrien".to_owned(),
+                percent: 42,
+                wpm: 97,
+                finished: false,
+            }),
+            Message::Leave("darrien".to_owned()),
+        ];
+
+        for message in messages {
+            assert_eq!(Message::parse(&message.to_line()), Some(message));
+        }
+    }
+
+    #[test]
+    fn tabs_do_not_break_messages() {
+        let line = Message::Hello("tab\tby\nname".to_owned()).to_line();
+        assert_eq!(
+            Message::parse(&line),
+            Some(Message::Hello("tab by name".to_owned()))
+        );
+    }
+
+    #[test]
+    fn junk_is_ignored() {
+        assert_eq!(Message::parse(""), None);
+        assert_eq!(Message::parse("HELLO"), None);
+        assert_eq!(Message::parse("COUNTDOWN\tsoon"), None);
+        assert_eq!(Message::parse("PROGRESS\tdarrien\tlots\t97\t0"), None);
+    }
+}
@@ -0,0 +1,379 @@
+use rand::seq::SliceRandom;
+use rusqlite::{params, Connection};
+use std::collections::{HashMap, HashSet};
+
+use crate::{heatmap::heatmap_db::MAX_LATENCY_MILLIS, passage_controller::PassageInfo};
+
+/// Number of words in a drill
+pub const DRILL_LENGTH: usize = 25;
+/// Keys hit fewer times than this don't say much about how slow the user is on them
+const MIN_LATENCY_SAMPLES: i64 = 5;
+/// Number of weak spots listed in the title of a drill
+const WEAKNESSES_SHOWN: usize = 5;
+
+/// How weak the user is on each character and pair of characters they've typed. The higher the
+/// weight the weaker they are, anything the user is fine on is left out.
+/// Everything is lowercase so practicing a letter helps with its capital too.
+#[derive(Debug, Default, PartialEq)]
+pub struct Weaknesses {
+    chars: HashMap<char, f64>,
+    bigrams: HashMap<(char, char), f64>,
+}
+
+/// Times a character was missed, `previous` is empty if it started a passage
+pub struct RawErrors {
+    pub previous: String,
+    pub expected: String,
+    pub count: i64,
+}
+
+/// Time taken to hit a character, `previous` is empty if the key before it was a mistake
+pub struct RawLatencies {
+    pub previous: String,
+    pub expected: String,
+    pub total_millis: i64,
+    pub samples: i64,
+}
+
+impl Weaknesses {
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty() && self.bigrams.is_empty()
+    }
+
+    /// How much practicing a word helps, the more weak spots it has the better
+    pub fn score_word(&self, word: &str) -> f64 {
+        let chars: Vec<char> = word.to_lowercase().chars().collect();
+        let char_score: f64 = chars.iter().filter_map(|c| self.chars.get(c)).sum();
+        let bigram_score: f64 = chars
+            .windows(2)
+            .filter_map(|pair| self.bigrams.get(&(pair[0], pair[1])))
+            .sum();
+        char_score + bigram_score
+    }
+
+    /// The weakest characters and pairs, weakest first. e.g. ["e", "th", "r"]
+    pub fn worst(&self, limit: usize) -> Vec<String> {
+        let mut worst: Vec<(String, f64)> = self
+            .chars
+            .iter()
+            .map(|(c, weight)| (c.to_string(), *weight))
+            .chain(
+                self.bigrams
+                    .iter()
+                    .map(|((a, b), weight)| (format!("{}{}", a, b), *weight)),
+            )
+            .collect();
+        worst.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        worst
+            .into_iter()
+            .take(limit)
+            .map(|(keys, _)| keys)
+            .collect()
+    }
+}
+
+/// Work out the user's weak spots from every mistake and keystroke they've made in any game mode.
+pub fn retrieve_weaknesses(conn: &Connection) -> Result<Weaknesses, rusqlite::Error> {
+    let mut errors_stmt = conn.prepare(
+        "SELECT previous, expected, SUM(count)
+            FROM char_errors
+            GROUP BY previous, expected",
+    )?;
+    let errors = errors_stmt
+        .query_map(params![], |row| {
+            Ok(RawErrors {
+                previous: row.get(0)?,
+                expected: row.get(1)?,
+                count: row.get(2)?,
+            })
+        })?
+        .collect::<Result<Vec<RawErrors>, rusqlite::Error>>()?;
+
+    // Only count a pair when both keys were hit correctly one after the other
+    let mut latencies_stmt = conn.prepare(
+        "SELECT CASE WHEN previous_hit THEN previous ELSE '' END AS pair_start,
+                expected,
+                SUM(latency),
+                COUNT(*)
+            FROM (
+                SELECT expected,
+                       typed,
+                       backspace,
+                       LAG(expected) OVER race AS previous,
+                       LAG(typed = expected AND backspace = 0) OVER race AS previous_hit,
+                       offset_millis - LAG(offset_millis) OVER race AS latency
+                    FROM keystrokes
+                    WINDOW race AS (PARTITION BY passage_stats_id ORDER BY row_id)
+            )
+            WHERE backspace = 0 AND typed = expected AND latency <= ?1
+            GROUP BY pair_start, expected",
+    )?;
+    let latencies = latencies_stmt
+        .query_map(params![MAX_LATENCY_MILLIS], |row| {
+            Ok(RawLatencies {
+                previous: row.get(0)?,
+                expected: row.get(1)?,
+                total_millis: row.get(2)?,
+                samples: row.get(3)?,
+            })
+        })?
+        .collect::<Result<Vec<RawLatencies>, rusqlite::Error>>()?;
+
+    Ok(as_weaknesses(&errors, &latencies))
+}
+
+/// Weigh up each character and pair by how often it's missed compared to the user's most missed
+/// one, plus how much slower it is to type than the user's average keystroke.
+pub fn as_weaknesses(errors: &[RawErrors], latencies: &[RawLatencies]) -> Weaknesses {
+    let mut char_errors: HashMap<char, i64> = HashMap::new();
+    let mut bigram_errors: HashMap<(char, char), i64> = HashMap::new();
+    for error in errors {
+        let (previous, expected) = match lowercase_keys(&error.previous, &error.expected) {
+            Some(keys) => keys,
+            None => continue,
+        };
+        *char_errors.entry(expected).or_default() += error.count;
+        if let Some(previous) = previous {
+            *bigram_errors.entry((previous, expected)).or_default() += error.count;
+        }
+    }
+
+    let mut char_latencies: HashMap<char, (i64, i64)> = HashMap::new();
+    let mut bigram_latencies: HashMap<(char, char), (i64, i64)> = HashMap::new();
+    for latency in latencies {
+        let (previous, expected) = match lowercase_keys(&latency.previous, &latency.expected) {
+            Some(keys) => keys,
+            None => continue,
+        };
+        let char_latency = char_latencies.entry(expected).or_default();
+        char_latency.0 += latency.total_millis;
+        char_latency.1 += latency.samples;
+        if let Some(previous) = previous {
+            let bigram_latency = bigram_latencies.entry((previous, expected)).or_default();
+            bigram_latency.0 += latency.total_millis;
+            bigram_latency.1 += latency.samples;
+        }
+    }
+    let total_samples: i64 = char_latencies.values().map(|(_, samples)| samples).sum();
+    let average_millis = if total_samples == 0 {
+        0.0
+    } else {
+        char_latencies.values().map(|(total, _)| total).sum::<i64>() as f64 / total_samples as f64
+    };
+
+    Weaknesses {
+        chars: combine_weights(
+            error_weights(&char_errors),
+            slowness_weights(&char_latencies, average_millis),
+        ),
+        bigrams: combine_weights(
+            error_weights(&bigram_errors),
+            slowness_weights(&bigram_latencies, average_millis),
+        ),
+    }
+}
+
+/// Pick words that are heavy on the user's weak spots, the more weak spots a word has the more
+/// likely it is to be picked.
+pub fn pick_drill_words(words: &[String], weaknesses: &Weaknesses, count: usize) -> Vec<String> {
+    let scored_words: Vec<(&String, f64)> = words
+        .iter()
+        .map(|word| (word, weaknesses.score_word(word)))
+        .filter(|(_, score)| *score > 0.0)
+        .collect();
+
+    match scored_words.choose_multiple_weighted(&mut rand::thread_rng(), count, |word| word.1) {
+        Ok(picked) => picked.map(|(word, _)| (*word).clone()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+/// Every distinct word in a set of passages. Code and passages without spaces between their words
+/// don't split into words we can drill on, so they're skipped.
+pub fn to_word_pool<'a>(passages: impl Iterator<Item = &'a PassageInfo>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    passages
+        .filter(|passage_info| {
+            !passage_info.is_code() && !crate::game::split::is_wide_character(&passage_info.passage)
+        })
+        .flat_map(|passage_info| passage_info.passage.split_whitespace())
+        .filter(|word| seen.insert(word.to_owned()))
+        .map(|word| word.to_owned())
+        .collect()
+}
+
+pub fn describe(weaknesses: &Weaknesses) -> String {
+    weaknesses
+        .worst(WEAKNESSES_SHOWN)
+        .iter()
+        .map(|keys| keys.replace(' ', "␣").replace('\n', "↵"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Whitespace is typed between words rather than in them, so there's no way to drill on it
+fn lowercase_keys(previous: &str, expected: &str) -> Option<(Option<char>, char)> {
+    let expected = expected.to_lowercase().chars().next()?;
+    if expected.is_whitespace() {
+        return None;
+    }
+    let previous = previous
+        .to_lowercase()
+        .chars()
+        .next()
+        .filter(|c| !c.is_whitespace());
+    Some((previous, expected))
+}
+
+fn error_weights<K: Copy + Eq + std::hash::Hash>(errors: &HashMap<K, i64>) -> HashMap<K, f64> {
+    let most_errors = errors.values().cloned().max().unwrap_or(0);
+    errors
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .map(|(keys, count)| (*keys, *count as f64 / most_errors as f64))
+        .collect()
+}
+
+/// Anything slower than average is weighted by how much slower it is, capped at twice as slow
+fn slowness_weights<K: Copy + Eq + std::hash::Hash>(
+    latencies: &HashMap<K, (i64, i64)>,
+    average_millis: f64,
+) -> HashMap<K, f64> {
+    if average_millis <= 0.0 {
+        return HashMap::new();
+    }
+    latencies
+        .iter()
+        .filter(|(_, (_, samples))| *samples >= MIN_LATENCY_SAMPLES)
+        .map(|(keys, (total, samples))| {
+            let slowness = (*total as f64 / *samples as f64) / average_millis - 1.0;
+            (*keys, slowness.min(1.0))
+        })
+        .filter(|(_, slowness)| *slowness > 0.0)
+        .collect()
+}
+
+fn combine_weights<K: Copy + Eq + std::hash::Hash>(
+    mut weights: HashMap<K, f64>,
+    more_weights: HashMap<K, f64>,
+) -> HashMap<K, f64> {
+    for (keys, weight) in more_weights {
+        *weights.entry(keys).or_default() += weight;
+    }
+    weights
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDb;
+
+    fn errors(previous: &str, expected: &str, count: i64) -> RawErrors {
+        RawErrors {
+            previous: previous.to_owned(),
+            expected: expected.to_owned(),
+            count,
+        }
+    }
+
+    fn latencies(previous: &str, expected: &str, average: i64, samples: i64) -> RawLatencies {
+        RawLatencies {
+            previous: previous.to_owned(),
+            expected: expected.to_owned(),
+            total_millis: average * samples,
+            samples,
+        }
+    }
+
+    #[test]
+    fn weak_spots_are_missed_or_slow() {
+        let weaknesses = as_weaknesses(
+            &[errors("t", "h", 4), errors("", "Q", 2), errors("e", " ", 9)],
+            &[
+                latencies("", "a", 90, 115),
+                latencies("o", "z", 150, 5),
+                // Too few samples to say the user is slow on it
+                latencies("", "x", 1000, 1),
+            ],
+        );
+
+        assert_eq!(
+            weaknesses,
+            Weaknesses {
+                chars: HashMap::from([('h', 1.0), ('q', 0.5), ('z', 0.5)]),
+                bigrams: HashMap::from([(('t', 'h'), 1.0), (('o', 'z'), 0.5)]),
+            }
+        );
+        assert_eq!(weaknesses.worst(3), vec!["h", "th", "oz"]);
+    }
+
+    #[test]
+    fn words_score_by_their_weak_spots() {
+        let weaknesses = Weaknesses {
+            chars: HashMap::from([('h', 1.0), ('q', 0.5)]),
+            bigrams: HashMap::from([(('t', 'h'), 1.0)]),
+        };
+
+        assert_eq!(weaknesses.score_word("The"), 2.0);
+        assert_eq!(weaknesses.score_word("quick"), 0.5);
+        assert_eq!(weaknesses.score_word("brown"), 0.0);
+
+        let words = vec!["brown".to_owned(), "fox".to_owned(), "the".to_owned()];
+        assert_eq!(
+            pick_drill_words(&words, &weaknesses, DRILL_LENGTH),
+            vec!["the".to_owned()]
+        );
+    }
+
+    #[test]
+    fn word_pool_skips_code_and_repeats() {
+        let passages: Vec<PassageInfo> = ["the cat and the hat", "fn main() {\n}", "你好你好"]
+            .iter()
+            .map(|passage| PassageInfo {
+                passage: passage.to_string(),
+                title: "Passage".to_owned(),
+                passage_path: "User input".to_owned(),
+            })
+            .collect();
+
+        assert_eq!(
+            to_word_pool(passages.iter()),
+            vec!["the", "cat", "and", "hat"]
+        );
+    }
+
+    #[test]
+    fn weaknesses_come_from_the_db() {
+        let db = TempDb::new("drill");
+        let db_path = db.path();
+        let conn = Connection::open(&db_path).unwrap();
+        let mut keystrokes = String::new();
+        // "ab" typed over and over, with b always slow to follow a
+        for race_offset in 0..5 {
+            let start = race_offset * 1000;
+            keystrokes.push_str(&format!(
+                "(1, {}, 0, 'a', 'a', 1, 0), (1, {}, 1, 'b', 'b', 1, 0), ",
+                start + 100,
+                start + 900
+            ));
+        }
+        conn.execute_batch(&format!(
+            "INSERT INTO passages (passage, passage_len) VALUES ('/default/a', 2);
+            INSERT INTO passage_stats (row_id, passage, game_mode) VALUES (1, '/default/a', 0);
+            INSERT INTO char_errors (expected, previous, typed, game_mode, count)
+                VALUES ('c', '', 'x', 0, 3);
+            INSERT INTO keystrokes
+                (passage_stats_id, offset_millis, position, expected, typed, correct, backspace)
+                VALUES {};",
+            keystrokes.trim_end_matches(", ")
+        ))
+        .unwrap();
+
+        let weaknesses = retrieve_weaknesses(&conn).unwrap();
+
+        assert_eq!(weaknesses.chars.get(&'c'), Some(&1.0));
+        assert!(weaknesses.chars.contains_key(&'b'));
+        assert!(weaknesses.bigrams.contains_key(&('a', 'b')));
+        assert!(!weaknesses.chars.contains_key(&'a'));
+    }
+}
@@ -1,16 +1,23 @@
 use itertools::izip;
 use rand::Rng;
 use std::{
-    fs::{read_dir, DirEntry, File},
-    io::{BufRead, BufReader},
+    fs::{read_dir, DirEntry},
     path,
     path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
 };
 
 use crate::{actions::Action, config::TyperacerConfig, dirs::setup_dirs};
 use rand::seq::SliceRandom;
 use rusqlite::{params, Connection};
 
+pub mod drill;
+pub mod passage_file;
+pub mod passage_index;
+
+/// Number of words in a training passage
+const TRAINING_LENGTH: usize = 25;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PassageInfo {
     pub passage: String,
@@ -18,6 +25,16 @@ pub struct PassageInfo {
     pub passage_path: String,
 }
 
+impl PassageInfo {
+    /// Code is typed with its line breaks and indentation. Passages from a code pack are code even
+    /// when they fit on one line. Prose is always flattened onto one line, so a passage spanning
+    /// lines is code too, which covers passages whose pack isn't installed here.
+    pub fn is_code(&self) -> bool {
+        self.passage.contains('\n')
+            || passage_file::in_code_pack(path::Path::new(&self.passage_path))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Controller<'a> {
     passages: Vec<PassageInfo>,
@@ -26,6 +43,11 @@ pub struct Controller<'a> {
     start_idx: usize,
     first_run: bool,
     config: &'a TyperacerConfig,
+    /// Every word in the enabled lang packs, loaded the first time a drill is asked for
+    drill_words: Option<Vec<String>>,
+    /// Passages in the enabled lang packs, loaded from the passage index the first time a passage
+    /// is asked for
+    indexed_passages: Option<Vec<passage_index::IndexedPassage>>,
 }
 
 enum DirType {
@@ -47,18 +69,32 @@ impl<'a> Controller<'a> {
             start_idx: 0,
             first_run: true,
             config,
+            drill_words: None,
+            indexed_passages: None,
         }
     }
 
+    /// Build a training passage out of the words the user has missed.
+    /// Words that are due for review come first, oldest first, and if there aren't enough of them
+    /// the passage is topped up with the words that will be due soonest.
     pub fn retrieve_mistaken_words_passage(
         &mut self,
         conn: &Connection,
     ) -> Result<PassageInfo, rusqlite::Error> {
-        let mut stmt = conn.prepare("SELECT word from mistaken_words")?;
-
-        let user_results_iter = stmt.query_map(params![], |row| row.get(0))?;
-
-        let words = user_results_iter
+        let now_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as i64;
+        let mut stmt = conn.prepare(
+            "SELECT word FROM mistaken_words
+                ORDER BY next_due_secs > ?1, next_due_secs ASC, miss_count DESC
+                LIMIT ?2",
+        )?;
+
+        let user_results_iter =
+            stmt.query_map(params![now_secs, TRAINING_LENGTH as i64], |row| row.get(0))?;
+
+        let mut words = user_results_iter
             .map(|result| result.unwrap())
             .collect::<Vec<String>>();
 
@@ -70,19 +106,56 @@ impl<'a> Controller<'a> {
             });
         }
 
-        let words_string = words
-            .choose_multiple(&mut rand::thread_rng(), 25)
-            .cloned()
-            .collect::<Vec<String>>()
-            .join(" ");
+        // Mix them up so the order doesn't give away which words are the most overdue
+        words.shuffle(&mut rand::thread_rng());
 
         Ok(PassageInfo {
-            passage: words_string,
+            passage: words.join(" "),
             title: "Mistaken Words".to_owned(),
             passage_path: "TRAINING_MODE_PATH".to_owned(),
         })
     }
 
+    /// Build a drill out of words from the enabled lang packs that are heavy on the characters and
+    /// pairs of characters the user misses or is slow to type.
+    pub fn retrieve_drill_passage(
+        &mut self,
+        conn: &Connection,
+    ) -> Result<PassageInfo, rusqlite::Error> {
+        let weaknesses = drill::retrieve_weaknesses(conn)?;
+        let words = if weaknesses.is_empty() {
+            vec![]
+        } else {
+            drill::pick_drill_words(self.get_drill_words(), &weaknesses, drill::DRILL_LENGTH)
+        };
+
+        if words.is_empty() {
+            return Ok(PassageInfo {
+                passage: "We don't know your weak spots yet! Play the default game mode for a while and come back to drill on them.".to_owned(),
+                title: "Drill".to_owned(),
+                passage_path: "DRILL_MODE_PATH".to_owned(),
+            });
+        }
+
+        Ok(PassageInfo {
+            passage: words.join(" "),
+            title: format!("Drill: {}", drill::describe(&weaknesses)),
+            passage_path: "DRILL_MODE_PATH".to_owned(),
+        })
+    }
+
+    fn get_drill_words(&mut self) -> &[String] {
+        if self.drill_words.is_none() {
+            let passages: Vec<PassageInfo> = self
+                .get_enabled_quote_files()
+                .iter()
+                .filter_map(|quote| passage_file::read_passage_file(&quote.path()).ok())
+                .collect();
+            self.drill_words = Some(drill::to_word_pool(passages.iter()));
+        }
+        self.drill_words.as_deref().unwrap_or_default()
+    }
+
     /// Retrieve a passage.
     /// Takes into account history and the previous action given.
     pub fn retrieve_passage(&mut self, action: Action) -> &PassageInfo {
@@ -102,7 +175,8 @@ impl<'a> Controller<'a> {
         // Doing nothing on the first run solves this problem.
         if self.first_run {
             self.first_run = false;
-            self.passages.push(self.get_new_passage());
+            let passage = self.get_new_passage();
+            self.passages.push(passage);
         } else {
             self.current_passage_idx = (self.current_passage_idx + 1) % self.history_size;
 
@@ -112,15 +186,17 @@ impl<'a> Controller<'a> {
             if self.current_passage_idx == self.start_idx {
                 self.start_idx = (self.start_idx + 1) % self.history_size;
                 // Should we expand the vector, or push a new passage on?
+                let passage = self.get_new_passage();
                 if self.passages.len() < self.history_size {
-                    self.passages.push(self.get_new_passage());
+                    self.passages.push(passage);
                 } else {
-                    self.passages[self.current_passage_idx] = self.get_new_passage();
+                    self.passages[self.current_passage_idx] = passage;
                 }
             } else if self.passages.len() < self.history_size
                 && self.current_passage_idx == self.passages.len()
             {
-                self.passages.push(self.get_new_passage());
+                let passage = self.get_new_passage();
+                self.passages.push(passage);
             }
         }
         &self.passages[self.current_passage_idx]
@@ -195,21 +271,15 @@ impl<'a> Controller<'a> {
     }
 
     fn filter_user_dirs(&self, dir_type: DirType) -> DirType {
-        if self.config.lang_packs.is_some() {
-            if self
-                .config
-                .lang_packs
-                .as_ref()
-                .unwrap()
-                .blacklisted
-                .is_some()
-            {
-                self.filter_blacklist(dir_type)
-            } else {
-                self.filter_whitelist(dir_type)
+        match &self.config.lang_packs {
+            Some(lang_packs) => {
+                if lang_packs.blacklisted.is_some() {
+                    self.filter_blacklist(dir_type)
+                } else {
+                    self.filter_whitelist(dir_type)
+                }
             }
-        } else {
-            dir_type
+            None => dir_type,
         }
     }
 
@@ -320,6 +390,8 @@ impl<'a> Controller<'a> {
         read_dir(&path)
             .unwrap_or_else(|err| panic!("Couldn't read directory {}: {}", path.display(), err))
             .map(|entry| entry.expect("Failed to evaluate path when reading files"))
+            // Hidden files like the code pack marker describe the pack, they aren't quotes
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
             .collect()
     }
 
@@ -366,66 +438,116 @@ impl<'a> Controller<'a> {
         }
     }
 
-    // Retrieve a random passage and title from quote database.
-    // Defaults to boring passage if no files are found.
-    // Returns (passage, author/title)
-    // TODO: Test
-    // Difficult to test with unit tests. Expects a database file.
-    #[cfg(not(test))]
-    fn get_new_passage(&self) -> PassageInfo {
-        let fallback = PassageInfo {
-            passage: "The quick brown fox jumps over the lazy dog".to_owned(),
-            title: "darrienglasser.com".to_owned(),
-            passage_path: "FALLBACK_PATH".to_owned(),
-        };
-
-        let mut quote_dirs: Vec<DirEntry> = vec![];
+    /// Every quote file in the packs the user has enabled
+    fn get_enabled_quote_files(&self) -> Vec<DirEntry> {
+        let mut quotes: Vec<DirEntry> = vec![];
 
         for dir in self.get_quote_dirs().into_iter() {
-            quote_dirs.append(&mut match self.filter_user_dirs(dir) {
+            let quote_dirs = match self.filter_user_dirs(dir) {
                 DirType::MainDir(v) | DirType::ExtraDirs(v) => v,
-            });
+            };
+            for quote_dir in quote_dirs {
+                quotes.append(&mut self.get_files_from_dir(quote_dir));
+            }
         }
 
-        if quote_dirs.is_empty() {
-            return fallback;
-        }
+        quotes
+    }
 
-        let mut quotes: Vec<DirEntry> = vec![];
-        let mut num_files: usize = 0;
+    /// Bring the passage index up to date with the installed packs and load the passages in the
+    /// packs the user has enabled
+    #[cfg(not(test))]
+    fn load_indexed_passages(&self) -> Result<Vec<passage_index::IndexedPassage>, rusqlite::Error> {
+        let quote_dirs = setup_dirs::get_quote_dirs();
+        let mut conn = Connection::open(setup_dirs::get_db_path())?;
+        passage_index::refresh_index(
+            &mut conn,
+            &quote_dirs.main_pack_dir,
+            &quote_dirs.extra_packs,
+        )?;
+        let (enabled_dirs, _) = self.get_quote_dir_shortnames();
+        passage_index::retrieve_passages(&conn, &enabled_dirs)
+    }
+
+    /// Start at a random passage in the index, and if it's no good keep going until we find one
+    /// that is. A passage that can't be read or doesn't match the index means its pack changed
+    /// without a new version, so the pack is indexed again next time.
+    #[cfg(not(test))]
+    fn pick_indexed_passage(
+        indexed_passages: &[passage_index::IndexedPassage],
+    ) -> Option<PassageInfo> {
+        let invalidate = |passage_path: &path::Path| {
+            if let Ok(conn) = Connection::open(setup_dirs::get_db_path()) {
+                let _ = passage_index::invalidate_pack_of(&conn, passage_path);
+            }
+        };
 
-        for dir in quote_dirs {
-            let mut quotes_in_dir = self.get_files_from_dir(dir);
-            num_files += quotes_in_dir.len();
-            quotes.append(&mut quotes_in_dir);
+        let num_passages = indexed_passages.len();
+        let random_passage_num = rand::thread_rng().gen_range(0..num_passages);
+        for count in 0..num_passages {
+            let indexed = &indexed_passages[(random_passage_num + count) % num_passages];
+            match passage_file::read_passage_file(&indexed.passage_path) {
+                Ok(passage_info) => {
+                    if passage_index::passage_hash(&passage_info.passage) != indexed.passage_hash {
+                        invalidate(&indexed.passage_path);
+                    }
+                    return Some(passage_info);
+                }
+                Err(_) => invalidate(&indexed.passage_path),
+            }
         }
+        None
+    }
+
+    /// Every pack is looked through for a passage, for when there's no passage index to go by
+    #[cfg(not(test))]
+    fn pick_scanned_passage(&self) -> Option<PassageInfo> {
+        let quotes = self.get_enabled_quote_files();
+        let num_files = quotes.len();
 
         if num_files > 0 {
+            // Start at a random file, and if it's no good keep going until we find one that is
             let random_file_num = rand::thread_rng().gen_range(0..num_files);
-            for (count, path) in quotes.iter().enumerate() {
-                let path = path.path();
-                if count == random_file_num {
-                    let file = File::open(&path).expect("Unable to open quote file");
-                    let mut passage: Vec<String> = vec![];
-                    for line in BufReader::new(file).lines() {
-                        passage.push(line.unwrap());
-                    }
-                    if passage.len() >= 2 {
-                        return PassageInfo {
-                            passage: passage[0].trim().to_string(),
-                            title: passage[1].clone(),
-                            passage_path: path.to_string_lossy().into_owned(),
-                        };
-                    }
+            for count in 0..num_files {
+                let path = quotes[(random_file_num + count) % num_files].path();
+                if let Ok(passage_info) = passage_file::read_passage_file(&path) {
+                    return Some(passage_info);
                 }
             }
         }
+        None
+    }
+
+    // Retrieve a random passage and title from the passage index, which is brought up to date the
+    // first time a passage is asked for.
+    // Defaults to boring passage if no files are found.
+    // Returns (passage, author/title)
+    // TODO: Test
+    // Difficult to test with unit tests. Expects a database file.
+    #[cfg(not(test))]
+    fn get_new_passage(&mut self) -> PassageInfo {
+        let fallback = PassageInfo {
+            passage: "The quick brown fox jumps over the lazy dog".to_owned(),
+            title: "darrienglasser.com".to_owned(),
+            passage_path: "FALLBACK_PATH".to_owned(),
+        };
+
+        if self.indexed_passages.is_none() {
+            // An empty index falls back to looking through the packs
+            self.indexed_passages = Some(self.load_indexed_passages().unwrap_or_default());
+        }
+        let passage_info = match self.indexed_passages.as_deref() {
+            Some(indexed_passages) if !indexed_passages.is_empty() => {
+                Controller::pick_indexed_passage(indexed_passages)
+            }
+            _ => self.pick_scanned_passage(),
+        };
 
-        fallback
+        passage_info.unwrap_or(fallback)
     }
 
     #[cfg(test)]
-    fn get_new_passage(&self) -> PassageInfo {
+    fn get_new_passage(&mut self) -> PassageInfo {
         // Since we aren't working with real passages, we need some source of randomness
         // The chance that two of these collide is close enough to zero that we can assume they
         // will always be different.
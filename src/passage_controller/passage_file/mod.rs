@@ -0,0 +1,308 @@
+use serde::Deserialize;
+use std::{
+    fmt,
+    fs::File,
+    io::{BufRead, BufReader, Error, Read},
+    path::Path,
+};
+
+use crate::{game::split, passage_controller::PassageInfo};
+
+/// A quote directory containing a file with this name is a code pack
+pub const CODE_PACK_MARKER: &str = ".code";
+
+/// Everything a structured passage file can say about a passage. Only `text` is required.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PassageMetadata {
+    pub text: String,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub source: Option<String>,
+    pub language: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub difficulty: Option<Difficulty>,
+}
+
+/// Packs can rate difficulty however they like, as a number or a word
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum Difficulty {
+    Level(i64),
+    Name(String),
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Difficulty::Level(level) => write!(f, "{}", level),
+            Difficulty::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl PassageMetadata {
+    /// Title shown above the passage, packing in as much of the metadata as we have.
+    /// e.g. "The Angel by Aesop - Fables (English, difficulty 2) #animals #morals"
+    pub fn display_title(&self) -> String {
+        let mut title = match (&self.title, &self.author) {
+            (Some(title), Some(author)) => format!("{} by {}", title, author),
+            (Some(title), None) => title.clone(),
+            (None, Some(author)) => author.clone(),
+            (None, None) => "Untitled".to_owned(),
+        };
+        if let Some(source) = &self.source {
+            title.push_str(&format!(" - {}", source));
+        }
+
+        let details: Vec<String> = self
+            .language
+            .iter()
+            .cloned()
+            .chain(
+                self.difficulty
+                    .iter()
+                    .map(|difficulty| format!("difficulty {}", difficulty)),
+            )
+            .collect();
+        if !details.is_empty() {
+            title.push_str(&format!(" ({})", details.join(", ")));
+        }
+
+        for tag in self.tags.iter().flatten() {
+            title.push_str(&format!(" #{}", tag));
+        }
+        title
+    }
+}
+
+/// Why a passage file can't be played
+#[derive(Debug)]
+pub enum PassageFileError {
+    Unreadable(Error),
+    /// Two line files need both a passage and an attribution
+    MissingLines,
+    EmptyPassage,
+    Malformed(String),
+}
+
+impl fmt::Display for PassageFileError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PassageFileError::Unreadable(e) => write!(f, "unable to read file: {}", e),
+            PassageFileError::MissingLines => {
+                write!(
+                    f,
+                    "needs a passage on the first line and attribution on the second"
+                )
+            }
+            PassageFileError::EmptyPassage => write!(f, "passage has no text to type"),
+            PassageFileError::Malformed(e) => write!(f, "malformed passage file: {}", e),
+        }
+    }
+}
+
+/// Read a quote file from a lang pack.
+/// `.toml` and `.json` files are structured passages with metadata, see `PassageMetadata`.
+/// Anything else is a legacy quote file: the passage on the first line followed by the title.
+/// Code packs have passages spanning many lines, so they flip it around: the title first, then
+/// the code.
+pub fn read_passage_file(path: &Path) -> Result<PassageInfo, PassageFileError> {
+    let is_code_pack = in_code_pack(path);
+    let file = File::open(path).map_err(PassageFileError::Unreadable)?;
+
+    let (passage, title) = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext @ ("toml" | "json")) => {
+            let mut contents = String::new();
+            BufReader::new(file)
+                .read_to_string(&mut contents)
+                .map_err(PassageFileError::Unreadable)?;
+            let metadata = parse_structured(ext, &contents)?;
+            let passage = if is_code_pack {
+                split::tidy_code(&metadata.text)
+            } else {
+                flatten_prose(&metadata.text)
+            };
+            (passage, metadata.display_title())
+        }
+        _ => {
+            let lines = BufReader::new(file)
+                .lines()
+                .collect::<Result<Vec<String>, Error>>()
+                .map_err(PassageFileError::Unreadable)?;
+            if lines.len() < 2 {
+                return Err(PassageFileError::MissingLines);
+            }
+            if is_code_pack {
+                (split::tidy_code(&lines[1..].join("\n")), lines[0].clone())
+            } else {
+                (lines[0].trim().to_string(), lines[1].clone())
+            }
+        }
+    };
+
+    if passage.is_empty() {
+        return Err(PassageFileError::EmptyPassage);
+    }
+    Ok(PassageInfo {
+        passage,
+        title,
+        passage_path: path.to_string_lossy().into_owned(),
+    })
+}
+
+/// Whether a quote file sits next to a `CODE_PACK_MARKER`. Paths that aren't in a directory, like
+/// the ones given to user input, never are.
+pub fn in_code_pack(path: &Path) -> bool {
+    path.parent()
+        .filter(|pack_dir| !pack_dir.as_os_str().is_empty())
+        .is_some_and(|pack_dir| pack_dir.join(CODE_PACK_MARKER).exists())
+}
+
+fn parse_structured(ext: &str, contents: &str) -> Result<PassageMetadata, PassageFileError> {
+    if ext == "toml" {
+        toml::from_str(contents).map_err(|e| PassageFileError::Malformed(e.to_string()))
+    } else {
+        serde_json::from_str(contents).map_err(|e| PassageFileError::Malformed(e.to_string()))
+    }
+}
+
+/// Prose is typed as a single line, so line breaks in the text are only there to make the file
+/// easier to read. Wide character languages don't put spaces between words, so their lines are
+/// joined without one.
+pub fn flatten_prose(text: &str) -> String {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    match lines.first() {
+        Some(first_line) if split::is_wide_character(first_line) => lines.concat(),
+        _ => lines
+            .iter()
+            .flat_map(|line| line.split_whitespace())
+            .collect::<Vec<&str>>()
+            .join(" "),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    /// A fresh pack dir holding the passage files
+    fn pack_dir_with(files: &[(&str, &str)]) -> TempDir {
+        let pack_dir = TempDir::new("pack");
+        for (name, contents) in files {
+            pack_dir.write(name, contents);
+        }
+        pack_dir
+    }
+
+    #[test]
+    fn reads_legacy_passages() {
+        let pack_dir = pack_dir_with(&[
+            ("voltaire", "The best is the enemy of the good. \nVoltaire"),
+            ("short", "Just a passage"),
+        ]);
+
+        let passage_info = read_passage_file(&pack_dir.join("voltaire"));
+        let short = read_passage_file(&pack_dir.join("short"));
+
+        let passage_info = passage_info.unwrap();
+        assert_eq!(passage_info.passage, "The best is the enemy of the good.");
+        assert_eq!(passage_info.title, "Voltaire");
+        assert!(matches!(short, Err(PassageFileError::MissingLines)));
+    }
+
+    #[test]
+    fn reads_structured_passages() {
+        let pack_dir = pack_dir_with(&[
+            (
+                "angel.toml",
+                r#"
+title = "The Angel"
+author = "Aesop"
+source = "Fables"
+language = "English"
+tags = ["animals", "morals"]
+difficulty = 2
+text = """
+Close by grew a slender, beautiful, rose-bush,
+but some wicked hand had broken the stem.
+"""
+"#,
+            ),
+            (
+                "voltaire.json",
+                r#"{"text": "The best is the enemy of the good.", "author": "Voltaire", "difficulty": "easy"}"#,
+            ),
+            ("typo.toml", "txet = \"oops\""),
+        ]);
+
+        let angel = read_passage_file(&pack_dir.join("angel.toml"));
+        let voltaire = read_passage_file(&pack_dir.join("voltaire.json"));
+        let typo = read_passage_file(&pack_dir.join("typo.toml"));
+
+        let angel = angel.unwrap();
+        assert_eq!(
+            angel.passage,
+            "Close by grew a slender, beautiful, rose-bush, but some wicked hand had broken the stem."
+        );
+        assert_eq!(
+            angel.title,
+            "The Angel by Aesop - Fables (English, difficulty 2) #animals #morals"
+        );
+
+        let voltaire = voltaire.unwrap();
+        assert_eq!(voltaire.passage, "The best is the enemy of the good.");
+        assert_eq!(voltaire.title, "Voltaire (difficulty easy)");
+
+        assert!(matches!(typo, Err(PassageFileError::Malformed(_))));
+    }
+
+    #[test]
+    fn reads_code_passages() {
+        let pack_dir = pack_dir_with(&[
+            (CODE_PACK_MARKER, ""),
+            (
+                "hello",
+                "Hello world\nfn main() {\n\tprintln!(\"hi\");\n}\n",
+            ),
+            (
+                "hello.toml",
+                "title = \"Hello world\"\ntext = \"\"\"\nfn main() {\n    println!(\"hi\");\n}\n\"\"\"",
+            ),
+            ("increment", "Increment\nx += 1;\n"),
+        ]);
+        let prose_dir = pack_dir_with(&[("one-liner", "x += 1;\nNot code")]);
+
+        let legacy = read_passage_file(&pack_dir.join("hello"));
+        let structured = read_passage_file(&pack_dir.join("hello.toml"));
+        let one_line = read_passage_file(&pack_dir.join("increment")).unwrap();
+        let prose = read_passage_file(&prose_dir.join("one-liner")).unwrap();
+
+        for passage_info in [legacy.unwrap(), structured.unwrap()] {
+            assert_eq!(passage_info.title, "Hello world");
+            assert_eq!(
+                passage_info.passage,
+                "fn main() {\n    println!(\"hi\");\n}"
+            );
+            assert!(passage_info.is_code());
+        }
+        // It's the pack that makes a passage code, not its line breaks
+        assert_eq!(one_line.passage, "x += 1;");
+        assert!(one_line.is_code());
+        assert!(!prose.is_code());
+    }
+
+    #[test]
+    fn flattens_wide_prose_without_spaces() {
+        assert_eq!(flatten_prose("你好\n  你好\n"), "你好你好");
+        assert_eq!(
+            flatten_prose("the quick\n  brown   fox\n"),
+            "the quick brown fox"
+        );
+    }
+}
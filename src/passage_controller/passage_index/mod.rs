@@ -0,0 +1,346 @@
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::passage_controller::passage_file;
+
+/// A passage that could be read when its pack was indexed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedPassage {
+    pub passage_path: PathBuf,
+    /// See `passage_hash`, if the file no longer matches the pack needs indexing again
+    pub passage_hash: i64,
+}
+
+/// Stable across runs and Rust versions (unlike `DefaultHasher`) since it's kept in the database.
+/// FNV-1a, which is plenty for telling when a passage changed.
+pub fn passage_hash(passage: &str) -> i64 {
+    passage.bytes().fold(0xcbf29ce484222325, |hash: u64, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    }) as i64
+}
+
+/// Contents of a pack's version file, which change whenever the pack does. Packs are versioned and
+/// given a new uuid when their passages change, so there's no need to look any deeper.
+fn pack_version(pack_dir: &Path) -> String {
+    fs::read_to_string(pack_dir.join("version"))
+        .map(|version| version.trim().to_owned())
+        .unwrap_or_default()
+}
+
+/// Bring the index up to date with the installed packs, indexing any pack that is new or has
+/// changed version since it was last indexed and dropping packs that are gone.
+/// Quote dirs in the main pack are known by their name, and extra packs by the pack and quote dir,
+/// the same names used to black and whitelist them.
+pub fn refresh_index(
+    conn: &mut Connection,
+    main_pack_dir: &Path,
+    extra_packs: &[PathBuf],
+) -> Result<(), rusqlite::Error> {
+    let tx = conn.transaction()?;
+    let mut indexed_versions: HashMap<String, String> = {
+        let mut stmt = tx.prepare("SELECT pack_dir, version FROM passage_index_packs")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect::<Result<_, _>>()?
+    };
+
+    let packs = std::iter::once((main_pack_dir, true))
+        .chain(
+            extra_packs
+                .iter()
+                .map(|pack_dir| (pack_dir.as_path(), false)),
+        )
+        .filter(|(pack_dir, _)| pack_dir.is_dir());
+    for (pack_dir, is_main) in packs {
+        let pack_key = pack_dir.to_string_lossy().into_owned();
+        let version = pack_version(pack_dir);
+        if indexed_versions.remove(&pack_key).as_ref() != Some(&version) {
+            forget_pack(&tx, &pack_key)?;
+            index_pack(&tx, pack_dir, &pack_key, &version, is_main)?;
+        }
+    }
+
+    // Whatever is left was uninstalled
+    for pack_key in indexed_versions.keys() {
+        forget_pack(&tx, pack_key)?;
+    }
+    tx.commit()
+}
+
+fn forget_pack(conn: &Connection, pack_key: &str) -> Result<(), rusqlite::Error> {
+    conn.execute(
+        "DELETE FROM passage_index WHERE pack_dir = ?1",
+        params![pack_key],
+    )?;
+    conn.execute(
+        "DELETE FROM passage_index_packs WHERE pack_dir = ?1",
+        params![pack_key],
+    )?;
+    Ok(())
+}
+
+/// Visible entries of a directory. Hidden ones like `.git` and the code pack marker aren't
+/// passages.
+fn visible_entries(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| !entry.file_name().to_string_lossy().starts_with('.'))
+                .map(|entry| entry.path())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn index_pack(
+    tx: &Transaction,
+    pack_dir: &Path,
+    pack_key: &str,
+    version: &str,
+    is_main: bool,
+) -> Result<(), rusqlite::Error> {
+    tx.execute(
+        "INSERT INTO passage_index_packs (pack_dir, version) VALUES (?1, ?2)",
+        params![pack_key, version],
+    )?;
+
+    let mut insert = tx.prepare(
+        "INSERT OR REPLACE INTO passage_index (
+            passage_path,
+            pack_dir,
+            quote_dir,
+            passage_len,
+            passage_hash,
+            title
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+    )?;
+    for quote_dir in visible_entries(pack_dir)
+        .into_iter()
+        .filter(|path| path.is_dir())
+    {
+        let quote_dir_name = if is_main {
+            quote_dir.file_stem()
+        } else {
+            quote_dir.file_name()
+        }
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+        let quote_dir_key = if is_main {
+            quote_dir_name
+        } else {
+            let pack_name = pack_dir
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            format!(
+                "{}{}{}",
+                pack_name,
+                std::path::MAIN_SEPARATOR,
+                quote_dir_name
+            )
+        };
+
+        for quote_file in visible_entries(&quote_dir) {
+            // Passages that can't be read are left out, so they're never picked
+            if let Ok(passage_info) = passage_file::read_passage_file(&quote_file) {
+                insert.execute(params![
+                    passage_info.passage_path,
+                    pack_key,
+                    quote_dir_key,
+                    passage_info.passage.chars().count() as i64,
+                    passage_hash(&passage_info.passage),
+                    passage_info.title,
+                ])?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every indexed passage in the given quote dirs
+pub fn retrieve_passages(
+    conn: &Connection,
+    quote_dirs: &[String],
+) -> Result<Vec<IndexedPassage>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT passage_path, passage_hash FROM passage_index
+            WHERE quote_dir = ?1
+            ORDER BY passage_path",
+    )?;
+    let mut passages = vec![];
+    for quote_dir in quote_dirs {
+        let rows = stmt.query_map(params![quote_dir], |row| {
+            Ok(IndexedPassage {
+                passage_path: PathBuf::from(row.get::<_, String>(0)?),
+                passage_hash: row.get(1)?,
+            })
+        })?;
+        for passage in rows {
+            passages.push(passage?);
+        }
+    }
+    Ok(passages)
+}
+
+/// The pack a passage came from changed without its version changing, index it again next time.
+/// The pack is forgotten rather than given a made up version, which a pack without a version file
+/// could end up matching.
+pub fn invalidate_pack_of(conn: &Connection, passage_path: &Path) -> Result<(), rusqlite::Error> {
+    let tx = conn.unchecked_transaction()?;
+    let pack_key: Option<String> = tx
+        .query_row(
+            "SELECT pack_dir FROM passage_index WHERE passage_path = ?1",
+            params![passage_path.to_string_lossy()],
+            |row| row.get(0),
+        )
+        .optional()?;
+    if let Some(pack_key) = pack_key {
+        forget_pack(&tx, &pack_key)?;
+    }
+    tx.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TempDb, TempDir};
+
+    struct TestPacks {
+        dir: TempDir,
+        db_path: PathBuf,
+        _db: TempDb,
+    }
+
+    impl TestPacks {
+        fn new() -> TestPacks {
+            let db = TempDb::new("index-db");
+            TestPacks {
+                dir: TempDir::new("index"),
+                db_path: db.path(),
+                _db: db,
+            }
+        }
+
+        fn write(&self, path: &str, contents: &str) {
+            self.dir.write(path, contents);
+        }
+
+        fn refresh(&self) {
+            refresh_index(
+                &mut Connection::open(&self.db_path).unwrap(),
+                &self.dir.join("main"),
+                &[self.dir.join("extra").join("team")],
+            )
+            .unwrap();
+        }
+
+        /// Names of the passages in the quote dirs, relative to the test dir
+        fn passages(&self, quote_dirs: &[&str]) -> Vec<String> {
+            let quote_dirs: Vec<String> = quote_dirs
+                .iter()
+                .map(|quote_dir| quote_dir.replace('/', std::path::MAIN_SEPARATOR_STR))
+                .collect();
+            retrieve_passages(&Connection::open(&self.db_path).unwrap(), &quote_dirs)
+                .unwrap()
+                .into_iter()
+                .map(|passage| {
+                    passage
+                        .passage_path
+                        .strip_prefix(self.dir.path())
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace(std::path::MAIN_SEPARATOR, "/")
+                })
+                .collect()
+        }
+    }
+
+    #[test]
+    fn indexes_playable_passages() {
+        let packs = TestPacks::new();
+        packs.write("main/version", "1.0.0\nuuid");
+        packs.write(
+            "main/default/voltaire",
+            "The best is the enemy of the good.\nVoltaire",
+        );
+        packs.write("main/default/broken", "No attribution");
+        packs.write("main/.git/HEAD", "ref: refs/heads/main");
+        packs.write("main/harry-potter/dobby", "Dobby is free.\nDobby");
+        packs.write("extra/team/version", "1\nuuid");
+        packs.write("extra/team/default/motto", "Ship it.\nThe team");
+        packs.refresh();
+
+        assert_eq!(
+            packs.passages(&["default", "team/default"]),
+            vec!["main/default/voltaire", "extra/team/default/motto"]
+        );
+        assert_eq!(
+            packs.passages(&["harry-potter"]),
+            vec!["main/harry-potter/dobby"]
+        );
+        assert_eq!(passage_hash("Ship it."), passage_hash("Ship it."));
+        assert_ne!(passage_hash("Ship it."), passage_hash("Ship it!"));
+    }
+
+    #[test]
+    fn reindexes_packs_that_change() {
+        let packs = TestPacks::new();
+        packs.write("main/version", "1.0.0\nuuid");
+        packs.write("main/default/a", "First.\nA");
+        packs.write("extra/team/version", "1\nuuid");
+        packs.write("extra/team/default/motto", "Ship it.\nThe team");
+        packs.refresh();
+
+        // Same version, so it's left alone
+        packs.write("main/default/b", "Second.\nB");
+        packs.refresh();
+        assert_eq!(packs.passages(&["default"]), vec!["main/default/a"]);
+
+        packs.write("main/version", "1.1.0\nuuid");
+        packs.refresh();
+        assert_eq!(
+            packs.passages(&["default"]),
+            vec!["main/default/a", "main/default/b"]
+        );
+
+        // Changed under us without a new version
+        packs.write("main/default/c", "Third.\nC");
+        invalidate_pack_of(
+            &Connection::open(&packs.db_path).unwrap(),
+            &packs.dir.join("main").join("default").join("a"),
+        )
+        .unwrap();
+        packs.refresh();
+        assert_eq!(packs.passages(&["default"]).len(), 3);
+
+        // Uninstalled packs are dropped
+        fs::remove_dir_all(packs.dir.join("extra")).unwrap();
+        packs.refresh();
+        assert!(packs.passages(&["team/default"]).is_empty());
+    }
+
+    #[test]
+    fn invalidates_packs_without_a_version() {
+        let packs = TestPacks::new();
+        packs.write("main/default/a", "First.\nA");
+        packs.refresh();
+        packs.write("main/default/b", "Second.\nB");
+        packs.refresh();
+        assert_eq!(packs.passages(&["default"]), vec!["main/default/a"]);
+
+        invalidate_pack_of(
+            &Connection::open(&packs.db_path).unwrap(),
+            &packs.dir.join("main").join("default").join("a"),
+        )
+        .unwrap();
+        packs.refresh();
+        assert_eq!(
+            packs.passages(&["default"]),
+            vec!["main/default/a", "main/default/b"]
+        );
+    }
+}
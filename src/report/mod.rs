@@ -0,0 +1,320 @@
+use rusqlite::Connection;
+use serde::Serialize;
+use std::{
+    io::{Error, Write},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    config::Goals,
+    game::GameMode,
+    goals::{self, GoalProgress},
+};
+
+pub mod report_db;
+
+/// How to print stats for the `stats` subcommand
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Table,
+    Json,
+    Csv,
+}
+
+impl Format {
+    pub fn values() -> Vec<&'static str> {
+        vec!["table", "json", "csv"]
+    }
+
+    pub fn from_name(name: &str) -> Option<Format> {
+        match name {
+            "table" => Some(Format::Table),
+            "json" => Some(Format::Json),
+            "csv" => Some(Format::Csv),
+            _ => None,
+        }
+    }
+}
+
+/// A single race as it is stored in the stats db
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RaceRecord {
+    pub id: i64,
+    pub passage: String,
+    pub wpm: i64,
+    pub accuracy: f64,
+    pub highest_combo: i64,
+    pub game_mode: String,
+    pub when_played_secs: i64,
+    /// Only races played after race times were tracked have this
+    pub elapsed_millis: Option<i64>,
+}
+
+/// How the user does in a single game mode, across every race they've played in it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ModeSummary {
+    pub game_mode: String,
+    pub races: usize,
+    pub best_wpm: i64,
+    pub average_wpm: f64,
+    pub median_wpm: f64,
+    pub average_accuracy: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    pub summaries: Vec<ModeSummary>,
+    /// The most recent races, newest first
+    pub recent_races: Vec<RaceRecord>,
+    pub goals: GoalProgress,
+}
+
+/// Print a summary of every race in the stats db along with the last few races played and how
+/// the user is doing on today's goals.
+pub fn print_report(
+    db_path: &Path,
+    format: Format,
+    last: usize,
+    goals: &Goals,
+) -> Result<(), Error> {
+    let conn = Connection::open(db_path).map_err(Error::other)?;
+    let races = report_db::retrieve_races(&conn).map_err(Error::other)?;
+    let report = build_report(&races, last, goals::todays_progress(db_path, goals)?);
+
+    let mut out = std::io::stdout().lock();
+    match format {
+        Format::Table => write_table(&report, &mut out),
+        Format::Json => {
+            serde_json::to_writer_pretty(&mut out, &report)?;
+            writeln!(out)
+        }
+        Format::Csv => write_csv(&report, &mut out),
+    }
+}
+
+pub fn build_report(races: &[RaceRecord], last: usize, goals: GoalProgress) -> Report {
+    let summaries = GameMode::values()
+        .into_iter()
+        .filter_map(|game_mode| {
+            let mode_races: Vec<&RaceRecord> = races
+                .iter()
+                .filter(|race| race.game_mode == game_mode.to_string())
+                .collect();
+            summarize(game_mode, &mode_races)
+        })
+        .collect();
+
+    Report {
+        summaries,
+        recent_races: races.iter().rev().take(last).cloned().collect(),
+        goals,
+    }
+}
+
+/// None if there are no races to summarize
+fn summarize(game_mode: GameMode, races: &[&RaceRecord]) -> Option<ModeSummary> {
+    if races.is_empty() {
+        return None;
+    }
+
+    let mut wpms: Vec<i64> = races.iter().map(|race| race.wpm).collect();
+    wpms.sort_unstable();
+    let middle = wpms.len() / 2;
+    let median_wpm = if wpms.len() % 2 == 1 {
+        wpms[middle] as f64
+    } else {
+        (wpms[middle - 1] + wpms[middle]) as f64 / 2.0
+    };
+
+    Some(ModeSummary {
+        game_mode: game_mode.to_string(),
+        races: races.len(),
+        best_wpm: wpms[wpms.len() - 1],
+        average_wpm: wpms.iter().sum::<i64>() as f64 / races.len() as f64,
+        median_wpm,
+        average_accuracy: races.iter().map(|race| race.accuracy).sum::<f64>() / races.len() as f64,
+    })
+}
+
+fn write_table<W: Write>(report: &Report, out: &mut W) -> Result<(), Error> {
+    if report.summaries.is_empty() {
+        return writeln!(out, "No races yet! Play a few games and come back.");
+    }
+
+    writeln!(
+        out,
+        "{:<14} {:>6} {:>9} {:>9} {:>11} {:>9}",
+        "Mode", "Races", "Best WPM", "Avg WPM", "Median WPM", "Accuracy"
+    )?;
+    for summary in report.summaries.iter() {
+        writeln!(
+            out,
+            "{:<14} {:>6} {:>9} {:>9.1} {:>11.1} {:>8.1}%",
+            summary.game_mode,
+            summary.races,
+            summary.best_wpm,
+            summary.average_wpm,
+            summary.median_wpm,
+            summary.average_accuracy
+        )?;
+    }
+
+    writeln!(out, "\nLast {} races", report.recent_races.len())?;
+    writeln!(
+        out,
+        "{:>6} {:<12} {:<14} {:>5} {:>9} {:>8}  Passage",
+        "ID", "When", "Mode", "WPM", "Accuracy", "Time"
+    )?;
+    let now = now_secs();
+    for race in report.recent_races.iter() {
+        writeln!(
+            out,
+            "{:>6} {:<12} {:<14} {:>5} {:>8.1}% {:>8}  {}",
+            race.id,
+            time_ago(now, race.when_played_secs),
+            race.game_mode,
+            race.wpm,
+            race.accuracy,
+            race.elapsed_millis.map_or("-".to_owned(), |millis| format!(
+                "{:.1}s",
+                millis as f64 / 1000.0
+            )),
+            race.passage
+        )?;
+    }
+
+    writeln!(out, "\nToday")?;
+    for line in report.goals.lines() {
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+/// CSV can only hold one table at a time, so the summaries, recent races and goal progress are
+/// written as three tables separated by blank lines.
+fn write_csv<W: Write>(report: &Report, out: &mut W) -> Result<(), Error> {
+    let mut summary_writer = csv::Writer::from_writer(&mut *out);
+    for summary in report.summaries.iter() {
+        summary_writer.serialize(summary).map_err(Error::other)?;
+    }
+    summary_writer.flush()?;
+    drop(summary_writer);
+
+    writeln!(out)?;
+
+    let mut race_writer = csv::Writer::from_writer(&mut *out);
+    for race in report.recent_races.iter() {
+        race_writer.serialize(race).map_err(Error::other)?;
+    }
+    race_writer.flush()?;
+    drop(race_writer);
+
+    writeln!(out)?;
+
+    let mut goals_writer = csv::Writer::from_writer(&mut *out);
+    goals_writer
+        .serialize(&report.goals)
+        .map_err(Error::other)?;
+    goals_writer.flush()
+}
+
+pub fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("Time went backwards")
+        .as_secs() as i64
+}
+
+/// Rough human readable time since a race was played, e.g. "3h ago"
+pub fn time_ago(now_secs: i64, when_secs: i64) -> String {
+    let secs = (now_secs - when_secs).max(0);
+    match secs {
+        0..=59 => "just now".to_owned(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86_399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86_400),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn race(id: i64, game_mode: GameMode, wpm: i64, accuracy: f64) -> RaceRecord {
+        RaceRecord {
+            id,
+            passage: "/default/0022791a-573f-414b-954f-7582f5bcd09e".to_owned(),
+            wpm,
+            accuracy,
+            highest_combo: 10,
+            game_mode: game_mode.to_string(),
+            when_played_secs: id,
+            elapsed_millis: Some(10_000),
+        }
+    }
+
+    fn no_goals() -> GoalProgress {
+        goals::progress(&[], 0, &Goals::default())
+    }
+
+    #[test]
+    fn summarizes_each_mode_played() {
+        let races = vec![
+            race(1, GameMode::Default, 60, 90.0),
+            race(2, GameMode::Default, 80, 100.0),
+            race(3, GameMode::InstantDeath, 50, 100.0),
+            race(4, GameMode::Default, 90, 95.0),
+            race(5, GameMode::Default, 70, 95.0),
+        ];
+
+        let report = build_report(&races, 2, no_goals());
+
+        assert_eq!(
+            report.summaries,
+            vec![
+                ModeSummary {
+                    game_mode: "Default".to_owned(),
+                    races: 4,
+                    best_wpm: 90,
+                    average_wpm: 75.0,
+                    median_wpm: 75.0,
+                    average_accuracy: 95.0,
+                },
+                ModeSummary {
+                    game_mode: "Instant Death".to_owned(),
+                    races: 1,
+                    best_wpm: 50,
+                    average_wpm: 50.0,
+                    median_wpm: 50.0,
+                    average_accuracy: 100.0,
+                },
+            ]
+        );
+        assert_eq!(
+            report.recent_races,
+            vec![races[4].clone(), races[3].clone()]
+        );
+    }
+
+    #[test]
+    fn csv_has_every_table() {
+        let report = build_report(&[race(1, GameMode::Default, 60, 90.0)], 10, no_goals());
+        let mut out: Vec<u8> = vec![];
+        write_csv(&report, &mut out).unwrap();
+
+        let csv = String::from_utf8(out).unwrap();
+        let tables: Vec<&str> = csv.split("\n\n").collect();
+        assert_eq!(tables.len(), 3);
+        assert!(tables[0].starts_with("game_mode,races,best_wpm"));
+        assert!(tables[1].starts_with("id,passage,wpm"));
+        assert!(tables[2].starts_with("races_today,races_goal"));
+    }
+
+    #[test]
+    fn time_ago_is_rough() {
+        assert_eq!(time_ago(100, 70), "just now");
+        assert_eq!(time_ago(7200, 0), "2h ago");
+        assert_eq!(time_ago(3 * 86_400, 0), "3d ago");
+    }
+}
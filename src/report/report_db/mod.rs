@@ -0,0 +1,27 @@
+use rusqlite::{params, Connection, Result};
+
+use crate::{game::GameMode, report::RaceRecord};
+
+/// Every race the user has played, oldest first.
+pub fn retrieve_races(conn: &Connection) -> Result<Vec<RaceRecord>, rusqlite::Error> {
+    let mut stmt = conn.prepare(
+        "SELECT row_id, passage, wpm, accuracy, highest_combo, game_mode, when_played_secs, elapsed_millis
+            FROM passage_stats
+            ORDER BY when_played_secs ASC, row_id ASC",
+    )?;
+
+    let races = stmt.query_map(params![], |row| {
+        Ok(RaceRecord {
+            id: row.get(0)?,
+            passage: row.get(1)?,
+            wpm: row.get(2)?,
+            accuracy: row.get(3)?,
+            highest_combo: row.get(4)?,
+            game_mode: GameMode::from(row.get::<_, i64>(5)?).to_string(),
+            when_played_secs: row.get(6)?,
+            elapsed_millis: row.get(7)?,
+        })
+    })?;
+
+    races.collect()
+}
@@ -1,55 +1,76 @@
 use std::cmp::max;
 #[cfg(not(test))]
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::Instant;
 
 #[cfg(test)]
 #[derive(Debug, Clone)]
 struct Time {
-    seconds: u64,
+    millis: u64,
 }
 
 #[cfg(not(test))]
 #[derive(Debug, Clone)]
-struct Time {}
+struct Time {
+    origin: Instant,
+}
 
 impl Time {
     #[cfg(test)]
     pub fn new() -> Self {
-        Time { seconds: 0 }
+        Time { millis: 0 }
     }
 
     #[cfg(not(test))]
     pub fn new() -> Self {
-        Time {}
+        Time {
+            origin: Instant::now(),
+        }
     }
 
     #[cfg(test)]
     /// Return the time used for testing
     pub fn now(&self) -> u64 {
-        self.seconds
+        self.millis
     }
 
     #[cfg(not(test))]
-    /// Return the current time in seconds since UNIX_EPOCH
+    /// Return the milliseconds passed on the monotonic clock since this Time was created
     pub fn now(&self) -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("Time went backwards")
-            .as_secs()
+        self.origin.elapsed().as_millis() as u64
     }
 }
 
+/// A character the user got wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mistype {
+    pub expected: char,
+    /// The character in the passage before the one that was missed, None at the very start
+    pub previous: Option<char>,
+    pub typed: char,
+}
+
 /// Store the current stats to be displayed to the user
 #[derive(Debug, Clone)]
 pub struct Stats {
     pub errors: u16,
     wpm: u64,
     legacy_wpm: bool,
-    start_time: u64,
+    start_time: Option<u64>,
+    elapsed_millis: u64,
     time: Time,
     char_properly_typed: Vec<bool>,
     pub combo: usize,
     highest_combo: usize,
+    mistypes: Vec<Mistype>,
+    /// Every character typed, right or wrong, including the ones that were deleted again
+    typed_chars: u64,
+    /// How many characters were typed in each second since the first one
+    chars_per_second: Vec<u64>,
+    /// Wrong characters the user left in their input, these only stick around when the
+    /// race ends mid word
+    uncorrected_errors: u64,
+    /// Words per minute at the end of every second since the first character
+    wpm_samples: Vec<u64>,
 }
 
 impl Stats {
@@ -59,11 +80,17 @@ impl Stats {
             errors: 0,
             wpm: 0,
             legacy_wpm,
-            start_time: 0,
+            start_time: None,
+            elapsed_millis: 0,
             time: Time::new(),
             char_properly_typed: Vec::new(),
             combo: 0,
             highest_combo: 0,
+            mistypes: Vec::new(),
+            typed_chars: 0,
+            chars_per_second: Vec::new(),
+            uncorrected_errors: 0,
+            wpm_samples: Vec::new(),
         }
     }
 
@@ -71,6 +98,17 @@ impl Stats {
     /// If legacy is set to true, use the actual words per minute, otherwise use chars/5 per minute
     /// See: https://en.wikipedia.org/wiki/Words_per_minute#Alphanumeric_entry
     pub fn update_wpm(&mut self, word_idx: usize, word_vec: &[&str]) {
+        let start_time = match self.start_time {
+            Some(start_time) => start_time,
+            // The user hasn't started typing yet, there's nothing to measure
+            None => return,
+        };
+        self.update_wpm_at(self.time.now() - start_time, word_idx, word_vec);
+    }
+
+    /// Same as update_wpm, but `elapsed_millis` into the race rather than going by the clock.
+    /// Replays use this to follow the time recorded for the race, whatever speed they play at.
+    pub fn update_wpm_at(&mut self, elapsed_millis: u64, word_idx: usize, word_vec: &[&str]) {
         let word_count_float = if self.legacy_wpm {
             // Get words per minute where a word is a set of characters delimited by a space.
             word_idx as f64
@@ -84,22 +122,58 @@ impl Stats {
                 .sum();
             char_count as f64 / 5.0
         };
-        let minute_float = ((self.time.now() - self.start_time) as f64) / 60.0;
-        self.wpm = (word_count_float / minute_float).ceil() as u64
+        self.elapsed_millis = elapsed_millis;
+        if self.elapsed_millis == 0 {
+            self.wpm = 0;
+            return;
+        }
+
+        let minute_float = self.elapsed_millis as f64 / 60_000.0;
+        self.wpm = (word_count_float / minute_float).ceil() as u64;
+
+        // Updates don't land exactly on the second, so any seconds that went by since the last
+        // one all get the latest wpm
+        let full_seconds = (self.elapsed_millis / 1000) as usize;
+        if self.wpm_samples.len() < full_seconds {
+            self.wpm_samples.resize(full_seconds, self.wpm);
+        }
+    }
+
+    /// Count a typed character towards raw wpm and consistency
+    pub fn record_typed_char(&mut self) {
+        self.record_typed_char_at(self.get_millis_since_start());
+    }
+
+    /// Same as record_typed_char, but for a character typed `elapsed_millis` into the race
+    pub fn record_typed_char_at(&mut self, elapsed_millis: u64) {
+        self.typed_chars += 1;
+        let second = (elapsed_millis / 1000) as usize;
+        if self.chars_per_second.len() <= second {
+            self.chars_per_second.resize(second + 1, 0);
+        }
+        self.chars_per_second[second] += 1;
     }
 
-    /// Update the start time to the current time in seconds - 1
+    /// Set how many wrong characters are sitting in the user's input right now
+    pub fn update_uncorrected_errors(&mut self, uncorrected_errors: usize) {
+        self.uncorrected_errors = uncorrected_errors as u64;
+    }
+
+    /// Start the clock if it hasn't been started yet
     pub fn update_start_time(&mut self) {
-        if self.start_time == 0 {
-            self.start_time = self.time.now() - 1;
+        if self.start_time.is_none() {
+            self.start_time = Some(self.time.now());
         }
     }
 
     /// Increment the number of errors by 1
-    pub fn increment_errors(&mut self, current_letter: usize) {
+    /// If we know which character the user missed, remember it so we can tell them what to
+    /// practice.
+    pub fn increment_errors(&mut self, current_letter: usize, mistype: Option<Mistype>) {
         self.errors += 1;
         self.reset_combo();
         self.update_accuracy(false, current_letter);
+        self.mistypes.extend(mistype);
     }
 
     pub fn increment_combo(&mut self, current_letter: usize) {
@@ -146,21 +220,41 @@ impl Stats {
     pub fn reset(&mut self) {
         self.wpm = 0;
         self.errors = 0;
-        self.start_time = 0;
+        self.start_time = None;
+        self.elapsed_millis = 0;
         self.combo = 0;
+        self.highest_combo = 0;
         self.char_properly_typed = Vec::new();
+        self.mistypes = Vec::new();
+        self.typed_chars = 0;
+        self.chars_per_second = Vec::new();
+        self.uncorrected_errors = 0;
+        self.wpm_samples = Vec::new();
     }
 
     /// Create the vector of text elements
     pub fn text(&self) -> Vec<Vec<String>> {
         vec![
             vec!["WPM".to_string(), self.wpm.to_string()],
+            vec!["Raw".to_string(), self.get_raw_wpm().to_string()],
+            vec!["Net".to_string(), self.get_net_wpm().to_string()],
             vec!["Errors".to_string(), self.errors.to_string()],
             vec!["Combo".to_string(), self.combo.to_string()],
             vec![
                 "Acc".to_string(),
                 format!("{:.4}%", self.get_typing_accuracy().to_string()),
             ],
+            vec![
+                "Time".to_string(),
+                format!("{:.1}s", self.elapsed_millis as f64 / 1000.0),
+            ],
+            vec![
+                "Cons".to_string(),
+                self.get_consistency()
+                    .map_or("-".to_string(), |consistency| {
+                        format!("{:.0}%", consistency)
+                    }),
+            ],
         ]
     }
 
@@ -168,6 +262,51 @@ impl Stats {
         self.wpm
     }
 
+    /// Words per minute counting every character typed, mistakes and all.
+    /// A word is always 5 characters here, even with legacy wpm.
+    pub fn get_raw_wpm(&self) -> u64 {
+        if self.elapsed_millis == 0 {
+            return 0;
+        }
+        let minute_float = self.elapsed_millis as f64 / 60_000.0;
+        (self.typed_chars as f64 / 5.0 / minute_float).ceil() as u64
+    }
+
+    /// Raw wpm less a word per minute for every mistake left in the input.
+    /// See: https://en.wikipedia.org/wiki/Words_per_minute#Alphanumeric_entry
+    pub fn get_net_wpm(&self) -> u64 {
+        if self.elapsed_millis == 0 {
+            return 0;
+        }
+        let minute_float = self.elapsed_millis as f64 / 60_000.0;
+        let penalty = (self.uncorrected_errors as f64 / minute_float).ceil() as u64;
+        self.get_raw_wpm().saturating_sub(penalty)
+    }
+
+    /// How steady the user's speed was from one second to the next, out of 100.
+    /// 100 is the same number of characters every second and it falls as the standard
+    /// deviation grows relative to the average speed.
+    /// None until there are at least two full seconds to compare.
+    pub fn get_consistency(&self) -> Option<f64> {
+        // The last second is still going, so it would look slower than it is
+        let full_seconds = (self.elapsed_millis / 1000) as usize;
+        let samples = &self.chars_per_second[..full_seconds.min(self.chars_per_second.len())];
+        if samples.len() < 2 {
+            return None;
+        }
+
+        let mean = samples.iter().sum::<u64>() as f64 / samples.len() as f64;
+        if mean == 0.0 {
+            return Some(0.0);
+        }
+        let variance = samples
+            .iter()
+            .map(|count| (*count as f64 - mean).powi(2))
+            .sum::<f64>()
+            / samples.len() as f64;
+        Some((100.0 * (1.0 - variance.sqrt() / mean)).clamp(0.0, 100.0))
+    }
+
     pub fn get_highest_combo(&self) -> usize {
         self.highest_combo
     }
@@ -178,8 +317,29 @@ impl Stats {
     }
 
     /// Get the value of `start_time`
-    pub fn get_start_time(&self) -> u64 {
+    pub fn get_start_time(&self) -> Option<u64> {
+        self.start_time
+    }
+
+    /// Milliseconds since the first character was typed, 0 if nothing has been typed yet
+    pub fn get_millis_since_start(&self) -> u64 {
         self.start_time
+            .map_or(0, |start_time| self.time.now() - start_time)
+    }
+
+    /// Words per minute sampled once a second, oldest first
+    pub fn get_wpm_samples(&self) -> &[u64] {
+        &self.wpm_samples
+    }
+
+    /// Every character the user missed this game, in the order they were missed
+    pub fn get_mistypes(&self) -> &[Mistype] {
+        &self.mistypes
+    }
+
+    /// Milliseconds between the first character typed and the last time wpm was updated
+    pub fn get_elapsed_millis(&self) -> u64 {
+        self.elapsed_millis
     }
 }
 
@@ -194,8 +354,8 @@ mod tests {
 
         // Setup stats with testing start_time and time struct
         let mut stats = Stats::new(false);
-        stats.start_time = 500;
-        stats.time = Time { seconds: 501 };
+        stats.start_time = Some(500_000);
+        stats.time = Time { millis: 501_000 };
 
         // Where a word is 5 characters, we know the user has typed 10 characters
         // in 1 second, which comes out to 120 wpm.
@@ -203,8 +363,8 @@ mod tests {
         assert_eq!(stats.wpm, 120);
 
         let word_idx = 4;
-        stats.start_time = 500;
-        stats.time = Time { seconds: 510 };
+        stats.start_time = Some(500_000);
+        stats.time = Time { millis: 510_000 };
 
         // Where a word is 5 characters, we know the user has typed 20 characters in
         // 10 seconds. Which comes out to 24 wpm.
@@ -218,27 +378,55 @@ mod tests {
 
         // Setup stats with testing start_time and time struct
         let mut stats = Stats::new(true);
-        stats.start_time = 500;
-        stats.time = Time { seconds: 501 };
+        stats.start_time = Some(500_000);
+        stats.time = Time { millis: 501_000 };
 
         // The user has typed 2 words in 1 second, which comes out to 120 wpm.
         stats.update_wpm(word_idx, &[]);
         assert_eq!(stats.wpm, 120);
 
         let word_idx = 4;
-        stats.start_time = 500;
-        stats.time = Time { seconds: 510 };
+        stats.start_time = Some(500_000);
+        stats.time = Time { millis: 510_000 };
 
         // The user has typed 4 words in 10 seconds, which comes out to 24 wpm.
         stats.update_wpm(word_idx, &[]);
         assert_eq!(stats.wpm, 24);
     }
 
+    #[test]
+    fn test_sub_second_wpm() {
+        let word_vec = vec!["There's", "a", "time", "when", "the", "operation"];
+
+        let mut stats = Stats::new(false);
+        stats.start_time = Some(500_000);
+        stats.time = Time { millis: 501_500 };
+
+        // 10 characters in 1.5 seconds comes out to 80 wpm.
+        // Whole second timing would have called this 120 wpm.
+        stats.update_wpm(2, &word_vec);
+        assert_eq!(stats.wpm, 80);
+        assert_eq!(stats.get_elapsed_millis(), 1500);
+    }
+
+    #[test]
+    fn test_wpm_before_start() {
+        // No time has passed when the user hasn't typed anything, so we shouldn't divide by it
+        let mut stats = Stats::new(false);
+        stats.update_wpm(2, &["the", "quick"]);
+        assert_eq!(stats.wpm, 0);
+
+        stats.update_start_time();
+        stats.update_wpm(0, &["the", "quick"]);
+        assert_eq!(stats.wpm, 0);
+        assert_eq!(stats.get_elapsed_millis(), 0);
+    }
+
     #[test]
     fn test_errors() {
         let mut stats = Stats::new(false);
         assert_eq!(stats.errors, 0);
-        stats.increment_errors(0);
+        stats.increment_errors(0, None);
         assert_eq!(stats.errors, 1);
     }
 
@@ -274,11 +462,11 @@ mod tests {
 
         let correct_vec = vec![false, false, false, false, false];
 
-        stats.increment_errors(0);
-        stats.increment_errors(1);
-        stats.increment_errors(2);
-        stats.increment_errors(3);
-        stats.increment_errors(4);
+        stats.increment_errors(0, None);
+        stats.increment_errors(1, None);
+        stats.increment_errors(2, None);
+        stats.increment_errors(3, None);
+        stats.increment_errors(4, None);
 
         assert_eq!(correct_vec, stats.char_properly_typed);
         assert_eq_float(stats.get_typing_accuracy(), 0.0);
@@ -290,11 +478,11 @@ mod tests {
 
         let correct_vec = vec![false, false, false];
 
-        stats.increment_errors(0);
-        stats.increment_errors(1);
+        stats.increment_errors(0, None);
+        stats.increment_errors(1, None);
         stats.increment_combo(1);
         stats.increment_combo(2);
-        stats.increment_errors(2);
+        stats.increment_errors(2, None);
         stats.increment_combo(1);
         stats.increment_combo(2);
 
@@ -306,4 +494,104 @@ mod tests {
         let error_margin = f64::EPSILON;
         assert!((v1 - v2).abs() < error_margin);
     }
+
+    #[test]
+    fn test_mistypes_are_remembered_until_reset() {
+        let mut stats = Stats::new(false);
+        let mistype = Mistype {
+            expected: 'e',
+            previous: Some('h'),
+            typed: 'r',
+        };
+
+        stats.increment_errors(0, Some(mistype.clone()));
+        stats.increment_errors(1, None);
+        assert_eq!(stats.get_mistypes(), [mistype]);
+        assert_eq!(stats.errors, 2);
+
+        stats.reset();
+        assert!(stats.get_mistypes().is_empty());
+    }
+
+    #[test]
+    fn test_raw_and_net_wpm() {
+        let mut stats = Stats::new(false);
+        // 15 characters in 2 seconds, 5 of them deleted again so only 10 made it in
+        for _ in 0..15 {
+            stats.record_typed_char_at(1000);
+        }
+        stats.update_wpm_at(2000, 2, &["There's", "a", "time"]);
+        assert_eq!(stats.wpm, 60);
+        assert_eq!(stats.get_raw_wpm(), 90);
+        assert_eq!(stats.get_net_wpm(), 90);
+
+        // Each mistake left behind costs 30 wpm over 2 seconds
+        stats.update_uncorrected_errors(2);
+        assert_eq!(stats.get_net_wpm(), 30);
+        stats.update_uncorrected_errors(5);
+        assert_eq!(stats.get_net_wpm(), 0);
+    }
+
+    #[test]
+    fn test_highest_combo_is_per_race() {
+        let mut stats = Stats::new(false);
+        for letter in 0..5 {
+            stats.increment_combo(letter);
+        }
+        assert_eq!(stats.get_highest_combo(), 5);
+
+        // The same Stats is reused for the next race
+        stats.reset();
+        assert_eq!(stats.get_highest_combo(), 0);
+        stats.increment_combo(0);
+        stats.increment_combo(1);
+        stats.increment_errors(2, None);
+        stats.increment_combo(2);
+        assert_eq!(stats.get_highest_combo(), 2);
+    }
+
+    #[test]
+    fn test_consistency() {
+        let mut stats = Stats::new(false);
+        for millis in [0, 100, 200, 1000, 1100, 1200, 2000] {
+            stats.record_typed_char_at(millis);
+        }
+        // The third second hasn't finished yet
+        stats.update_wpm_at(1500, 0, &[]);
+        assert_eq!(stats.get_consistency(), None);
+        stats.update_wpm_at(2500, 0, &[]);
+        assert_eq_float(stats.get_consistency().unwrap(), 100.0);
+
+        // Slowing down to 3, 3, 2 then 1 characters a second
+        stats.record_typed_char_at(2000);
+        stats.record_typed_char_at(3000);
+        stats.update_wpm_at(4000, 0, &[]);
+        let consistency = stats.get_consistency().unwrap();
+        assert!((consistency - 63.1).abs() < 0.1, "{}", consistency);
+
+        stats.reset();
+        assert_eq!(stats.get_consistency(), None);
+        assert_eq!(stats.get_raw_wpm(), 0);
+    }
+
+    #[test]
+    fn test_wpm_is_sampled_every_second() {
+        let words = ["There's", "a", "time", "when", "the", "operation"];
+        let mut stats = Stats::new(false);
+        stats.update_wpm_at(500, 1, &words);
+        assert!(stats.get_wpm_samples().is_empty());
+
+        // 10 characters in 1 second
+        stats.update_wpm_at(1000, 2, &words);
+        assert_eq!(stats.get_wpm_samples(), [120]);
+        stats.update_wpm_at(1900, 2, &words);
+        assert_eq!(stats.get_wpm_samples(), [120]);
+
+        // Nothing was typed for a while, every second missed gets the same sample
+        stats.update_wpm_at(4000, 2, &words);
+        assert_eq!(stats.get_wpm_samples(), [120, 30, 30, 30]);
+
+        stats.reset();
+        assert!(stats.get_wpm_samples().is_empty());
+    }
 }
@@ -0,0 +1,69 @@
+use rand::Rng;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::db;
+
+/// Tests in the same run are told apart by a counter, and parallel runs by the process id
+static NEXT_DIR: AtomicUsize = AtomicUsize::new(0);
+
+/// A fresh directory for a test, removed again when it's dropped so nothing is left behind even
+/// when an assert fails
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!(
+            "typeracer-{}-{}-{}-{}",
+            name,
+            std::process::id(),
+            NEXT_DIR.fetch_add(1, Ordering::Relaxed),
+            rand::thread_rng().gen_range(0..10_000_000)
+        ));
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        self.path.join(path)
+    }
+
+    /// Write a file inside the dir, creating any dirs it's in
+    pub fn write(&self, path: &str, contents: &str) {
+        let path = self.path.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// A stats db with every migration run, in a dir of its own
+pub struct TempDb {
+    dir: TempDir,
+}
+
+impl TempDb {
+    pub fn new(name: &str) -> TempDb {
+        let dir = TempDir::new(name);
+        db::create_database(&dir.join("stats.db")).unwrap();
+        TempDb { dir }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join("stats.db")
+    }
+}